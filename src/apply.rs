@@ -0,0 +1,59 @@
+use crate::error::{Error, Result};
+use crate::repo_project_selector::ProjectInfo;
+use colored::*;
+use git2::Repository;
+use rayon::prelude::*;
+
+/// Applies `action` to the git repos of all projects in parallel and prints
+/// its output per project, followed by `summary` and the number of projects
+/// it was done in, e.g. "Deleted tag v1 in 3/5 projects". Projects for which
+/// `action` returns None are skipped, e.g. because they lack the tag; they
+/// are listed with the `skipped` note if given. Fails if `action` failed in
+/// any of the projects.
+pub fn apply_to_repos<F>(
+    list_of_projects: &[ProjectInfo],
+    summary: &str,
+    skipped: Option<&str>,
+    action: F,
+) -> Result<()>
+where
+    F: Fn(&mut Repository) -> Result<Option<String>> + Sync,
+{
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| action(&mut Repository::open(&project.absolute_path)?))
+        .collect();
+
+    let mut done = 0;
+    let mut failed = 0;
+    for (project, result) in list_of_projects.iter().zip(results) {
+        match result {
+            Ok(Some(output)) => {
+                done += 1;
+                print!("{}: {}", project.path.green(), output);
+            }
+            Ok(None) => {
+                if let Some(note) = skipped {
+                    println!("{}: {}", project.path, note);
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                println!("{}: {}", project.path.red(), e);
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "{} in {}/{} projects",
+        summary,
+        done,
+        list_of_projects.len()
+    );
+
+    match failed {
+        0 => Ok(()),
+        _ => Err(Error::Command(format!("failed in {} projects", failed))),
+    }
+}
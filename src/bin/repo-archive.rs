@@ -0,0 +1,172 @@
+extern crate clap;
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, ValueEnum};
+use colored::*;
+use git2::Repository;
+use rayon::prelude::*;
+use repo_utils::disk_usage::human_readable;
+use repo_utils::exec;
+use repo_utils::generate;
+use repo_utils::paths;
+use repo_utils::progress::Progress;
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use std::env;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/// Archive the checked out state of repos managed by git-repo along with a manifest pinning
+/// their revisions, e.g. to ship a workspace to an airgapped site,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// what is archived per project
+    #[arg(short, long, value_enum, default_value_t = Format::Bundle)]
+    format: Format,
+
+    /// folder the archives and the snapshot manifest are written to, created if missing
+    #[arg(value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
+    output: PathBuf,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    /// a git bundle with the history of HEAD, like `git bundle create`
+    Bundle,
+    /// a tarball of the files of HEAD, like `git archive`
+    Tar,
+}
+
+impl Format {
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::Bundle => "bundle",
+            Format::Tar => "tar",
+        }
+    }
+}
+
+/// name of the manifest written along with the archives
+const SNAPSHOT_FILE: &str = "snapshot.xml";
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, workspace, list_of_projects) = args.selection.select()?;
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    fs::create_dir_all(&args.output)
+        .with_context(|| format!("Unable to create {:?}", args.output))?;
+    // git runs in the projects, so relative paths wouldn't work
+    args.output = paths::canonicalize(&args.output)?;
+    let snapshot = workspace.manifest()?.snapshot(&list_of_projects)?;
+    let snapshot_file = args.output.join(SNAPSHOT_FILE);
+    snapshot.write_xml(
+        &mut File::create(&snapshot_file)
+            .with_context(|| format!("Unable to create {:?}", snapshot_file))?,
+    )?;
+
+    let progress_bar = config.ui.progress_bar(list_of_projects.len())?;
+    archive_all(&list_of_projects, &args, &progress_bar)
+}
+
+fn archive_all(
+    list_of_projects: &[ProjectInfo],
+    args: &Args,
+    progress: &dyn Progress,
+) -> Result<()> {
+    progress.start(list_of_projects.len() as u64);
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| {
+            let result = archive(project, args.format, &args.output);
+            progress.inc();
+            result
+        })
+        .collect();
+    progress.finish();
+
+    let mut archived = 0;
+    let mut total_size = 0;
+    let mut failed = 0;
+    for (project, result) in list_of_projects.iter().zip(results) {
+        match result {
+            Ok(size) => {
+                archived += 1;
+                total_size += size;
+            }
+            Err(e) => {
+                failed += 1;
+                println!("{}: {:#}", project.path.red(), e);
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "Archived {}/{} projects to {}, {} in total",
+        archived,
+        list_of_projects.len(),
+        args.output.display(),
+        human_readable(total_size)
+    );
+
+    if failed > 0 {
+        bail!("archiving failed in {} projects", failed);
+    }
+    Ok(())
+}
+
+/// writes the archive of the project to `<output>/<path>.<extension>`,
+/// returns its size
+fn archive(project: &ProjectInfo, format: Format, output: &Path) -> Result<u64> {
+    let head = Repository::open(&project.absolute_path)?
+        .head()?
+        .peel_to_commit()?
+        .id()
+        .to_string();
+
+    let file = output.join(format!("{}.{}", project.path, format.extension()));
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file_arg = file.to_string_lossy().to_string();
+    let prefix = format!("--prefix={}/", project.path);
+    let argv: Vec<String> = match format {
+        Format::Bundle => vec!["git", "bundle", "create", "-q", &file_arg, "HEAD"],
+        // the mtime of all entries is the commit time, so the tarball is reproducible
+        Format::Tar => vec![
+            "git",
+            "archive",
+            "--format=tar",
+            &prefix,
+            "-o",
+            &file_arg,
+            &head,
+        ],
+    }
+    .into_iter()
+    .map(str::to_string)
+    .collect();
+
+    let result = exec::direct_command(&argv)?
+        .current_dir(&project.absolute_path)
+        .output()?;
+    if !result.status.success() {
+        bail!(
+            "{} failed: {}",
+            argv.join(" "),
+            String::from_utf8_lossy(&result.stderr).trim()
+        );
+    }
+    Ok(fs::metadata(&file)?.len())
+}
@@ -0,0 +1,216 @@
+extern crate clap;
+
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use colored::*;
+use git2::{DiffOptions, Repository};
+use rayon::prelude::*;
+use repo_utils::date::parse_date;
+use repo_utils::generate;
+use repo_utils::progress::Progress;
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::io;
+
+/// Commits, insertions and deletions per author summed up over all repos managed by git-repo,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// only count commits authored at or after the given date, e.g. 2024-01-31
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    since: Option<i64>,
+
+    /// only count commits authored before the given date, e.g. 2024-12-31
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    until: Option<i64>,
+
+    /// how the statistics are printed
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+/// the commits of an author within a single project
+#[derive(Default)]
+struct Contribution {
+    commits: usize,
+    insertions: usize,
+    deletions: usize,
+}
+
+/// the contributions of an author summed up over all projects
+#[derive(Serialize, Default)]
+struct AuthorStats {
+    author: String,
+    commits: usize,
+    insertions: usize,
+    deletions: usize,
+    active_projects: usize,
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, _, list_of_projects) = args.selection.select()?;
+
+    // keep stdout clean for csv and json
+    eprintln!("Selected {} projects", list_of_projects.len());
+
+    let progress_bar = config.ui.progress_bar(list_of_projects.len())?;
+    let stats = collect(&list_of_projects, &args, &progress_bar);
+
+    match args.format {
+        Format::Table => print_table(&stats),
+        Format::Csv => print_csv(&stats),
+        Format::Json => {
+            serde_json::to_writer_pretty(io::stdout(), &stats)?;
+            println!();
+        }
+    }
+    Ok(())
+}
+
+fn collect(
+    list_of_projects: &[ProjectInfo],
+    args: &Args,
+    progress: &dyn Progress,
+) -> Vec<AuthorStats> {
+    progress.start(list_of_projects.len() as u64);
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| {
+            let contributions = contributions_of(project, args);
+            progress.inc();
+            contributions
+        })
+        .collect();
+    progress.finish();
+
+    let mut stats: BTreeMap<String, (AuthorStats, BTreeSet<&str>)> = BTreeMap::new();
+    for (project, result) in list_of_projects.iter().zip(results) {
+        let contributions = match result {
+            Ok(contributions) => contributions,
+            Err(e) => {
+                eprintln!("{}: {}", project.path.red(), e);
+                continue;
+            }
+        };
+        for (author, contribution) in contributions {
+            let (author_stats, projects) = stats.entry(author.clone()).or_default();
+            author_stats.author = author;
+            author_stats.commits += contribution.commits;
+            author_stats.insertions += contribution.insertions;
+            author_stats.deletions += contribution.deletions;
+            projects.insert(&project.path);
+        }
+    }
+
+    let mut stats: Vec<AuthorStats> = stats
+        .into_values()
+        .map(|(mut author_stats, projects)| {
+            author_stats.active_projects = projects.len();
+            author_stats
+        })
+        .collect();
+    stats.sort_by(|a, b| b.commits.cmp(&a.commits).then(a.author.cmp(&b.author)));
+    stats
+}
+
+/// walks the history of HEAD, merges aren't counted
+fn contributions_of(
+    project: &ProjectInfo,
+    args: &Args,
+) -> repo_utils::Result<BTreeMap<String, Contribution>> {
+    let repo = Repository::open(&project.absolute_path)?;
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+
+    let mut contributions: BTreeMap<String, Contribution> = BTreeMap::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        let time = commit.author().when().seconds();
+        if args.until.is_some_and(|until| time >= until) {
+            continue;
+        }
+        // no break, rebased or cherry-picked commits can be authored
+        // before the commits they are based on
+        if args.since.is_some_and(|since| time < since) {
+            continue;
+        }
+        if commit.parent_count() > 1 {
+            continue;
+        }
+
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let diff = repo.diff_tree_to_tree(
+            parent_tree.as_ref(),
+            Some(&commit.tree()?),
+            Some(&mut DiffOptions::new()),
+        )?;
+        let diff_stats = diff.stats()?;
+
+        let author = commit.author();
+        let name = format!(
+            "{} <{}>",
+            author.name().unwrap_or_default(),
+            author.email().unwrap_or_default()
+        );
+        let contribution = contributions.entry(name).or_default();
+        contribution.commits += 1;
+        contribution.insertions += diff_stats.insertions();
+        contribution.deletions += diff_stats.deletions();
+    }
+    Ok(contributions)
+}
+
+fn print_table(stats: &[AuthorStats]) {
+    println!(
+        "{:>8} {:>10} {:>10} {:>9}  author",
+        "commits", "insertions", "deletions", "projects"
+    );
+    for s in stats {
+        println!(
+            "{:>8} {:>10} {:>10} {:>9}  {}",
+            s.commits, s.insertions, s.deletions, s.active_projects, s.author
+        );
+    }
+    println!();
+    println!(
+        "{} commits by {} authors",
+        stats.iter().map(|s| s.commits).sum::<usize>(),
+        stats.len()
+    );
+}
+
+fn print_csv(stats: &[AuthorStats]) {
+    println!("author,commits,insertions,deletions,active_projects");
+    for s in stats {
+        println!(
+            "\"{}\",{},{},{},{}",
+            s.author.replace('"', "\"\""),
+            s.commits,
+            s.insertions,
+            s.deletions,
+            s.active_projects
+        );
+    }
+}
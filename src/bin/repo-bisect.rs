@@ -0,0 +1,285 @@
+extern crate clap;
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Parser;
+use colored::*;
+use git2::{Oid, Repository, Sort};
+use repo_utils::date::parse_date;
+use repo_utils::exec;
+use repo_utils::generate;
+use repo_utils::git_ops;
+use repo_utils::repo_project_selector::{parse_manifest, Manifest, ProjectInfo};
+use repo_utils::selection::SelectionArgs;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Find the project and commit which introduced a regression between two manifest snapshots of a
+/// workspace managed by git-repo, see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// manifest snapshot the test command passes with, e.g. written by repo-manifest-snapshot
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath, required_unless_present = "good_date")]
+    good: Option<PathBuf>,
+
+    /// manifest snapshot the test command fails with
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath, required_unless_present = "bad_date")]
+    bad: Option<PathBuf>,
+
+    /// instead of --good: the projects as of the start of the given day (UTC), e.g. 2024-01-31
+    #[arg(long, value_name = "DATE", value_parser = parse_date, conflicts_with = "good")]
+    good_date: Option<i64>,
+
+    /// instead of --bad: the projects as of the start of the given day (UTC), e.g. 2024-02-29
+    #[arg(long, value_name = "DATE", value_parser = parse_date, conflicts_with = "bad")]
+    bad_date: Option<i64>,
+
+    /// shell command run in the root folder of the workspace, exit code 0 means good
+    #[arg(required = true, trailing_var_arg = true)]
+    command: Vec<String>,
+}
+
+/// the state of the workspace at one end of the bisection
+enum Snapshot {
+    Manifest(Box<Manifest>),
+    /// seconds since the epoch, the projects are taken from the
+    /// history of their checked out commit
+    Date(i64),
+}
+
+impl Snapshot {
+    fn new(manifest: &Option<PathBuf>, date: Option<i64>) -> Result<Snapshot> {
+        match (manifest, date) {
+            (Some(path), _) => Ok(Snapshot::Manifest(Box::new(
+                parse_manifest(path).with_context(|| format!("Failed to parse {:?}", path))?,
+            ))),
+            (None, Some(date)) => Ok(Snapshot::Date(date)),
+            (None, None) => bail!("no manifest or date given"),
+        }
+    }
+
+    /// the commit of the project in this snapshot, None if it isn't part of it
+    fn commit_of(&self, repo: &Repository, info: &ProjectInfo) -> Result<Option<Oid>> {
+        match self {
+            Snapshot::Manifest(manifest) => {
+                let revision = match manifest
+                    .find_project(&info.path)
+                    .and_then(|project| manifest.revision_of(project))
+                {
+                    Some(revision) => revision,
+                    None => return Ok(None),
+                };
+                let commit = repo
+                    .revparse_single(revision)
+                    .and_then(|o| o.peel_to_commit())
+                    .with_context(|| {
+                        format!("{}: unknown revision {}, fetch first", info.path, revision)
+                    })?;
+                Ok(Some(commit.id()))
+            }
+            Snapshot::Date(date) => {
+                let mut revwalk = repo.revwalk()?;
+                revwalk.set_sorting(Sort::TIME)?;
+                revwalk.push_head()?;
+                for oid in revwalk {
+                    let oid = oid?;
+                    if repo.find_commit(oid)?.committer().when().seconds() < *date {
+                        return Ok(Some(oid));
+                    }
+                }
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// a project whose revision differs between the good and the bad manifest
+struct Candidate {
+    info: ProjectInfo,
+    good: Oid,
+    bad: Oid,
+    /// what was checked out before bisecting, restored afterwards
+    original: String,
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (_, workspace, list_of_projects) = args.selection.select()?;
+
+    let good = Snapshot::new(&args.good, args.good_date)?;
+    let bad = Snapshot::new(&args.bad, args.bad_date)?;
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    let candidates = find_candidates(list_of_projects, &good, &bad)?;
+    if candidates.is_empty() {
+        bail!("the good and the bad snapshot don't differ in any of the selected projects");
+    }
+    println!(
+        "Bisecting {} projects differing between the good and the bad snapshot",
+        candidates.len()
+    );
+
+    let command = args.command.join(" ");
+    let result = bisect(&candidates, &command, workspace.root_folder());
+
+    for candidate in &candidates {
+        if let Err(e) = checkout(candidate, &candidate.original) {
+            println!(
+                "{}: failed to restore {}: {}",
+                candidate.info.path.red(),
+                candidate.original,
+                e
+            );
+        }
+    }
+    result
+}
+
+fn find_candidates(
+    list_of_projects: Vec<ProjectInfo>,
+    good: &Snapshot,
+    bad: &Snapshot,
+) -> Result<Vec<Candidate>> {
+    let mut candidates = vec![];
+    for info in list_of_projects {
+        let repo = Repository::open(&info.absolute_path)?;
+        let (good, bad) = match (good.commit_of(&repo, &info)?, bad.commit_of(&repo, &info)?) {
+            (Some(good), Some(bad)) if good != bad => (good, bad),
+            _ => continue,
+        };
+
+        if !repo.statuses(None)?.is_empty() {
+            bail!(
+                "{} has uncommited changes, bisecting would lose them",
+                info.path
+            );
+        }
+        let head = repo.head()?;
+        let original = match head.is_branch() {
+            true => head.shorthand().map(|s| s.to_string()),
+            false => head.target().map(|oid| oid.to_string()),
+        }
+        .ok_or_else(|| anyhow!("{}: invalid HEAD", info.path))?;
+
+        candidates.push(Candidate {
+            good,
+            bad,
+            original,
+            info,
+        });
+    }
+    Ok(candidates)
+}
+
+/// narrows down the project first: the first n candidates are moved to
+/// their bad revision, the others are kept at the good one. Then the
+/// commits of the culprit are bisected.
+fn bisect(candidates: &[Candidate], command: &str, root_folder: &Path) -> Result<()> {
+    let mut step = 0;
+    let mut test = |description: String| -> Result<bool> {
+        step += 1;
+        println!("{}", format!("Step {}: {}", step, description).bold());
+        let status = exec::shell_command(command)
+            .current_dir(root_folder)
+            .status()
+            .with_context(|| format!("Failed to run {:?}", command))?;
+        let good = status.success();
+        println!("→ {}", if good { "good".green() } else { "bad".red() });
+        Ok(good)
+    };
+    let move_to = |bad_count: usize| -> Result<()> {
+        for (index, candidate) in candidates.iter().enumerate() {
+            let oid = if index < bad_count {
+                candidate.bad
+            } else {
+                candidate.good
+            };
+            checkout(candidate, &oid.to_string())?;
+        }
+        Ok(())
+    };
+
+    move_to(0)?;
+    if !test("all projects at the good revision".to_string())? {
+        bail!("the test command fails with the good snapshot already");
+    }
+    move_to(candidates.len())?;
+    if test("all projects at the bad revision".to_string())? {
+        bail!("the test command passes with the bad snapshot");
+    }
+
+    let (mut good, mut bad) = (0, candidates.len());
+    while bad - good > 1 {
+        let mid = (good + bad) / 2;
+        move_to(mid)?;
+        match test(format!("{} projects at the bad revision", mid))? {
+            true => good = mid,
+            false => bad = mid,
+        }
+    }
+    let culprit = &candidates[bad - 1];
+    println!();
+    println!("First bad project: {}", culprit.info.path.red());
+
+    // all projects before the culprit stay at their bad revision
+    move_to(bad - 1)?;
+    let repo = Repository::open(&culprit.info.absolute_path)?;
+    let commits = commits_between(&repo, culprit.good, culprit.bad)?;
+    if commits.is_empty() {
+        // the bad revision is an ancestor of the good one, the regression
+        // is the rollback as a whole
+        println!(
+            "First bad change: {} moved back from {:.7} to {:.7}, dropping {} commits",
+            culprit.info.path.red(),
+            culprit.good,
+            culprit.bad,
+            commits_between(&repo, culprit.bad, culprit.good)?.len()
+        );
+        return Ok(());
+    }
+
+    let (mut good, mut bad) = (0, commits.len());
+    while bad - good > 1 {
+        let mid = (good + bad) / 2;
+        let commit = commits[mid - 1];
+        checkout(culprit, &commit.to_string())?;
+        match test(format!("{} at {:.7}", culprit.info.path, commit))? {
+            true => good = mid,
+            false => bad = mid,
+        }
+    }
+
+    let commit = repo.find_commit(commits[bad - 1])?;
+    println!();
+    println!(
+        "First bad commit: {} {:.7} {}",
+        culprit.info.path.red(),
+        commit.id(),
+        commit.summary().unwrap_or_default()
+    );
+    Ok(())
+}
+
+/// commits reachable from `bad` but not from `good`, oldest first
+fn commits_between(repo: &Repository, good: Oid, bad: Oid) -> Result<Vec<Oid>> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+    revwalk.push(bad)?;
+    revwalk.hide(good)?;
+    Ok(revwalk.collect::<Result<Vec<_>, _>>()?)
+}
+
+fn checkout(candidate: &Candidate, revision: &str) -> Result<()> {
+    let repo = Repository::open(&candidate.info.absolute_path)?;
+    git_ops::checkout(&repo, revision)
+        .with_context(|| format!("{}: failed to check out {}", candidate.info.path, revision))?;
+    Ok(())
+}
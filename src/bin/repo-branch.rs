@@ -0,0 +1,157 @@
+extern crate clap;
+
+use anyhow::Result;
+use clap::{ArgGroup, Parser};
+use colored::*;
+use git2::{BranchType, Repository};
+use rayon::prelude::*;
+use repo_utils::apply::apply_to_repos;
+use repo_utils::generate;
+use repo_utils::git_ops::{self, BranchInfo, Divergence};
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use std::env;
+
+/// List the local branches of repos managed by git-repo compared with their upstream and the
+/// last "repo sync", or create, delete or check out a branch in all of them,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+#[command(group(ArgGroup::new("action").args(["create", "delete", "checkout"])))]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Verbose output, e.g. also list projects without local branches
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
+
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    /// create the branch at the last "repo sync" state and check it out, like "repo start"
+    #[arg(long, value_name = "BRANCH")]
+    create: Option<String>,
+
+    /// delete the branch, like "repo abandon"; projects having it checked out are detached at the last "repo sync" state
+    #[arg(long, value_name = "BRANCH")]
+    delete: Option<String>,
+
+    /// check out the branch in all projects having it
+    #[arg(long, value_name = "BRANCH")]
+    checkout: Option<String>,
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, workspace, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+
+    let sync_branch_name = workspace.sync_branch_name()?;
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    let skipped = args.verbose.then_some("no such branch");
+    if let Some(name) = &args.create {
+        apply_to_repos(
+            &list_of_projects,
+            &format!("Created branch {}", name),
+            None,
+            |repo| git_ops::start_branch(repo, name, &sync_branch_name).map(Some),
+        )
+        .map_err(Into::into)
+    } else if let Some(name) = &args.delete {
+        apply_to_repos(
+            &list_of_projects,
+            &format!("Deleted branch {}", name),
+            skipped,
+            |repo| match has_branch(repo, name) {
+                true => git_ops::abandon_branch(repo, name, &sync_branch_name).map(Some),
+                false => Ok(None),
+            },
+        )
+        .map_err(Into::into)
+    } else if let Some(name) = &args.checkout {
+        apply_to_repos(
+            &list_of_projects,
+            &format!("Checked out branch {}", name),
+            skipped,
+            |repo| match has_branch(repo, name) {
+                true => git_ops::checkout(repo, name).map(Some),
+                false => Ok(None),
+            },
+        )
+        .map_err(Into::into)
+    } else {
+        list(&list_of_projects, &sync_branch_name, args.verbose)
+    }
+}
+
+fn has_branch(repo: &Repository, name: &str) -> bool {
+    repo.find_branch(name, BranchType::Local).is_ok()
+}
+
+fn list(list_of_projects: &[ProjectInfo], sync_branch_name: &str, verbose: bool) -> Result<()> {
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| {
+            let repo = Repository::open(&project.absolute_path)?;
+            git_ops::branches(&repo, sync_branch_name)
+        })
+        .collect();
+
+    for (project, result) in list_of_projects.iter().zip(results) {
+        let branches = match result {
+            Ok(branches) => branches,
+            Err(e) => {
+                println!("{}: {}", project.path.red(), e);
+                continue;
+            }
+        };
+        if branches.is_empty() {
+            if verbose {
+                println!("{}: no local branches", project.path.green());
+            }
+            continue;
+        }
+
+        println!("{}", project.path.yellow());
+        let width = branches.iter().map(|b| b.name.len()).max().unwrap_or(0);
+        for branch in &branches {
+            print_branch(branch, width);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_branch(branch: &BranchInfo, width: usize) {
+    let marker = match branch.is_head {
+        true => "*",
+        false => " ",
+    };
+    let mut line = format!("  {} {:width$}", marker, branch.name, width = width);
+    for divergence in branch.upstream.iter().chain(&branch.sync) {
+        line += &format!(" [{}]", describe(divergence));
+    }
+    println!("{}", line.trim_end());
+}
+
+fn describe(divergence: &Divergence) -> String {
+    let mut counts = vec![];
+    if divergence.ahead > 0 {
+        counts.push(format!("ahead {}", divergence.ahead));
+    }
+    if divergence.behind > 0 {
+        counts.push(format!("behind {}", divergence.behind));
+    }
+    match counts.is_empty() {
+        true => format!("{}: up to date", divergence.name),
+        false => format!("{}: {}", divergence.name, counts.join(", ")),
+    }
+}
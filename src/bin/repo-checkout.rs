@@ -0,0 +1,166 @@
+extern crate clap;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::*;
+use git2::{ErrorCode, Repository};
+use rayon::prelude::*;
+use repo_utils::generate;
+use repo_utils::git_ops;
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use std::env;
+
+/// Check out a branch, tag or revision in repos managed by git-repo,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Verbose output, e.g. also list projects lacking the revision
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
+
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    /// detach HEAD at the commit, even if REVISION names a branch
+    #[arg(short, long, default_value = "false")]
+    detach: bool,
+
+    /// projects lacking the revision get a branch of that name, created at the last "repo sync" state
+    #[arg(long, default_value = "false", conflicts_with = "detach")]
+    create_missing: bool,
+
+    /// check out projects with local changes as well, the changes are kept (unless they conflict)
+    #[arg(short, long, default_value = "false")]
+    force: bool,
+
+    /// branch, tag or revision expression, e.g. "topic", "v1.0" or "HEAD~2"
+    revision: String,
+}
+
+/// what happened in a project
+enum Outcome {
+    CheckedOut(String),
+    /// the revision was missing, a branch was created at the sync branch
+    Created(String),
+    Missing,
+    /// left alone due to local changes
+    Dirty,
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, workspace, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+
+    let sync_branch_name = workspace.sync_branch_name()?;
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| checkout(project, &sync_branch_name, &args))
+        .collect();
+
+    let mut checked_out = 0;
+    let mut created = vec![];
+    let mut missing = vec![];
+    let mut dirty = vec![];
+    let mut failed = 0;
+    for (project, result) in list_of_projects.iter().zip(results) {
+        match result {
+            Ok(Outcome::CheckedOut(output)) => {
+                checked_out += 1;
+                print!("{}: {}", project.path.green(), output);
+            }
+            Ok(Outcome::Created(output)) => {
+                created.push(project.path.as_str());
+                print!("{}: created, {}", project.path.yellow(), output);
+            }
+            Ok(Outcome::Missing) => {
+                missing.push(project.path.as_str());
+                if args.verbose {
+                    println!("{}: {} not found", project.path.yellow(), args.revision);
+                }
+            }
+            Ok(Outcome::Dirty) => {
+                dirty.push(project.path.as_str());
+                println!(
+                    "{}: has local changes, skipped (use --force to check out anyway)",
+                    project.path.red()
+                );
+            }
+            Err(e) => {
+                failed += 1;
+                println!("{}: {}", project.path.red(), e);
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "Checked out {} in {}/{} projects",
+        args.revision,
+        checked_out,
+        list_of_projects.len()
+    );
+    for (label, paths) in [
+        (format!("Created at {} in", sync_branch_name), &created),
+        ("Not found in".to_string(), &missing),
+        ("Skipped due to local changes".to_string(), &dirty),
+    ] {
+        if !paths.is_empty() {
+            println!("{} {} projects", label, paths.len());
+        }
+    }
+
+    if failed > 0 || !dirty.is_empty() {
+        bail!("failed in {} projects", failed + dirty.len());
+    }
+    Ok(())
+}
+
+fn checkout(
+    project: &ProjectInfo,
+    sync_branch_name: &str,
+    args: &Args,
+) -> repo_utils::Result<Outcome> {
+    let repo = Repository::open(&project.absolute_path)?;
+
+    let commit = match repo.revparse_single(&args.revision) {
+        Ok(object) => Some(object.peel_to_commit()?),
+        Err(e) if e.code() == ErrorCode::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    if commit.is_none() && !args.create_missing {
+        return Ok(Outcome::Missing);
+    }
+    if !args.force && !git_ops::status(&repo)?.is_empty() {
+        return Ok(Outcome::Dirty);
+    }
+
+    match commit {
+        Some(commit) if args.detach => Ok(Outcome::CheckedOut(git_ops::checkout(
+            &repo,
+            &commit.id().to_string(),
+        )?)),
+        Some(_) => Ok(Outcome::CheckedOut(git_ops::checkout(
+            &repo,
+            &args.revision,
+        )?)),
+        None => Ok(Outcome::Created(git_ops::start_branch(
+            &repo,
+            &args.revision,
+            sync_branch_name,
+        )?)),
+    }
+}
@@ -0,0 +1,113 @@
+extern crate clap;
+
+use anyhow::Result;
+use clap::Parser;
+use colored::*;
+use git2::Repository;
+use rayon::prelude::*;
+use repo_utils::generate;
+use repo_utils::git_ops::{self, CherryCommit};
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use std::env;
+
+/// List the commits on HEAD of repos managed by git-repo which are not part of the last
+/// "repo sync", telling local work from commits already merged upstream under a different sha,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Verbose output, e.g. also list projects without any commits on top of upstream
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
+
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    /// compare with the given revision instead of the sync branch of the last "repo sync", e.g. "origin/main"
+    #[arg(short, long, value_name = "REVISION")]
+    upstream: Option<String>,
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, workspace, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+
+    let upstream = match &args.upstream {
+        Some(upstream) => upstream.clone(),
+        None => format!("refs/remotes/{}", workspace.sync_branch_name()?),
+    };
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| {
+            let repo = Repository::open(&project.absolute_path)?;
+            git_ops::cherry(&repo, &upstream)
+        })
+        .collect();
+
+    print_commits(&list_of_projects, results, args.verbose);
+    Ok(())
+}
+
+fn print_commits(
+    list_of_projects: &[ProjectInfo],
+    results: Vec<repo_utils::Result<Vec<CherryCommit>>>,
+    verbose: bool,
+) {
+    let mut local = 0;
+    let mut upstreamed = 0;
+    let mut projects_with_local = 0;
+    for (project, result) in list_of_projects.iter().zip(results) {
+        let commits = match result {
+            Ok(commits) => commits,
+            Err(e) => {
+                println!("{}: {}", project.path.red(), e);
+                continue;
+            }
+        };
+        if commits.is_empty() {
+            if verbose {
+                println!("{}: up to date", project.path.green());
+            }
+            continue;
+        }
+
+        println!("{}", project.path.yellow());
+        for commit in &commits {
+            // same markers as `git cherry -v`
+            let line = format!("{:.7} {}", commit.oid, commit.summary);
+            match commit.upstreamed {
+                true => println!("  - {}", line.dimmed()),
+                false => println!("  + {}", line),
+            }
+        }
+
+        let count = commits.iter().filter(|c| !c.upstreamed).count();
+        if count > 0 {
+            projects_with_local += 1;
+        }
+        local += count;
+        upstreamed += commits.len() - count;
+    }
+
+    println!();
+    println!(
+        "{} local commits in {}/{} projects, {} already upstream",
+        local,
+        projects_with_local,
+        list_of_projects.len(),
+        upstreamed
+    );
+}
@@ -0,0 +1,219 @@
+extern crate clap;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use colored::*;
+use git2::{Oid, Repository};
+use rayon::prelude::*;
+use repo_utils::generate;
+use repo_utils::paths;
+use repo_utils::repo_project_selector::{parse_manifest, Manifest, ProjectInfo};
+use repo_utils::selection::SelectionArgs;
+use repo_utils::workspace::Workspace;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Compare the checked out revisions of repos managed by git-repo with another manifest or
+/// workspace, i.e. show what would change when syncing to it, see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Verbose output, e.g. also list projects at the same revision
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
+
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    /// manifest file or root folder of another workspace to compare with
+    #[arg(value_hint = clap::ValueHint::AnyPath)]
+    other: PathBuf,
+}
+
+/// how a project differs from the other manifest or workspace
+#[derive(Debug, PartialEq, Eq)]
+enum Difference {
+    Same,
+    /// the revisions differ, `commits` are the numbers of commits only
+    /// checked out in this workspace and only in the other one, if the
+    /// other revision is known locally
+    Differs {
+        local: Oid,
+        other: String,
+        commits: Option<(usize, usize)>,
+    },
+    /// only defined in the other manifest
+    Added,
+    /// not defined in the other manifest
+    Removed,
+}
+
+/// where the revisions to compare with come from
+enum Other {
+    /// revisions are resolved in the local repos of the projects
+    Manifest(Manifest),
+    /// revisions are the HEADs of the projects in that workspace
+    Workspace(Workspace),
+}
+
+impl Other {
+    fn open(path: &Path) -> Result<Other> {
+        match path.is_dir() {
+            true => Ok(Other::Workspace(Workspace::discover(path)?)),
+            false => Ok(Other::Manifest(
+                parse_manifest(path).with_context(|| format!("Failed to parse {:?}", path))?,
+            )),
+        }
+    }
+
+    fn manifest(&self) -> Result<&Manifest> {
+        match self {
+            Other::Manifest(manifest) => Ok(manifest),
+            Other::Workspace(workspace) => Ok(workspace.manifest()?),
+        }
+    }
+
+    /// the revision of the project, as revision expression and resolved
+    /// within the local repo, if possible
+    fn revision_of(&self, path: &str, repo: &Repository) -> Result<(String, Option<Oid>)> {
+        match self {
+            Other::Manifest(manifest) => {
+                let project = manifest.find_project(path).expect("defined in manifest");
+                let revision = manifest.revision_of(project).unwrap_or("HEAD");
+                let oid = resolve(repo, revision, manifest.remote_of(project));
+                Ok((revision.to_string(), oid))
+            }
+            Other::Workspace(workspace) => {
+                let other_repo = Repository::open(paths::join(workspace.root_folder(), path))?;
+                let oid = other_repo.head()?.peel_to_commit()?.id();
+                let known = repo.find_commit(oid).is_ok();
+                Ok((oid.to_string(), Some(oid).filter(|_| known)))
+            }
+        }
+    }
+}
+
+/// resolves a manifest revision the way repo does after fetching:
+/// branches are looked up as remote branches of the project's remote
+fn resolve(repo: &Repository, revision: &str, remote: Option<&str>) -> Option<Oid> {
+    let branch = revision.strip_prefix("refs/heads/").unwrap_or(revision);
+    remote
+        .and_then(|remote| {
+            repo.revparse_single(&format!("refs/remotes/{}/{}", remote, branch))
+                .ok()
+        })
+        .or_else(|| repo.revparse_single(revision).ok())
+        .and_then(|object| object.peel_to_commit().ok())
+        .map(|commit| commit.id())
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, workspace, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+
+    let other = Other::open(&args.other)?;
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    let other_manifest = other.manifest()?;
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| diff(project, &other, other_manifest))
+        .collect();
+
+    let mut differences: Vec<(String, Result<Difference>)> = list_of_projects
+        .iter()
+        .map(|project| project.path.clone())
+        .zip(results)
+        .collect();
+    let manifest = workspace.manifest()?;
+    differences.extend(
+        other_manifest
+            .projects
+            .iter()
+            .filter(|p| !manifest.contains_project(&p.path))
+            .map(|p| (p.path.clone(), Ok(Difference::Added))),
+    );
+
+    print_differences(&differences, args.verbose);
+    Ok(())
+}
+
+fn diff(project: &ProjectInfo, other: &Other, other_manifest: &Manifest) -> Result<Difference> {
+    if !other_manifest.contains_project(&project.path) {
+        return Ok(Difference::Removed);
+    }
+
+    let repo = Repository::open(&project.absolute_path)?;
+    let local = repo.head()?.peel_to_commit()?.id();
+    let (revision, other_oid) = other.revision_of(&project.path, &repo)?;
+
+    match other_oid {
+        Some(oid) if oid == local => Ok(Difference::Same),
+        Some(oid) => Ok(Difference::Differs {
+            local,
+            other: revision,
+            commits: Some(repo.graph_ahead_behind(local, oid)?),
+        }),
+        None => Ok(Difference::Differs {
+            local,
+            other: revision,
+            commits: None,
+        }),
+    }
+}
+
+fn print_differences(differences: &[(String, Result<Difference>)], verbose: bool) {
+    let (mut same, mut differing, mut added, mut removed, mut failed) = (0, 0, 0, 0, 0);
+    for (path, difference) in differences {
+        match difference {
+            Ok(Difference::Same) => {
+                same += 1;
+                if verbose {
+                    println!("{}: same revision", path.green());
+                }
+            }
+            Ok(Difference::Differs {
+                local,
+                other,
+                commits,
+            }) => {
+                differing += 1;
+                let commits = match commits {
+                    Some((ahead, behind)) => {
+                        format!("{} commits to drop, {} commits to get", ahead, behind)
+                    }
+                    None => "revision not available locally, fetch first".to_string(),
+                };
+                println!("{}: {:.7} -> {} ({})", path.yellow(), local, other, commits);
+            }
+            Ok(Difference::Added) => {
+                added += 1;
+                println!("{}: added", path.blue());
+            }
+            Ok(Difference::Removed) => {
+                removed += 1;
+                println!("{}: removed", path.red());
+            }
+            Err(e) => {
+                failed += 1;
+                println!("{}: {:#}", path.red(), e);
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "{} same, {} differing, {} added, {} removed, {} failed",
+        same, differing, added, removed, failed
+    );
+}
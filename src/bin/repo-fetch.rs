@@ -0,0 +1,128 @@
+extern crate clap;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::*;
+use git2::Repository;
+use rayon::prelude::*;
+use repo_utils::generate;
+use repo_utils::git_ops::{self, FetchStats};
+use repo_utils::progress::Progress;
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use std::env;
+use std::time::Instant;
+
+/// Fetch all remotes of repos managed by git-repo in parallel,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Verbose output, e.g. also list the refs updated by the fetch
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
+
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    /// fetch the given remote(s) only, defaults to all remotes of a project
+    #[arg(short, long, value_name = "REMOTE")]
+    remote: Option<Vec<String>>,
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, _, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    let progress_bar = config.ui.progress_bar(list_of_projects.len())?;
+    fetch(&list_of_projects, &args, &progress_bar)
+}
+
+fn fetch(list_of_projects: &[ProjectInfo], args: &Args, progress: &dyn Progress) -> Result<()> {
+    let timestamp_before_fetching = Instant::now();
+    let remotes = args.remote.clone().unwrap_or_default();
+
+    progress.start(list_of_projects.len() as u64);
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| {
+            let result = Repository::open(&project.absolute_path)
+                .map_err(Into::into)
+                .and_then(|repo| git_ops::fetch_remotes(&repo, &remotes));
+            progress.inc();
+            result
+        })
+        .collect();
+    progress.finish();
+
+    let mut total = FetchStats::default();
+    let mut failed = 0;
+    for (project, result) in list_of_projects.iter().zip(results) {
+        let fetched = match result {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                failed += 1;
+                println!("{}: {}", project.path.red(), e);
+                continue;
+            }
+        };
+        for stats in fetched {
+            total.received_objects += stats.received_objects;
+            total.received_bytes += stats.received_bytes;
+            total
+                .updated_refs
+                .extend(stats.updated_refs.iter().cloned());
+            print_stats(project, &stats, args.verbose);
+        }
+    }
+
+    println!();
+    println!(
+        "Fetched {} projects in {}s: {} refs updated, {} objects ({} bytes) received",
+        list_of_projects.len() - failed,
+        timestamp_before_fetching.elapsed().as_secs(),
+        total.updated_refs.len(),
+        total.received_objects,
+        total.received_bytes
+    );
+
+    if failed > 0 {
+        bail!("fetching failed in {} projects", failed);
+    }
+    Ok(())
+}
+
+fn print_stats(project: &ProjectInfo, stats: &FetchStats, verbose: bool) {
+    if stats.updated_refs.is_empty() {
+        if verbose {
+            println!("{}: {}: up to date", project.path.green(), stats.remote);
+        }
+        return;
+    }
+
+    println!(
+        "{}: {}: {} refs updated, received {}/{} objects ({} bytes)",
+        project.path.yellow(),
+        stats.remote,
+        stats.updated_refs.len(),
+        stats.received_objects,
+        stats.total_objects,
+        stats.received_bytes
+    );
+    if verbose {
+        for updated_ref in &stats.updated_refs {
+            println!("    {}", updated_ref);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+extern crate clap;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::*;
+use git2::{Oid, Repository};
+use rayon::prelude::*;
+use repo_utils::generate;
+use repo_utils::git_ops;
+use repo_utils::progress::Progress;
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use std::env;
+use std::time::Instant;
+
+/// Find the repos managed by git-repo which contain a commit, given by its sha or Change-Id,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Verbose output, e.g. also print the full commit message
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
+
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    /// sha (abbreviated or full) or Change-Id of the commit
+    commit: String,
+}
+
+/// a commit found in a project
+struct Found {
+    oid: Oid,
+    message: String,
+    refs: Vec<String>,
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, _, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    let progress_bar = config.ui.progress_bar(list_of_projects.len())?;
+    find(&list_of_projects, &args, &progress_bar)
+}
+
+fn find(list_of_projects: &[ProjectInfo], args: &Args, progress: &dyn Progress) -> Result<()> {
+    let timestamp_before_searching = Instant::now();
+
+    progress.start(list_of_projects.len() as u64);
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| {
+            let found = find_in(project, &args.commit);
+            progress.inc();
+            found
+        })
+        .collect();
+    progress.finish();
+
+    let mut found_in = 0;
+    for (project, result) in list_of_projects.iter().zip(results) {
+        match result {
+            Ok(Some(found)) => {
+                found_in += 1;
+                print_found(project, &found, args.verbose);
+            }
+            Ok(None) => {}
+            Err(e) => println!("{}: {}", project.path.red(), e),
+        }
+    }
+
+    println!();
+    println!(
+        "Searched {} projects in {}s: {} found in {} of them",
+        list_of_projects.len(),
+        timestamp_before_searching.elapsed().as_secs(),
+        args.commit,
+        found_in
+    );
+
+    if found_in == 0 {
+        bail!("{} not found", args.commit);
+    }
+    Ok(())
+}
+
+fn find_in(project: &ProjectInfo, query: &str) -> repo_utils::Result<Option<Found>> {
+    let repo = Repository::open(&project.absolute_path)?;
+    let oid = match git_ops::find_commit(&repo, query)? {
+        Some(oid) => oid,
+        None => return Ok(None),
+    };
+    let commit = repo.find_commit(oid)?;
+    Ok(Some(Found {
+        oid,
+        message: commit.message().unwrap_or_default().to_string(),
+        refs: git_ops::refs_containing(&repo, oid)?,
+    }))
+}
+
+fn print_found(project: &ProjectInfo, found: &Found, verbose: bool) {
+    let summary = found.message.lines().next().unwrap_or_default();
+    println!(
+        "{} ({}): {:.7} {}",
+        project.path.green(),
+        project.name,
+        found.oid,
+        summary
+    );
+    match found.refs.is_empty() {
+        true => println!("    not contained in any branch or tag"),
+        false => println!("    contained in {}", found.refs.join(", ")),
+    }
+    if verbose {
+        for line in found.message.lines().skip(1) {
+            println!("    | {}", line);
+        }
+    }
+}
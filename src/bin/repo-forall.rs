@@ -1,41 +1,54 @@
 extern crate clap;
 
 use anyhow::{anyhow, bail, Error, Result};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use colored::*;
 use crossbeam::channel::unbounded;
-use indicatif::{ParallelProgressIterator, ProgressBar, ProgressStyle};
+use git2::Repository;
+use indicatif::ParallelProgressIterator;
 use rayon::prelude::*;
-use repo_utils::repo_project_selector::{find_repo_root_folder, select_projects};
+use regex::Regex;
+use repo_utils::config::Config;
+use repo_utils::exec;
+use repo_utils::generate;
+use repo_utils::git_ops;
+use repo_utils::git_status::query_git_statuses;
+use repo_utils::json_lines;
+use repo_utils::pager;
+use repo_utils::paths;
+use repo_utils::progress::NoProgress;
+use repo_utils::repo_project_selector::{Manifest, ProjectInfo};
+use repo_utils::selection::SelectionArgs;
+use repo_utils::workspace::Workspace;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::fmt;
+use std::fs;
 use std::io;
-use std::io::Write;
-use std::process::{Command, Output};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Output};
 use std::str;
-use std::time::Instant;
+use std::thread;
+use std::time::{Duration, Instant};
 
 /// Execute commands on git repositories managed by repo,
 /// see https://github.com/elektronenhirn/repo-utils
 #[derive(Parser, Debug)]
 #[command(author, version, long_about = None)]
 struct Args {
-    /// change working directory (mostly useful for testing)
-    #[arg(short = 'C', long, value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
-    cwd: Option<std::path::PathBuf>,
-
-    /// ignore projects which are not defined in the given manifest file(s)
-    #[arg(short, long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
-    manifest: Option<Vec<std::path::PathBuf>>,
-
-    /// ignore projects which are not part of the given group(s)
-    #[arg(short, long)]
-    group: Option<Vec<String>>,
+    #[command(flatten)]
+    selection: SelectionArgs,
 
     /// Verbose output
-    #[arg(short, long, default_value = "false")]
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
     verbose: bool,
 
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
     /// Stop running commands for anymore projects whenever one failed
     #[arg(short, long, default_value = "false")]
     fail_fast: bool,
@@ -44,95 +57,280 @@ struct Args {
     #[arg(short, long, default_value = "false")]
     print_project_path: bool,
 
+    /// Execute a built-in git operation instead of spawning a shell,
+    /// COMMAND is passed as arguments to the operation
+    #[arg(short, long, value_enum, value_name = "OP")]
+    builtin: Option<BuiltinOp>,
+
+    /// Only run the command in projects with uncommited changes or local commits
+    #[arg(long, default_value = "false", conflicts_with = "only_clean")]
+    only_dirty: bool,
+
+    /// Only run the command in projects without uncommited changes or local commits
+    #[arg(long, default_value = "false")]
+    only_clean: bool,
+
+    /// Retry failed executions up to N times before counting them as failed [default: 0]
+    #[arg(long, value_name = "N")]
+    retries: Option<u32>,
+
+    /// Seconds to wait before retrying, doubled for every further attempt up to 10 minutes [default: 0]
+    #[arg(long, value_name = "SECONDS")]
+    retry_backoff: Option<u64>,
+
+    /// Only run the command in projects which failed during the previous run
+    #[arg(long, default_value = "false")]
+    resume: bool,
+
+    /// Additionally write the output of each project into <DIR>/<project path>.log
+    #[arg(long, value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
+    log_dir: Option<PathBuf>,
+
+    /// Kill the command and count it as failed if it runs longer than SECONDS
+    #[arg(long, value_name = "SECONDS", conflicts_with = "builtin")]
+    timeout: Option<u64>,
+
+    /// Read stdin once and deliver it to the stdin of each command
+    #[arg(long, default_value = "false", conflicts_with = "builtin")]
+    stdin: bool,
+
+    /// Suppress the output of the commands, print a table of exit codes instead
+    #[arg(short, long, default_value = "false")]
+    quiet: bool,
+
+    /// Print the results as json array of {path, exit_code, stdout, stderr, duration_ms}
+    #[arg(long, default_value = "false", conflicts_with = "quiet")]
+    json: bool,
+
+    /// Print the result of each project as soon as it is known, as one json object per line
+    #[arg(long, default_value = "false", conflicts_with_all = ["quiet", "json"])]
+    jsonl: bool,
+
+    /// Print identical outputs only once, headed by the list of projects which produced it
+    #[arg(long, default_value = "false", conflicts_with_all = ["quiet", "json", "jsonl"])]
+    dedupe_output: bool,
+
+    /// Execute the given shell script in each project instead of an inline command,
+    /// COMMAND is passed as arguments to the script
+    #[arg(short, long, visible_alias = "file", value_name = "FILE", value_hint = clap::ValueHint::FilePath, conflicts_with = "builtin")]
+    script: Option<PathBuf>,
+
+    /// Run the command in one project after another, in manifest order, with live output
+    #[arg(long, default_value = "false", conflicts_with_all = ["quiet", "json", "jsonl", "dedupe_output"])]
+    serial: bool,
+
+    /// Execute COMMAND directly instead of passing it to the platform's shell
+    #[arg(long, default_value = "false", conflicts_with_all = ["builtin", "script"])]
+    no_shell: bool,
+
+    /// Count an execution as failed if its output doesn't match REGEX
+    #[arg(long, value_name = "REGEX")]
+    expect: Option<Regex>,
+
+    /// Count an execution as failed if its output matches REGEX
+    #[arg(long, value_name = "REGEX")]
+    fail_on: Option<Regex>,
+
+    /// Don't pipe the output through $PAGER, even if it is printed to a terminal
+    #[arg(long, default_value = "false")]
+    no_pager: bool,
+
     command: Vec<String>,
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+impl Args {
+    /// stdout is reserved for json, everything else goes to stderr
+    fn prints_json(&self) -> bool {
+        self.json || self.jsonl
+    }
+}
+
+/// Common git operations implemented on top of git2
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum BuiltinOp {
+    /// like `git status --short`
+    Status,
+    /// like `git fetch [REMOTE]...`, fetches all remotes by default
+    Fetch,
+    /// like `git branch -D BRANCH...`
+    BranchDelete,
+    /// like `git checkout REVISION`
+    Checkout,
+}
+
+impl BuiltinOp {
+    fn execute(&self, repo_path: &Path, args: &[String]) -> Result<Output> {
+        let repo = Repository::open(repo_path)?;
+        let stdout = match self {
+            BuiltinOp::Status => git_ops::status(&repo)?,
+            BuiltinOp::Fetch => git_ops::fetch(&repo, args)?,
+            BuiltinOp::BranchDelete => git_ops::delete_branches(&repo, args)?,
+            BuiltinOp::Checkout => match args {
+                [revision] => git_ops::checkout(&repo, revision)?,
+                _ => bail!("checkout expects exactly one revision"),
+            },
+        };
+        Ok(Output {
+            status: ExitStatus::default(),
+            stdout: stdout.into_bytes(),
+            stderr: vec![],
+        })
+    }
+}
 
-    if let Some(cwd) = args.cwd {
-        env::set_current_dir(cwd)?;
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
     }
+    let mut args = Args::parse();
 
-    if args.command.is_empty() {
+    if args.command.is_empty() && args.builtin.is_none() && args.script.is_none() {
         bail!("No command given")
     }
 
-    let list_of_projects = select_projects(false, args.group, args.manifest)?;
+    let (mut config, workspace, mut list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+    if args.resume {
+        let unfinished = load_unfinished_projects(&workspace)?;
+        list_of_projects.retain(|p| unfinished.contains(&p.path));
+    }
+    if args.only_dirty || args.only_clean {
+        list_of_projects = filter_by_status(&workspace, list_of_projects, args.only_dirty)?;
+    }
+
+    // json lines are meant to be consumed while they are printed
+    let paging = pager::start(args.no_pager || args.jsonl)?;
+    if paging.is_some() {
+        // the progress bar would garble the screen of the pager
+        config.ui.progress = false;
+    }
 
-    println!("Selected {} projects", list_of_projects.len());
+    // keep stdout clean for the json output
+    match args.prints_json() {
+        true => eprintln!("Selected {} projects", list_of_projects.len()),
+        false => println!("Selected {} projects", list_of_projects.len()),
+    }
 
-    forall(
-        list_of_projects,
-        args.command.join(" "),
-        args.fail_fast,
-        args.print_project_path,
-    )
+    forall(&workspace, list_of_projects, &args, &config)
 }
 
 fn forall(
-    list_of_projects: Vec<String>,
-    command: String,
-    fail_fast: bool,
-    print_project_path: bool,
+    workspace: &Workspace,
+    list_of_projects: Vec<ProjectInfo>,
+    args: &Args,
+    config: &Config,
 ) -> Result<()> {
     let timestamp_before_exec = Instant::now();
 
-    let repo_root_folder = find_repo_root_folder()?;
+    let cmd_context = CmdContext::from(args, config, workspace)?;
 
     // Create a simple streaming channel
     let (tx, rx) = unbounded();
-    let progress_bar = ProgressBar::new(list_of_projects.len() as u64).with_style(
-        ProgressStyle::default_bar()
-            .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7}")?,
-    );
+    let progress_bar = config.ui.progress_bar(list_of_projects.len())?;
 
-    let _ = list_of_projects
-        .par_iter()
-        .progress_with(progress_bar)
-        .try_for_each(|path| {
-            let output = CommandOutput::new(
-                path,
-                Command::new("sh")
-                    .current_dir(&repo_root_folder.join(path))
-                    .arg("-c")
-                    .arg(&command)
-                    .env("REPO_PATH", path)
-                    .output()
-                    .map_err(Error::msg),
-            );
+    let run_in_project = |project: &ProjectInfo| {
+        let path = &project.path;
+        if args.serial && args.print_project_path {
+            println!("\n{}:", path.green());
+        }
 
-            let result: Result<()> = match fail_fast && !&output.success() {
-                true => Err(anyhow!("")),
-                false => Ok(()),
-            };
+        let output = execute_with_retries(project, &cmd_context);
+        if args.serial {
+            output.print_failure();
+        }
+        if let Some(log_dir) = &args.log_dir {
+            if let Err(e) = output.write_log(log_dir) {
+                eprintln!("{}: failed to write log: {}", path.red(), e);
+            }
+        }
+
+        if args.jsonl {
+            if let Err(e) = json_lines::print(&JsonRecord::from(&output)) {
+                eprintln!("{}: failed to print result: {}", path.red(), e);
+            }
+        }
+
+        let result: Result<()> = match args.fail_fast && !&output.success() {
+            true => Err(anyhow!("")),
+            false => Ok(()),
+        };
 
-            let _ = tx.send(output);
+        let _ = tx.send(output);
 
-            result
-        });
+        result
+    };
+
+    let _ = match args.serial {
+        true => {
+            let mut in_manifest_order = list_of_projects.clone();
+            in_manifest_order.sort_by_key(|p| {
+                let position = cmd_context.manifest.position_of(&p.path);
+                position.unwrap_or(usize::MAX)
+            });
+            in_manifest_order.iter().try_for_each(run_in_project)
+        }
+        false => list_of_projects
+            .par_iter()
+            .progress_with(progress_bar)
+            .try_for_each(run_in_project),
+    };
 
     let (mut succeeded, mut failed) = (0, 0);
+    let mut executed = HashSet::new();
+    let mut unfinished = vec![];
+    let outputs: Vec<CommandOutput> = rx.try_iter().collect();
 
-    rx.try_iter().for_each(|output| {
+    outputs.iter().for_each(|output| {
         match output.success() {
             true => succeeded += 1,
-            false => failed += 1,
+            false => {
+                failed += 1;
+                unfinished.push(output.path.clone());
+            }
         }
-        output.print(print_project_path);
+        if !args.quiet && !args.prints_json() && !args.dedupe_output && !args.serial {
+            output.print(args.print_project_path);
+        }
+        executed.insert(output.path.clone());
     });
 
-    println!();
+    if args.json {
+        print_json(&outputs)?;
+    } else if args.quiet {
+        print_results_table(&outputs);
+    } else if args.dedupe_output {
+        print_deduplicated(&outputs);
+    }
+    if !args.prints_json() {
+        print_exit_code_distribution(&outputs);
+    }
+
+    // with --fail-fast some projects might not have been visited at all
+    unfinished.extend(
+        list_of_projects
+            .iter()
+            .filter(|p| !executed.contains(&p.path))
+            .map(|p| p.path.clone()),
+    );
+    store_unfinished_projects(workspace, &unfinished)?;
+
+    if !args.prints_json() {
+        println!();
+    }
 
     match failed {
         0 => {
-            println!(
+            let summary = format!(
                 "Finished in {}s: {}/{} executions succeeded, {} failed",
                 timestamp_before_exec.elapsed().as_secs(),
                 succeeded,
                 list_of_projects.len(),
                 failed
             );
+            match args.prints_json() {
+                true => eprintln!("{}", summary),
+                false => println!("{}", summary),
+            }
             Ok(())
         }
         _ => Err(anyhow!(
@@ -145,31 +343,406 @@ fn forall(
     }
 }
 
+/// the wait between retries stops doubling here
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(600);
+
+/// Executes the command in the given project, failed executions are
+/// retried as often as requested by --retries
+fn execute_with_retries(project: &ProjectInfo, cmd_context: &CmdContext) -> CommandOutput {
+    let args = cmd_context.args;
+    let mut backoff = cmd_context.retry_backoff;
+    let mut attempt = 0;
+    let started = Instant::now();
+    loop {
+        let result = execute(project, cmd_context);
+        let mut output = CommandOutput::new(&project.path, result, started.elapsed());
+        output.check_output(args.expect.as_ref(), args.fail_on.as_ref());
+        if output.success() || attempt >= cmd_context.retries {
+            return output;
+        }
+        attempt += 1;
+        thread::sleep(backoff);
+        backoff = backoff.saturating_mul(2).min(MAX_RETRY_BACKOFF);
+    }
+}
+
+fn execute(project: &ProjectInfo, cmd_context: &CmdContext) -> Result<Output> {
+    let args = cmd_context.args;
+    let repo_path = &project.absolute_path;
+    match args.builtin {
+        Some(op) => op.execute(repo_path, &args.command),
+        None => {
+            let mut cmd = match &cmd_context.script {
+                Some(script) => {
+                    let mut cmd = exec::script_command(script);
+                    cmd.args(&args.command);
+                    cmd
+                }
+                None if args.no_shell => exec::direct_command(&args.command)?,
+                None => exec::shell_command(&cmd_context.command),
+            };
+            cmd.current_dir(repo_path)
+                .envs(cmd_context.project_env(project));
+            exec::output_with_timeout(
+                cmd,
+                cmd_context.stdin.as_deref(),
+                cmd_context.timeout,
+                args.serial,
+            )
+            .map_err(anyhow::Error::from)
+        }
+    }
+}
+
+// this class bundles all the objects required for executing the command,
+// so we can pass them more conveniently into all the methods
+struct CmdContext<'a> {
+    args: &'a Args,
+    command: String,
+    script: Option<PathBuf>,
+    manifest: &'a Manifest,
+    sync_branch_name: Option<String>,
+    stdin: Option<Vec<u8>>,
+    retries: u32,
+    retry_backoff: Duration,
+    timeout: Option<Duration>,
+}
+
+impl<'a> CmdContext<'a> {
+    pub fn from(
+        args: &'a Args,
+        config: &Config,
+        workspace: &'a Workspace,
+    ) -> Result<CmdContext<'a>> {
+        let manifest = workspace.manifest()?;
+        // the script is executed from within each project, so it
+        // has to be addressed by an absolute path
+        let script = match &args.script {
+            Some(script) => Some(
+                paths::canonicalize(script)
+                    .map_err(|e| anyhow!("Unable to find script {:?}: {}", script, e))?,
+            ),
+            None => None,
+        };
+        let stdin = match args.stdin {
+            true => {
+                let mut data = vec![];
+                io::stdin().read_to_end(&mut data)?;
+                Some(data)
+            }
+            false => None,
+        };
+
+        Ok(CmdContext {
+            args,
+            command: args.command.join(" "),
+            script,
+            manifest,
+            // only needed for REPO_LREV, so don't insist on it
+            sync_branch_name: workspace.sync_branch_name().ok(),
+            stdin,
+            retries: args.retries.unwrap_or(config.forall.retries),
+            retry_backoff: Duration::from_secs(
+                args.retry_backoff.unwrap_or(config.forall.retry_backoff),
+            ),
+            timeout: args
+                .timeout
+                .or(config.forall.timeout)
+                .map(Duration::from_secs),
+        })
+    }
+
+    /// Environment variables describing the project, named
+    /// like the ones provided by `repo forall`
+    pub fn project_env(&self, project: &ProjectInfo) -> Vec<(String, String)> {
+        let or_empty = |value: &Option<String>| value.clone().unwrap_or_default();
+        let mut env = vec![
+            ("REPO_PATH".to_string(), project.path.clone()),
+            ("REPO_PROJECT".to_string(), project.name.clone()),
+            ("REPO_REMOTE".to_string(), or_empty(&project.remote)),
+            (
+                "REPO_LREV".to_string(),
+                self.sync_revision(project).unwrap_or_default(),
+            ),
+            ("REPO_RREV".to_string(), or_empty(&project.revision)),
+            ("REPO_UPSTREAM".to_string(), or_empty(&project.upstream)),
+            (
+                "REPO_DEST_BRANCH".to_string(),
+                or_empty(&project.dest_branch),
+            ),
+            ("REPO_GROUPS".to_string(), project.groups.join(",")),
+        ];
+
+        // like "repo forall", annotations are exported as REPO__<name>
+        for (name, value) in &project.annotations {
+            env.push((format!("REPO__{}", name), value.clone()));
+        }
+        env
+    }
+
+    /// sha of the revision checked out by the last "repo sync"
+    fn sync_revision(&self, project: &ProjectInfo) -> Option<String> {
+        let sync_branch_name = self.sync_branch_name.as_ref()?;
+        let repo = Repository::open(&project.absolute_path).ok()?;
+        let reference = repo
+            .find_branch(sync_branch_name, git2::BranchType::Remote)
+            .ok()?;
+        let commit = reference.get().peel_to_commit().ok()?;
+        Some(commit.id().to_string())
+    }
+}
+
+/// Prints one line per project: path, exit code and the first line of stderr
+fn print_results_table(outputs: &[CommandOutput]) {
+    let mut outputs: Vec<&CommandOutput> = outputs.iter().collect();
+    outputs.sort_by(|a, b| a.path.cmp(&b.path));
+    let width = outputs.iter().map(|o| o.path.len()).max().unwrap_or(0);
+
+    for output in outputs {
+        let exit_code = match output.exit_code() {
+            Some(code) => code.to_string(),
+            None => "-".to_string(),
+        };
+        let line = format!(
+            "{:width$}  {:>4}  {}",
+            output.path,
+            exit_code,
+            output
+                .rejected
+                .clone()
+                .unwrap_or_else(|| output.first_line_of_stderr()),
+            width = width
+        );
+        match output.success() {
+            true => println!("{}", line),
+            false => println!("{}", line.red()),
+        }
+    }
+}
+
+/// Prints how many projects exited with which exit code, along
+/// with a few example projects for each of them
+fn print_exit_code_distribution(outputs: &[CommandOutput]) {
+    const MAX_EXAMPLES: usize = 3;
+
+    let mut paths_by_exit_code: BTreeMap<Option<i32>, Vec<&str>> = BTreeMap::new();
+    for output in outputs {
+        paths_by_exit_code
+            .entry(output.exit_code())
+            .or_default()
+            .push(&output.path);
+    }
+
+    println!("\nExit codes:");
+    for (exit_code, mut paths) in paths_by_exit_code {
+        paths.sort_unstable();
+        let mut examples = paths
+            .iter()
+            .take(MAX_EXAMPLES)
+            .copied()
+            .collect::<Vec<_>>()
+            .join(", ");
+        if paths.len() > MAX_EXAMPLES {
+            examples += ", ...";
+        }
+        let exit_code = match exit_code {
+            Some(code) => code.to_string(),
+            None => "-".to_string(),
+        };
+        println!("{:>6}: {:>5} x ({})", exit_code, paths.len(), examples);
+    }
+}
+
+/// Prints the output of projects with identical output (and exit code)
+/// only once, headed by the list of those projects
+fn print_deduplicated(outputs: &[CommandOutput]) {
+    let mut outputs: Vec<&CommandOutput> = outputs.iter().collect();
+    outputs.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut groups: Vec<(Vec<u8>, Vec<&CommandOutput>)> = vec![];
+    let mut index_of_group: HashMap<_, usize> = HashMap::new();
+    for output in outputs {
+        let text = output.combined_output();
+        let key = (output.exit_code(), text.clone());
+        match index_of_group.get(&key) {
+            Some(&index) => groups[index].1.push(output),
+            None => {
+                index_of_group.insert(key, groups.len());
+                groups.push((text, vec![output]));
+            }
+        }
+    }
+
+    for (text, members) in groups {
+        let paths: Vec<&str> = members.iter().map(|o| o.path.as_str()).collect();
+        let header = format!("{} ({}):", paths.join(", "), paths.len());
+        match members[0].success() {
+            true => println!("\n{}", header.green()),
+            false => println!("\n{}", header.red()),
+        }
+        let _ = io::stdout().write_all(&text);
+    }
+}
+
+/// Prints all results as a json array to stdout
+fn print_json(outputs: &[CommandOutput]) -> Result<()> {
+    let records: Vec<JsonRecord> = outputs.iter().map(JsonRecord::from).collect();
+    serde_json::to_writer_pretty(io::stdout(), &records)?;
+    println!();
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    path: &'a str,
+    exit_code: Option<i32>,
+    stdout: String,
+    stderr: String,
+    duration_ms: u128,
+}
+
+impl<'a> From<&'a CommandOutput> for JsonRecord<'a> {
+    fn from(output: &'a CommandOutput) -> Self {
+        let (stdout, stderr) = match &output.output {
+            Ok(o) => (
+                String::from_utf8_lossy(&o.stdout).into_owned(),
+                String::from_utf8_lossy(&o.stderr).into_owned(),
+            ),
+            Err(e) => (String::new(), e.to_string()),
+        };
+        JsonRecord {
+            path: &output.path,
+            exit_code: output.exit_code(),
+            stdout,
+            stderr,
+            duration_ms: output.duration.as_millis(),
+        }
+    }
+}
+
+/// Projects which failed (or were skipped due to --fail-fast) are
+/// recorded in this file, so they can be picked up with --resume
+fn unfinished_projects_file(workspace: &Workspace) -> PathBuf {
+    workspace.repo_folder().join("repo-forall.failed")
+}
+
+fn store_unfinished_projects(workspace: &Workspace, paths: &[String]) -> Result<()> {
+    let file = unfinished_projects_file(workspace);
+    match paths.is_empty() {
+        true if file.exists() => fs::remove_file(file).map_err(Error::msg),
+        true => Ok(()),
+        false => fs::write(file, paths.join("\n") + "\n").map_err(Error::msg),
+    }
+}
+
+fn load_unfinished_projects(workspace: &Workspace) -> Result<Vec<String>> {
+    let file = unfinished_projects_file(workspace);
+    let content = fs::read_to_string(&file)
+        .map_err(|e| anyhow!("Nothing to resume, unable to read {:?}: {}", file, e))?;
+    Ok(content.lines().map(|l| l.to_string()).collect())
+}
+
+/// Keeps only those projects which are dirty (or clean if `dirty` is false)
+fn filter_by_status(
+    workspace: &Workspace,
+    list_of_projects: Vec<ProjectInfo>,
+    dirty: bool,
+) -> Result<Vec<ProjectInfo>> {
+    let sync_branch_name = workspace.sync_branch_name()?;
+
+    let statuses = query_git_statuses(
+        workspace.root_folder(),
+        &list_of_projects,
+        &sync_branch_name,
+        &NoProgress,
+    )?;
+
+    Ok(list_of_projects
+        .into_iter()
+        .zip(statuses)
+        .filter(|(_, status)| status.is_dirty() == dirty)
+        .map(|(project, _)| project)
+        .collect())
+}
+
 struct CommandOutput {
     pub path: String,
     pub output: Result<Output>,
+    pub duration: Duration,
+    /// reason why the output was rejected by --expect or --fail-on
+    pub rejected: Option<String>,
 }
 
 impl CommandOutput {
-    pub fn new(path: &str, output: Result<Output>) -> Self {
+    pub fn new(path: &str, output: Result<Output>, duration: Duration) -> Self {
         CommandOutput {
             path: path.to_string(),
             output,
+            duration,
+            rejected: None,
         }
     }
 
     pub fn success(&self) -> bool {
         match &self.output {
-            Ok(output) => output.status.success(),
+            Ok(output) => output.status.success() && self.rejected.is_none(),
             Err(_) => false,
         }
     }
 
+    /// Rejects the output if it doesn't match `expect` or if it matches `fail_on`
+    pub fn check_output(&mut self, expect: Option<&Regex>, fail_on: Option<&Regex>) {
+        let text = String::from_utf8_lossy(&self.combined_output()).into_owned();
+        if let Some(expect) = expect {
+            if !expect.is_match(&text) {
+                self.rejected = Some(format!("output doesn't match '{}'", expect));
+            }
+        }
+        if let Some(fail_on) = fail_on {
+            if fail_on.is_match(&text) {
+                self.rejected = Some(format!("output matches '{}'", fail_on));
+            }
+        }
+    }
+
+    /// None if the command couldn't be executed or was killed by a signal
+    pub fn exit_code(&self) -> Option<i32> {
+        match &self.output {
+            Ok(output) => output.status.code(),
+            Err(_) => None,
+        }
+    }
+
+    pub fn first_line_of_stderr(&self) -> String {
+        let stderr = match &self.output {
+            Ok(output) => String::from_utf8_lossy(&output.stderr).into_owned(),
+            Err(e) => e.to_string(),
+        };
+        stderr.lines().next().unwrap_or_default().to_string()
+    }
+
+    /// stdout followed by stderr, or the error message if
+    /// the command couldn't be executed at all
+    pub fn combined_output(&self) -> Vec<u8> {
+        match &self.output {
+            Ok(output) => [&output.stdout[..], &output.stderr[..]].concat(),
+            Err(e) => format!("{}\n", e).into_bytes(),
+        }
+    }
+
     pub fn print(&self, print_project_path: bool) {
         if print_project_path {
             println!("\n{}:", self.path.green());
         }
 
+        self.print_failure();
+        if let Ok(output) = &self.output {
+            let _ = io::stdout().write_all(&output.stdout);
+            let _ = io::stdout().write_all(&output.stderr);
+        }
+    }
+
+    pub fn print_failure(&self) {
         if !self.success() {
             eprintln!(
                 "{}: {}:",
@@ -177,10 +750,30 @@ impl CommandOutput {
                 "failed to execute given command".red()
             );
         }
-        if let Ok(output) = &self.output {
-            let _ = io::stdout().write_all(&output.stdout);
-            let _ = io::stdout().write_all(&output.stderr);
+        if let Err(e) = &self.output {
+            eprintln!("{}", e);
+        }
+        if let Some(reason) = &self.rejected {
+            eprintln!("{}", reason);
+        }
+    }
+
+    /// Writes stdout and stderr into <log_dir>/<project path>.log
+    pub fn write_log(&self, log_dir: &Path) -> Result<()> {
+        let log_file = log_dir.join(format!("{}.log", self.path));
+        if let Some(parent) = log_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = fs::File::create(&log_file)
+            .map_err(|e| anyhow!("Unable to create {:?}: {}", log_file, e))?;
+        match &self.output {
+            Ok(output) => {
+                file.write_all(&output.stdout)?;
+                file.write_all(&output.stderr)?;
+            }
+            Err(e) => writeln!(file, "Failed to execute given command: {}", e)?,
         }
+        Ok(())
     }
 }
 
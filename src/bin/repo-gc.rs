@@ -0,0 +1,185 @@
+extern crate clap;
+
+use anyhow::{bail, Result};
+use clap::{Parser, ValueEnum};
+use colored::*;
+use git2::Repository;
+use rayon::prelude::*;
+use repo_utils::disk_usage::{disk_usage, human_readable};
+use repo_utils::exec;
+use repo_utils::generate;
+use repo_utils::progress::Progress;
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use std::env;
+use std::time::Instant;
+
+/// Run git maintenance tasks on repos managed by git-repo in parallel,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Verbose output, e.g. also list the output of git
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
+
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    /// the maintenance task(s) to run, in the given order
+    #[arg(short, long, value_enum, default_values_t = [Task::Gc])]
+    task: Vec<Task>,
+
+    /// number of loose objects triggering "gc", like git's gc.auto
+    #[arg(long, value_name = "N")]
+    auto_limit: Option<u32>,
+
+    /// number of packs triggering "gc" to consolidate them, like git's gc.autoPackLimit
+    #[arg(long, value_name = "N")]
+    pack_limit: Option<u32>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Task {
+    /// like `git gc --auto`, does nothing unless the limits are exceeded
+    Gc,
+    /// like `git prune`, removes unreachable loose objects
+    Prune,
+    /// like `git commit-graph write --reachable`, speeds up history traversal
+    CommitGraph,
+    /// like `git repack -d -l`, packs loose objects
+    Repack,
+}
+
+impl Task {
+    fn git_args(&self) -> &'static [&'static str] {
+        match self {
+            Task::Gc => &["gc", "--auto", "--quiet"],
+            Task::Prune => &["prune"],
+            Task::CommitGraph => &["commit-graph", "write", "--reachable"],
+            Task::Repack => &["repack", "-d", "-l", "-q"],
+        }
+    }
+}
+
+/// the result of maintaining a project
+struct Report {
+    size_before: u64,
+    size_after: u64,
+    output: String,
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, _, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    let progress_bar = config.ui.progress_bar(list_of_projects.len())?;
+    gc(&list_of_projects, &args, &progress_bar)
+}
+
+fn gc(list_of_projects: &[ProjectInfo], args: &Args, progress: &dyn Progress) -> Result<()> {
+    let timestamp_before_gc = Instant::now();
+
+    progress.start(list_of_projects.len() as u64);
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| {
+            let report = maintain(project, args);
+            progress.inc();
+            report
+        })
+        .collect();
+    progress.finish();
+
+    let mut total_before = 0;
+    let mut total_after = 0;
+    let mut failed = 0;
+    for (project, result) in list_of_projects.iter().zip(results) {
+        let report = match result {
+            Ok(report) => report,
+            Err(e) => {
+                failed += 1;
+                println!("{}: {:#}", project.path.red(), e);
+                continue;
+            }
+        };
+        total_before += report.size_before;
+        total_after += report.size_after;
+
+        if args.verbose || report.size_before != report.size_after {
+            println!(
+                "{}: {} -> {} ({} reclaimed)",
+                project.path.green(),
+                human_readable(report.size_before),
+                human_readable(report.size_after),
+                human_readable(report.size_before.saturating_sub(report.size_after))
+            );
+        }
+        if args.verbose && !report.output.is_empty() {
+            print!("{}", report.output);
+        }
+    }
+
+    println!();
+    println!(
+        "Finished in {}s: {} -> {}, {} reclaimed",
+        timestamp_before_gc.elapsed().as_secs(),
+        human_readable(total_before),
+        human_readable(total_after),
+        human_readable(total_before.saturating_sub(total_after))
+    );
+
+    if failed > 0 {
+        bail!("maintenance failed in {} projects", failed);
+    }
+    Ok(())
+}
+
+/// runs the tasks in the given project, measuring the size of its git dir
+fn maintain(project: &ProjectInfo, args: &Args) -> Result<Report> {
+    let git_dir = Repository::open(&project.absolute_path)?
+        .path()
+        .to_path_buf();
+    let size_before = disk_usage(&git_dir)?;
+
+    let mut limits = vec![];
+    if let Some(auto_limit) = args.auto_limit {
+        limits.push(format!("gc.auto={}", auto_limit));
+    }
+    if let Some(pack_limit) = args.pack_limit {
+        limits.push(format!("gc.autoPackLimit={}", pack_limit));
+    }
+
+    let mut output = String::new();
+    for task in &args.task {
+        let argv: Vec<String> = std::iter::once("git".to_string())
+            .chain(limits.iter().flat_map(|l| ["-c".to_string(), l.clone()]))
+            .chain(task.git_args().iter().map(|a| a.to_string()))
+            .collect();
+        let result = exec::direct_command(&argv)?
+            .current_dir(&project.absolute_path)
+            .output()?;
+        output += &String::from_utf8_lossy(&result.stdout);
+        output += &String::from_utf8_lossy(&result.stderr);
+        if !result.status.success() {
+            bail!("{} failed: {}", argv.join(" "), output.trim());
+        }
+    }
+
+    Ok(Report {
+        size_before,
+        size_after: disk_usage(&git_dir)?,
+        output,
+    })
+}
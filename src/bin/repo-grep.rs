@@ -0,0 +1,223 @@
+extern crate clap;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::*;
+use dialoguer::Select;
+use git2::Repository;
+use glob::Pattern;
+use rayon::prelude::*;
+use regex::{Regex, RegexBuilder};
+use repo_utils::generate;
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Search the files tracked by repos managed by git-repo for a regex,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// search case insensitive
+    #[arg(short, long, default_value = "false")]
+    ignore_case: bool,
+
+    /// only search files whose path (relative to the project) matches any of the given glob(s), e.g. "*.java"
+    #[arg(long, value_name = "GLOB")]
+    include: Option<Vec<Pattern>>,
+
+    /// don't search files whose path (relative to the project) matches any of the given glob(s), e.g. "test/**"
+    #[arg(long, value_name = "GLOB")]
+    exclude: Option<Vec<Pattern>>,
+
+    /// pick matches from an interactive list to see them in the context of their file
+    #[arg(long, default_value = "false")]
+    interactive: bool,
+
+    /// number of lines shown before and after a match picked from the interactive list
+    #[arg(long, value_name = "N", default_value = "5")]
+    context: usize,
+
+    /// the regex to search for
+    pattern: String,
+}
+
+/// a line matching the pattern
+struct Match {
+    /// relative to the root folder of the workspace
+    file: PathBuf,
+    line_number: usize,
+    line: String,
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let regex = RegexBuilder::new(&args.pattern)
+        .case_insensitive(args.ignore_case)
+        .build()?;
+
+    let (_, workspace, list_of_projects) = args.selection.select()?;
+
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| grep(project, &regex, &args))
+        .collect();
+
+    let mut matches = vec![];
+    let mut failed = 0;
+    for (project, result) in list_of_projects.iter().zip(results) {
+        match result {
+            Ok(project_matches) => matches.extend(project_matches),
+            Err(e) => {
+                eprintln!("{}: {}", project.path.red(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    match args.interactive {
+        true => pick(workspace.root_folder(), &matches, args.context)?,
+        false => print_matches(&matches, &regex),
+    }
+
+    // the matches found are incomplete, which must not go unnoticed by scripts
+    if failed > 0 {
+        bail!("searching failed in {} projects", failed);
+    }
+    Ok(())
+}
+
+/// searches the files of the project which are tracked by git
+fn grep(project: &ProjectInfo, regex: &Regex, args: &Args) -> Result<Vec<Match>> {
+    let repo = Repository::open(&project.absolute_path)?;
+    let index = repo.index()?;
+
+    let mut matches = vec![];
+    for entry in index.iter() {
+        let path = match std::str::from_utf8(&entry.path) {
+            Ok(path) => path.to_string(),
+            Err(_) => continue,
+        };
+        if !is_included(&path, args) {
+            continue;
+        }
+        // deleted files and submodules can't be read, binary files are skipped
+        let content = match fs::read(project.absolute_path.join(&path)) {
+            Ok(content) if !content.iter().take(8000).any(|b| *b == 0) => content,
+            _ => continue,
+        };
+
+        let file = Path::new(&project.path).join(&path);
+        for (index, line) in String::from_utf8_lossy(&content).lines().enumerate() {
+            if regex.is_match(line) {
+                matches.push(Match {
+                    file: file.clone(),
+                    line_number: index + 1,
+                    line: line.to_string(),
+                });
+            }
+        }
+    }
+    Ok(matches)
+}
+
+fn is_included(path: &str, args: &Args) -> bool {
+    let matches_any = |globs: &[Pattern]| globs.iter().any(|g| g.matches(path));
+    args.include.as_deref().is_none_or(matches_any)
+        && !args.exclude.as_deref().is_some_and(matches_any)
+}
+
+/// prints the matches grouped by file, like ripgrep does
+fn print_matches(matches: &[Match], regex: &Regex) {
+    let mut previous_file = None;
+    for m in matches {
+        if previous_file != Some(&m.file) {
+            if previous_file.is_some() {
+                println!();
+            }
+            println!("{}", m.file.display().to_string().magenta());
+            previous_file = Some(&m.file);
+        }
+        println!(
+            "{}:{}",
+            m.line_number.to_string().green(),
+            highlight(&m.line, regex)
+        );
+    }
+
+    let files: BTreeSet<_> = matches.iter().map(|m| &m.file).collect();
+    if !matches.is_empty() {
+        println!();
+    }
+    println!("{} matches in {} files", matches.len(), files.len());
+}
+
+fn highlight(line: &str, regex: &Regex) -> String {
+    let mut highlighted = String::new();
+    let mut end = 0;
+    for m in regex.find_iter(line) {
+        highlighted += &line[end..m.start()];
+        highlighted += &m.as_str().red().bold().to_string();
+        end = m.end();
+    }
+    highlighted += &line[end..];
+    highlighted
+}
+
+/// lists the matches, the one picked is shown in the context of its
+/// file until the list is left with Esc or q
+fn pick(root_folder: &Path, matches: &[Match], context: usize) -> Result<()> {
+    if matches.is_empty() {
+        println!("No matches");
+        return Ok(());
+    }
+
+    let items: Vec<String> = matches
+        .iter()
+        .map(|m| format!("{}:{}: {}", m.file.display(), m.line_number, m.line.trim()))
+        .collect();
+
+    let mut selection = 0;
+    while let Some(picked) = Select::new()
+        .with_prompt(format!("{} matches, Esc to quit", matches.len()))
+        .items(&items)
+        .default(selection)
+        .max_length(20)
+        .interact_opt()?
+    {
+        selection = picked;
+        print_context(root_folder, &matches[picked], context)?;
+    }
+    Ok(())
+}
+
+fn print_context(root_folder: &Path, m: &Match, context: usize) -> Result<()> {
+    let content = fs::read(root_folder.join(&m.file))?;
+    let first = m.line_number.saturating_sub(context).max(1);
+
+    println!("{}", m.file.display().to_string().magenta());
+    for (index, line) in String::from_utf8_lossy(&content)
+        .lines()
+        .enumerate()
+        .skip(first - 1)
+        .take(m.line_number - first + context + 1)
+    {
+        let line_number = index + 1;
+        match line_number == m.line_number {
+            true => println!("{}:{}", line_number.to_string().green(), line.bold()),
+            false => println!("{}-{}", line_number, line),
+        }
+    }
+    println!();
+    Ok(())
+}
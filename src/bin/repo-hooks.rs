@@ -0,0 +1,247 @@
+extern crate clap;
+
+use anyhow::{bail, Context, Result};
+use clap::{ArgGroup, Parser};
+use colored::*;
+use git2::Repository;
+use rayon::prelude::*;
+use repo_utils::generate;
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Install, verify or remove git hooks in all repos managed by git-repo,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+#[command(group(ArgGroup::new("action").args(["check", "remove"])))]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Verbose output, e.g. also list projects whose hooks are up to date
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
+
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    /// folder containing the hooks, defaults to the "hooks" folder of the manifest repo
+    #[arg(short, long, value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
+    template: Option<PathBuf>,
+
+    /// only consider the given hook(s) of the template folder, e.g. "commit-msg"
+    #[arg(long, value_name = "NAME")]
+    hook: Option<Vec<String>>,
+
+    /// only verify that the hooks are installed and up to date, don't change anything
+    #[arg(long, default_value = "false")]
+    check: bool,
+
+    /// remove the hooks again, hooks which were modified locally are kept
+    #[arg(long, default_value = "false")]
+    remove: bool,
+}
+
+/// a hook as found in the template folder
+struct Hook {
+    name: String,
+    content: Vec<u8>,
+}
+
+/// what was done (or would have to be done) about a hook in a project
+#[derive(Debug, PartialEq, Eq)]
+enum Change {
+    Installed,
+    Updated,
+    Removed,
+    Kept,
+}
+
+impl Change {
+    /// `check`: the change is pending, not done yet
+    fn describe(&self, check: bool) -> &'static str {
+        match self {
+            Change::Installed if check => "missing",
+            Change::Updated if check => "outdated",
+            Change::Installed => "installed",
+            Change::Updated => "updated",
+            Change::Removed => "removed",
+            Change::Kept => "modified, kept",
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, workspace, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+
+    let template = args
+        .template
+        .clone()
+        .unwrap_or_else(|| workspace.manifests_folder().join("hooks"));
+    let hooks = load_hooks(&template, args.hook.as_deref())?;
+
+    println!(
+        "Selected {} projects, {} hooks from {}",
+        list_of_projects.len(),
+        hooks.len(),
+        template.display()
+    );
+
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| apply(project, &hooks, &args))
+        .collect();
+
+    let mut changed = 0;
+    let mut failed = 0;
+    for (project, result) in list_of_projects.iter().zip(results) {
+        match result {
+            Ok(changes) if changes.is_empty() => {
+                if args.verbose {
+                    println!("{}: up to date", project.path.green());
+                }
+            }
+            Ok(changes) => {
+                changed += 1;
+                let changes: Vec<_> = changes
+                    .iter()
+                    .map(|(name, change)| format!("{} {}", name, change.describe(args.check)))
+                    .collect();
+                println!("{}: {}", project.path.yellow(), changes.join(", "));
+            }
+            Err(e) => {
+                failed += 1;
+                println!("{}: {}", project.path.red(), e);
+            }
+        }
+    }
+
+    println!();
+    let summary = match (args.check, args.remove) {
+        (true, _) => "Hooks missing or outdated",
+        (_, true) => "Removed hooks",
+        _ => "Installed hooks",
+    };
+    println!(
+        "{} in {}/{} projects",
+        summary,
+        changed,
+        list_of_projects.len()
+    );
+
+    if failed > 0 {
+        bail!("failed in {} projects", failed);
+    }
+    if args.check && changed > 0 {
+        bail!("hooks are not up to date in {} projects", changed);
+    }
+    Ok(())
+}
+
+/// reads all files of the template folder, or only the ones named in `filter`
+fn load_hooks(template: &Path, filter: Option<&[String]>) -> Result<Vec<Hook>> {
+    let entries = fs::read_dir(template)
+        .with_context(|| format!("Failed to read hooks from {}", template.display()))?;
+
+    let mut hooks = vec![];
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !entry.file_type()?.is_file()
+            || name.ends_with(".sample")
+            || filter.is_some_and(|filter| !filter.contains(&name))
+        {
+            continue;
+        }
+        hooks.push(Hook {
+            content: fs::read(entry.path())?,
+            name,
+        });
+    }
+    hooks.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if let Some(missing) =
+        filter.and_then(|filter| filter.iter().find(|f| !hooks.iter().any(|h| &h.name == *f)))
+    {
+        bail!("Hook {} not found in {}", missing, template.display());
+    }
+    if hooks.is_empty() {
+        bail!("No hooks found in {}", template.display());
+    }
+    Ok(hooks)
+}
+
+/// installs, verifies or removes the hooks of a project, returns what was
+/// done, or in case of --check what would have to be done
+fn apply(project: &ProjectInfo, hooks: &[Hook], args: &Args) -> Result<Vec<(String, Change)>> {
+    let hooks_folder = Repository::open(&project.absolute_path)?
+        .path()
+        .join("hooks");
+
+    let mut changes = vec![];
+    for hook in hooks {
+        let path = hooks_folder.join(&hook.name);
+        let installed = fs::read(&path).ok();
+        let up_to_date = installed.as_ref() == Some(&hook.content) && is_executable(&path)?;
+
+        let change = if args.remove {
+            match installed {
+                None => continue,
+                Some(content) if content != hook.content => Change::Kept,
+                Some(_) => {
+                    fs::remove_file(&path)?;
+                    Change::Removed
+                }
+            }
+        } else if up_to_date {
+            continue;
+        } else {
+            if !args.check {
+                fs::create_dir_all(&hooks_folder)?;
+                fs::write(&path, &hook.content)?;
+                make_executable(&path)?;
+            }
+            match installed {
+                None => Change::Installed,
+                Some(_) => Change::Updated,
+            }
+        };
+        changes.push((hook.name.clone(), change));
+    }
+    Ok(changes)
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> Result<bool> {
+    use std::os::unix::fs::PermissionsExt;
+    Ok(fs::metadata(path)?.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> Result<bool> {
+    Ok(true)
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_mode(permissions.mode() | 0o755);
+    fs::set_permissions(path, permissions)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
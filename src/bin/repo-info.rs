@@ -0,0 +1,152 @@
+extern crate clap;
+
+use anyhow::Result;
+use clap::Parser;
+use git2::Repository;
+use repo_utils::disk_usage::{disk_usage, human_readable};
+use repo_utils::generate;
+use repo_utils::git_status::{lookup_manifest_branch_in, query_git_statuses};
+use repo_utils::selection::SelectionArgs;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Print an overview of a workspace managed by git-repo, e.g. to be attached to support tickets,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, workspace, list_of_projects) = args.selection.select()?;
+
+    // every piece of information is optional, a broken workspace is what
+    // this tool is run for most of the time
+    let unknown = |e: &dyn std::fmt::Display| format!("unknown ({})", e);
+    let or_unknown = |result: Result<String>| result.unwrap_or_else(|e| unknown(&e));
+
+    let (manifest_url, manifest_branch, manifest_revision) =
+        match manifest_state(&workspace.manifests_folder()) {
+            Ok(state) => state,
+            Err(e) => (unknown(&e), unknown(&e), unknown(&e)),
+        };
+    let sync_branch_name = workspace.sync_branch_name();
+
+    // "repo sync" rewrites the project.list every time
+    let last_sync = or_unknown(
+        fs::metadata(workspace.repo_folder().join("project.list"))
+            .and_then(|m| m.modified())
+            .map(format_time)
+            .map_err(Into::into),
+    );
+
+    let mut groups: BTreeMap<&str, usize> = BTreeMap::new();
+    for project in &list_of_projects {
+        for group in &project.groups {
+            *groups.entry(group).or_default() += 1;
+        }
+    }
+
+    let dirty = match &sync_branch_name {
+        Ok(sync_branch_name) => {
+            let progress_bar = config.ui.progress_bar(list_of_projects.len())?;
+            or_unknown(
+                query_git_statuses(
+                    workspace.root_folder(),
+                    &list_of_projects,
+                    sync_branch_name,
+                    &progress_bar,
+                )
+                .map(|statuses| {
+                    let uncommitted = statuses.iter().filter(|s| s.uncomitted_changes).count();
+                    let local_commits = statuses.iter().filter(|s| s.local_commits > 0).count();
+                    format!(
+                        "{} with uncommitted changes, {} with local commits",
+                        uncommitted, local_commits
+                    )
+                })
+                .map_err(Into::into),
+            )
+        }
+        Err(e) => unknown(e),
+    };
+
+    let disk_usage = or_unknown(
+        disk_usage(workspace.root_folder())
+            .map(human_readable)
+            .map_err(Into::into),
+    );
+
+    println!("workspace:         {}", workspace.root_folder().display());
+    println!("manifest url:      {}", manifest_url);
+    println!("manifest branch:   {}", manifest_branch);
+    println!("manifest revision: {}", manifest_revision);
+    println!(
+        "sync branch:       {}",
+        or_unknown(sync_branch_name.map_err(Into::into))
+    );
+    println!("last sync:         {}", last_sync);
+    println!("projects:          {}", list_of_projects.len());
+    for (group, count) in groups {
+        println!("  {:16} {}", group, count);
+    }
+    println!("dirty projects:    {}", dirty);
+    println!("disk usage:        {}", disk_usage);
+    Ok(())
+}
+
+/// url, branch and revision checked out of the manifest repo
+fn manifest_state(manifests_folder: &Path) -> Result<(String, String, String)> {
+    let repo = Repository::open(manifests_folder)?;
+    let head = repo.head()?;
+    let url = repo
+        .find_remote("origin")
+        .ok()
+        .and_then(|r| r.url().map(|u| u.to_string()))
+        .unwrap_or_else(|| "unknown".to_string());
+    // the local branch is always "default", the one given to repo init is its upstream
+    let branch =
+        lookup_manifest_branch_in(manifests_folder).unwrap_or_else(|_| "unknown".to_string());
+    let revision = head.peel_to_commit()?.id().to_string();
+    Ok((url, branch, revision))
+}
+
+/// formats the time as UTC, e.g. "2024-01-31 17:05:00 UTC"
+fn format_time(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let (days, seconds_of_day) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+
+    // civil date of the days since 1970-01-01 in the proleptic gregorian calendar
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60
+    )
+}
@@ -0,0 +1,275 @@
+extern crate clap;
+
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use colored::*;
+use git2::Repository;
+use rayon::prelude::*;
+use repo_utils::generate;
+use repo_utils::json_lines;
+use repo_utils::progress::Progress;
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Report the licenses of repos managed by git-repo, as found in license files and SPDX headers,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// how the report is printed
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Table,
+    Csv,
+    Json,
+    /// one json object per project, printed as soon as it is scanned
+    Jsonl,
+}
+
+/// the licenses found in a project
+#[derive(Serialize, Default)]
+struct Report {
+    path: String,
+    license_files: Vec<LicenseFile>,
+    /// number of files per SPDX-License-Identifier
+    spdx: BTreeMap<String, usize>,
+}
+
+#[derive(Serialize)]
+struct LicenseFile {
+    /// relative to the project
+    file: String,
+    /// None if the license text isn't recognized
+    license: Option<&'static str>,
+}
+
+/// files at the root of a project containing the license, as used in AOSP and elsewhere
+const LICENSE_FILE_PREFIXES: &[&str] =
+    &["LICENSE", "LICENCE", "COPYING", "NOTICE", "MODULE_LICENSE_"];
+
+/// SPDX headers are expected in the first lines of a file
+const HEADER_SIZE: usize = 4096;
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, _, list_of_projects) = args.selection.select()?;
+
+    // keep stdout clean for csv and json
+    eprintln!("Selected {} projects", list_of_projects.len());
+
+    let progress_bar = config.ui.progress_bar(list_of_projects.len())?;
+    let stream = args.format == Format::Jsonl;
+    let reports = collect(&list_of_projects, stream, &progress_bar);
+
+    match args.format {
+        Format::Table => print_table(&reports),
+        Format::Csv => print_csv(&reports),
+        Format::Json => {
+            serde_json::to_writer_pretty(io::stdout(), &reports)?;
+            println!();
+        }
+        Format::Jsonl => {}
+    }
+    Ok(())
+}
+
+/// scans all projects, with `stream` each report is printed as json
+/// line as soon as it is known
+fn collect(list_of_projects: &[ProjectInfo], stream: bool, progress: &dyn Progress) -> Vec<Report> {
+    progress.start(list_of_projects.len() as u64);
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| {
+            let report = scan(project);
+            if let (true, Ok(report)) = (stream, &report) {
+                if let Err(e) = json_lines::print(report) {
+                    eprintln!("{}: failed to print report: {}", project.path.red(), e);
+                }
+            }
+            progress.inc();
+            report
+        })
+        .collect();
+    progress.finish();
+
+    let mut reports = vec![];
+    for (project, result) in list_of_projects.iter().zip(results) {
+        match result {
+            Ok(report) => reports.push(report),
+            Err(e) => eprintln!("{}: {}", project.path.red(), e),
+        }
+    }
+    reports
+}
+
+/// looks for license files in the root folder of the project and for
+/// SPDX headers in all files tracked by git
+fn scan(project: &ProjectInfo) -> Result<Report> {
+    let mut report = Report {
+        path: project.path.clone(),
+        ..Default::default()
+    };
+
+    let mut names: Vec<_> = fs::read_dir(&project.absolute_path)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| {
+            let upper = name.to_uppercase();
+            LICENSE_FILE_PREFIXES.iter().any(|p| upper.starts_with(p))
+        })
+        .collect();
+    names.sort();
+    for name in names {
+        let content = fs::read(project.absolute_path.join(&name))?;
+        report.license_files.push(LicenseFile {
+            license: identify(&name, &String::from_utf8_lossy(&content)),
+            file: name,
+        });
+    }
+
+    let repo = Repository::open(&project.absolute_path)?;
+    for entry in repo.index()?.iter() {
+        let path = match std::str::from_utf8(&entry.path) {
+            Ok(path) => path,
+            Err(_) => continue,
+        };
+        // deleted files and submodules can't be read
+        let header = match read_header(&project.absolute_path.join(path)) {
+            Ok(header) => header,
+            Err(_) => continue,
+        };
+        if let Some(identifier) = spdx_identifier(&String::from_utf8_lossy(&header)) {
+            *report.spdx.entry(identifier).or_default() += 1;
+        }
+    }
+    Ok(report)
+}
+
+/// reads the first HEADER_SIZE bytes of the file, without loading large files completely
+fn read_header(path: &Path) -> io::Result<Vec<u8>> {
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    File::open(path)?
+        .take(HEADER_SIZE as u64)
+        .read_to_end(&mut header)?;
+    Ok(header)
+}
+
+/// recognizes the most common licenses by their text, or by the name of
+/// the file for AOSP's MODULE_LICENSE_* markers
+fn identify(name: &str, content: &str) -> Option<&'static str> {
+    const MARKERS: &[(&str, &str)] = &[
+        ("MODULE_LICENSE_APACHE2", "Apache-2.0"),
+        ("MODULE_LICENSE_BSD", "BSD"),
+        ("MODULE_LICENSE_MIT", "MIT"),
+        ("MODULE_LICENSE_GPL", "GPL"),
+        ("MODULE_LICENSE_LGPL", "LGPL"),
+    ];
+    const TEXTS: &[(&str, &str)] = &[
+        ("Apache License, Version 2.0", "Apache-2.0"),
+        ("Apache License Version 2.0", "Apache-2.0"),
+        ("GNU LESSER GENERAL PUBLIC LICENSE", "LGPL"),
+        ("GNU GENERAL PUBLIC LICENSE", "GPL"),
+        ("Mozilla Public License", "MPL"),
+        ("Permission is hereby granted, free of charge", "MIT"),
+        ("Redistribution and use in source and binary forms", "BSD"),
+    ];
+
+    let upper = name.to_uppercase();
+    if let Some((_, license)) = MARKERS.iter().find(|(m, _)| upper.starts_with(m)) {
+        return Some(license);
+    }
+    // license texts are wrapped differently
+    let text = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    TEXTS
+        .iter()
+        .find(|(marker, _)| text.contains(marker))
+        .map(|(_, license)| *license)
+}
+
+/// the license expression of a "SPDX-License-Identifier: ..." header line
+fn spdx_identifier(header: &str) -> Option<String> {
+    const TAG: &str = "SPDX-License-Identifier:";
+    let line = header.lines().find(|l| l.contains(TAG))?;
+    let expression = line[line.find(TAG)? + TAG.len()..]
+        .trim()
+        // closing comment markers, e.g. in C or html
+        .trim_end_matches("*/")
+        .trim_end_matches("-->")
+        .trim();
+    match expression.is_empty() {
+        true => None,
+        false => Some(expression.to_string()),
+    }
+}
+
+fn license_files_of(report: &Report) -> String {
+    report
+        .license_files
+        .iter()
+        .map(|f| format!("{} ({})", f.file, f.license.unwrap_or("unknown")))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn spdx_of(report: &Report) -> String {
+    report
+        .spdx
+        .iter()
+        .map(|(identifier, count)| format!("{} ({})", identifier, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn print_table(reports: &[Report]) {
+    let mut without_license = 0;
+    for report in reports {
+        if report.license_files.is_empty() && report.spdx.is_empty() {
+            without_license += 1;
+            println!("{}: {}", report.path.red(), "no license found".red());
+            continue;
+        }
+        println!("{}", report.path.green());
+        if !report.license_files.is_empty() {
+            println!("  files: {}", license_files_of(report));
+        }
+        if !report.spdx.is_empty() {
+            println!("  SPDX:  {}", spdx_of(report));
+        }
+    }
+    println!();
+    println!(
+        "No license found in {}/{} projects",
+        without_license,
+        reports.len()
+    );
+}
+
+fn print_csv(reports: &[Report]) {
+    println!("path,license_files,spdx");
+    for report in reports {
+        println!(
+            "\"{}\",\"{}\",\"{}\"",
+            report.path.replace('"', "\"\""),
+            license_files_of(report).replace('"', "\"\""),
+            spdx_of(report).replace('"', "\"\"")
+        );
+    }
+}
@@ -0,0 +1,230 @@
+extern crate clap;
+
+use anyhow::Result;
+use clap::Parser;
+use colored::*;
+use repo_utils::config::Config;
+use repo_utils::generate;
+use repo_utils::repo_project_selector::{parse_file, parse_manifest, Manifest};
+use repo_utils::workspace::Workspace;
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Check the manifest of a workspace managed by git-repo for common mistakes,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    /// change working directory (mostly useful for testing)
+    #[arg(short = 'C', long, value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
+    cwd: Option<PathBuf>,
+
+    /// lint the given manifest file instead of .repo/manifest.xml and the local manifests
+    #[arg(short, long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    manifest: Option<PathBuf>,
+}
+
+/// A single finding, located by file or project path
+struct Problem {
+    location: String,
+    message: String,
+}
+
+impl Problem {
+    fn new(location: impl fmt::Display, message: impl Into<String>) -> Self {
+        Problem {
+            location: location.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.location.yellow(), self.message)
+    }
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let args = Args::parse();
+
+    if let Some(cwd) = &args.cwd {
+        env::set_current_dir(cwd)?;
+    }
+
+    let config = Config::load()?;
+    config.ui.apply();
+
+    let workspace = Workspace::current()?.manifest_cache(config.defaults.manifest_cache);
+    let mut problems = vec![];
+
+    // includes are checked upfront, the manifest can't be aggregated
+    // as long as some of them are missing or broken
+    let manifests_folder = workspace.manifests_folder();
+    match &args.manifest {
+        Some(manifest) => check_includes(
+            &manifests_folder.join(manifest),
+            &manifests_folder,
+            &mut problems,
+        ),
+        None => {
+            check_includes(
+                &workspace.repo_folder().join("manifest.xml"),
+                &manifests_folder,
+                &mut problems,
+            );
+            for local_manifest in local_manifests(&workspace)? {
+                let folder = local_manifest.parent().unwrap_or(Path::new("."));
+                check_includes(&local_manifest, folder, &mut problems);
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        let manifest = match &args.manifest {
+            Some(manifest) => parse_manifest(&manifests_folder.join(manifest))?,
+            None => workspace.manifest()?.clone(),
+        };
+        lint(&manifest, &mut problems);
+    }
+
+    for problem in &problems {
+        println!("{}", problem);
+    }
+
+    println!();
+    println!("Found {} problems", problems.len());
+
+    if !problems.is_empty() {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn local_manifests(workspace: &Workspace) -> Result<Vec<PathBuf>> {
+    let folder = workspace.repo_folder().join("local_manifests");
+    if !folder.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut files = fs::read_dir(folder)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    files.retain(|path| path.extension().is_some_and(|ext| ext == "xml"));
+    files.sort();
+    Ok(files)
+}
+
+/// recursively checks that the given manifest file and all of its
+/// includes (resolved relative to `include_folder`) exist and parse
+fn check_includes(path: &Path, include_folder: &Path, problems: &mut Vec<Problem>) {
+    let manifest = match parse_file(path) {
+        Ok(manifest) => manifest,
+        Err(e) => return problems.push(Problem::new(path.display(), e.to_string())),
+    };
+    for include in &manifest.includes {
+        let include_path = include_folder.join(&include.name);
+        if !include_path.is_file() {
+            problems.push(Problem::new(
+                path.display(),
+                format!("included file {} does not exist", include.name),
+            ));
+            continue;
+        }
+        // nested includes are resolved relative to the including file
+        let folder = include_path
+            .parent()
+            .unwrap_or(include_folder)
+            .to_path_buf();
+        check_includes(&include_path, &folder, problems);
+    }
+}
+
+fn lint(manifest: &Manifest, problems: &mut Vec<Problem>) {
+    check_duplicate_paths(manifest, problems);
+    check_nested_paths(manifest, problems);
+    check_remotes(manifest, problems);
+    check_groups(manifest, problems);
+    check_revisions(manifest, problems);
+}
+
+fn check_duplicate_paths(manifest: &Manifest, problems: &mut Vec<Problem>) {
+    let mut names_by_path: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for project in &manifest.projects {
+        names_by_path
+            .entry(&project.path)
+            .or_default()
+            .push(&project.name);
+    }
+    for (path, names) in names_by_path.iter().filter(|(_, names)| names.len() > 1) {
+        problems.push(Problem::new(
+            path,
+            format!("path is used by multiple projects: {}", names.join(", ")),
+        ));
+    }
+}
+
+fn check_nested_paths(manifest: &Manifest, problems: &mut Vec<Problem>) {
+    let mut paths: Vec<&str> = manifest.projects.iter().map(|p| p.path.as_str()).collect();
+    paths.sort_unstable();
+    paths.dedup();
+
+    for (i, outer) in paths.iter().enumerate() {
+        let prefix = format!("{}/", outer.trim_end_matches('/'));
+        for inner in paths[i + 1..].iter().filter(|p| p.starts_with(&prefix)) {
+            problems.push(Problem::new(inner, format!("nested in project {}", outer)));
+        }
+    }
+}
+
+fn check_remotes(manifest: &Manifest, problems: &mut Vec<Problem>) {
+    for project in &manifest.projects {
+        match manifest.remote_of(project) {
+            Some(remote) if manifest.find_remote(remote).is_none() => problems.push(Problem::new(
+                &project.path,
+                format!("unknown remote {}", remote),
+            )),
+            Some(_) => {}
+            None => problems.push(Problem::new(
+                &project.path,
+                "no remote and no default remote",
+            )),
+        }
+    }
+}
+
+fn check_groups(manifest: &Manifest, problems: &mut Vec<Problem>) {
+    for project in manifest
+        .projects
+        .iter()
+        .filter(|p| p.group_names().is_empty())
+    {
+        problems.push(Problem::new(&project.path, "not part of any group"));
+    }
+}
+
+fn check_revisions(manifest: &Manifest, problems: &mut Vec<Problem>) {
+    let mut revisions_by_name: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for project in &manifest.projects {
+        let revisions = revisions_by_name.entry(&project.name).or_default();
+        let revision = manifest.revision_of(project).unwrap_or("<none>");
+        if !revisions.contains(&revision) {
+            revisions.push(revision);
+        }
+    }
+    for (name, revisions) in revisions_by_name.iter().filter(|(_, r)| r.len() > 1) {
+        problems.push(Problem::new(
+            name,
+            format!(
+                "project is checked out at conflicting revisions: {}",
+                revisions.join(", ")
+            ),
+        ));
+    }
+}
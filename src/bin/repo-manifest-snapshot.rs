@@ -0,0 +1,58 @@
+extern crate clap;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use repo_utils::generate;
+use repo_utils::selection::SelectionArgs;
+use std::env;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Write a manifest with the revisions of all projects pinned to their
+/// currently checked out commits, see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// write the manifest to FILE instead of stdout
+    #[arg(short, long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (_, workspace, list_of_projects) = args.selection.select()?;
+
+    let manifest = workspace.manifest()?;
+    for info in &list_of_projects {
+        if !manifest.contains_project(&info.path) {
+            eprintln!("{}: not defined in the manifest, skipped", info.path);
+        }
+    }
+    let snapshot = manifest.snapshot(&list_of_projects)?;
+
+    match &args.output {
+        Some(path) => {
+            let mut file =
+                File::create(path).with_context(|| format!("Unable to create {:?}", path))?;
+            snapshot.write_xml(&mut file)?;
+            eprintln!(
+                "Wrote snapshot of {} projects to {:?}",
+                snapshot.projects.len(),
+                path
+            );
+        }
+        None => snapshot.write_xml(&mut io::stdout().lock())?,
+    }
+    io::stdout().flush()?;
+
+    Ok(())
+}
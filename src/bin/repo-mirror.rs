@@ -0,0 +1,253 @@
+extern crate clap;
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use colored::*;
+use git2::Repository;
+use rayon::prelude::*;
+use repo_utils::exec;
+use repo_utils::generate;
+use repo_utils::paths;
+use repo_utils::progress::Progress;
+use repo_utils::repo_project_selector::{Manifest, ProjectInfo};
+use repo_utils::selection::SelectionArgs;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Create or update local bare mirrors of all projects defined in the manifest of git-repo,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Verbose output, e.g. print the output of git clone and git remote update
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
+
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    /// copy the objects borrowed from the workspace into the mirror, so it doesn't depend on the workspace
+    #[arg(long, default_value = "false")]
+    dissociate: bool,
+
+    /// folder containing the mirrors, each project is mirrored to <DIR>/<name>.git
+    #[arg(value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
+    dir: PathBuf,
+}
+
+/// the result of mirroring a project
+enum Mirror {
+    /// cloned, `referenced` tells whether objects are shared with the workspace
+    Created {
+        referenced: bool,
+        output: String,
+    },
+    Updated {
+        output: String,
+    },
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, workspace, list_of_projects) = args
+        .selection
+        .select_with(|selector| selector.include_missing(true))?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+
+    let manifest = workspace.manifest()?;
+    let manifest_url = Repository::open(workspace.manifests_folder())
+        .ok()
+        .and_then(|repo| Some(repo.find_remote("origin").ok()?.url()?.to_string()));
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    fs::create_dir_all(&args.dir).with_context(|| format!("Unable to create {:?}", args.dir))?;
+    // git runs in the mirrors, so relative paths wouldn't work
+    args.dir = paths::canonicalize(&args.dir)?;
+
+    let progress_bar = config.ui.progress_bar(list_of_projects.len())?;
+    let sources = Sources {
+        manifest,
+        manifest_url: manifest_url.as_deref(),
+    };
+    run(&list_of_projects, &sources, &args, &progress_bar)
+}
+
+/// where the projects are mirrored from
+struct Sources<'a> {
+    manifest: &'a Manifest,
+    /// url of the manifest repo, relative fetch urls are resolved against it
+    manifest_url: Option<&'a str>,
+}
+
+fn run(
+    list_of_projects: &[ProjectInfo],
+    sources: &Sources,
+    args: &Args,
+    progress: &dyn Progress,
+) -> Result<()> {
+    progress.start(list_of_projects.len() as u64);
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| {
+            let result = mirror(project, sources, &args.dir, args.dissociate);
+            progress.inc();
+            result
+        })
+        .collect();
+    progress.finish();
+
+    let mut mirrored = 0;
+    let mut failed = 0;
+    for (project, result) in list_of_projects.iter().zip(results) {
+        let output = match result {
+            Ok(Mirror::Created { referenced, output }) => {
+                match referenced {
+                    true => println!(
+                        "{}: created, sharing objects with the workspace",
+                        project.name.yellow()
+                    ),
+                    false => println!("{}: created", project.name.yellow()),
+                }
+                output
+            }
+            Ok(Mirror::Updated { output }) => {
+                if args.verbose {
+                    println!("{}: updated", project.name.green());
+                }
+                output
+            }
+            Err(e) => {
+                failed += 1;
+                println!("{}: {:#}", project.name.red(), e);
+                continue;
+            }
+        };
+        mirrored += 1;
+        if args.verbose {
+            print!("{}", output);
+        }
+    }
+
+    println!();
+    println!(
+        "Mirrored {}/{} projects to {}",
+        mirrored,
+        list_of_projects.len(),
+        args.dir.display()
+    );
+
+    if failed > 0 {
+        bail!("mirroring failed in {} projects", failed);
+    }
+    Ok(())
+}
+
+/// clones the project as bare mirror into `dir`, borrowing objects from
+/// the project's checkout in the workspace if it has one, or updates an
+/// existing mirror
+fn mirror(
+    project: &ProjectInfo,
+    sources: &Sources,
+    dir: &Path,
+    dissociate: bool,
+) -> Result<Mirror> {
+    let destination = dir.join(format!("{}.git", project.name));
+    if destination.exists() {
+        let output = git(&["git", "remote", "update", "--prune"], &destination)?;
+        return Ok(Mirror::Updated { output });
+    }
+
+    let url = project_url(project, sources)?;
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let referenced = project.absolute_path.exists();
+    let mut argv = vec![
+        "git".to_string(),
+        "clone".to_string(),
+        "--mirror".to_string(),
+    ];
+    if referenced {
+        argv.push("--reference-if-able".to_string());
+        argv.push(project.absolute_path.display().to_string());
+        if dissociate {
+            argv.push("--dissociate".to_string());
+        }
+    }
+    argv.push(url);
+    argv.push(destination.display().to_string());
+
+    let output = git(&argv, dir)?;
+    Ok(Mirror::Created {
+        referenced: referenced && !dissociate,
+        output,
+    })
+}
+
+/// runs git in `cwd`, returns its stdout and stderr
+fn git<S: AsRef<str>>(argv: &[S], cwd: &Path) -> Result<String> {
+    let argv: Vec<String> = argv.iter().map(|a| a.as_ref().to_string()).collect();
+    let result = exec::direct_command(&argv)?.current_dir(cwd).output()?;
+    let mut output = String::from_utf8_lossy(&result.stdout).to_string();
+    output += &String::from_utf8_lossy(&result.stderr);
+    if !result.status.success() {
+        bail!("{} failed: {}", argv.join(" "), output.trim());
+    }
+    Ok(output)
+}
+
+/// the fetch url of the project's remote followed by the project's name,
+/// like the repo-tool builds it
+fn project_url(project: &ProjectInfo, sources: &Sources) -> Result<String> {
+    let remote = match project
+        .remote
+        .as_deref()
+        .and_then(|name| sources.manifest.find_remote(name))
+    {
+        Some(remote) => remote,
+        None => bail!("no remote defined in the manifest"),
+    };
+
+    let fetch = match remote.fetch.starts_with('.') {
+        true => match sources.manifest_url {
+            Some(manifest_url) => resolve(manifest_url, &remote.fetch),
+            None => bail!(
+                "fetch url {} is relative, but the manifest repo has no origin",
+                remote.fetch
+            ),
+        },
+        false => remote.fetch.clone(),
+    };
+    Ok(format!("{}/{}", fetch.trim_end_matches('/'), project.name))
+}
+
+/// resolves a relative fetch url like ".." against the url of the
+/// manifest repo
+fn resolve(base: &str, relative: &str) -> String {
+    let mut url = base.trim_end_matches('/').to_string();
+    for segment in relative.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                if let Some(index) = url.rfind('/') {
+                    url.truncate(index);
+                }
+            }
+            segment => {
+                url.push('/');
+                url.push_str(segment);
+            }
+        }
+    }
+    url
+}
@@ -0,0 +1,148 @@
+extern crate clap;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::*;
+use dialoguer::Confirm;
+use git2::Repository;
+use rayon::prelude::*;
+use repo_utils::generate;
+use repo_utils::git_ops::{self, PrunableBranch, PruneReason};
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use std::env;
+
+/// Delete local branches of repos managed by git-repo which were merged into the last
+/// "repo sync" or whose upstream is gone, see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Verbose output, e.g. also list projects without branches to delete
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
+
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    /// Dry-run, only lists the branches which would be deleted
+    #[arg(short, long, default_value = "false")]
+    dry_run: bool,
+
+    /// delete the branches without asking for confirmation
+    #[arg(short, long, default_value = "false")]
+    yes: bool,
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, workspace, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+
+    let sync_branch_name = workspace.sync_branch_name()?;
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    let prunable = scan(&list_of_projects, &sync_branch_name, args.verbose);
+    let count: usize = prunable.iter().map(|(_, branches)| branches.len()).sum();
+
+    println!();
+    println!(
+        "{} branches to delete in {}/{} projects",
+        count,
+        prunable.len(),
+        list_of_projects.len()
+    );
+
+    if args.dry_run || count == 0 {
+        return Ok(());
+    }
+    if !args.yes
+        && !Confirm::new()
+            .with_prompt(format!("Delete {} branches?", count))
+            .interact()?
+    {
+        println!("Skipping deletion of branches");
+        return Ok(());
+    }
+
+    prune(&prunable)
+}
+
+/// lists the prunable branches of all projects, returns the projects
+/// having any along with their branches
+fn scan<'a>(
+    list_of_projects: &'a [ProjectInfo],
+    sync_branch_name: &str,
+    verbose: bool,
+) -> Vec<(&'a ProjectInfo, Vec<PrunableBranch>)> {
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| {
+            let repo = Repository::open(&project.absolute_path)?;
+            git_ops::prunable_branches(&repo, sync_branch_name)
+        })
+        .collect();
+
+    let mut prunable = vec![];
+    for (project, result) in list_of_projects.iter().zip(results) {
+        let branches = match result {
+            Ok(branches) => branches,
+            Err(e) => {
+                println!("{}: {}", project.path.red(), e);
+                continue;
+            }
+        };
+        if branches.is_empty() {
+            if verbose {
+                println!("{}: nothing to delete", project.path.green());
+            }
+            continue;
+        }
+
+        println!("{}", project.path.yellow());
+        for branch in &branches {
+            let reason = match branch.reason {
+                PruneReason::Merged => format!("merged into {}", sync_branch_name),
+                PruneReason::UpstreamGone => "upstream is gone".to_string(),
+            };
+            println!("  {} ({})", branch.name, reason);
+        }
+        prunable.push((project, branches));
+    }
+    prunable
+}
+
+fn prune(prunable: &[(&ProjectInfo, Vec<PrunableBranch>)]) -> Result<()> {
+    let results: Vec<_> = prunable
+        .par_iter()
+        .map(|(project, branches)| {
+            let repo = Repository::open(&project.absolute_path)?;
+            let names: Vec<_> = branches.iter().map(|b| b.name.clone()).collect();
+            git_ops::delete_branches(&repo, &names)
+        })
+        .collect();
+
+    let mut failed = 0;
+    for ((project, _), result) in prunable.iter().zip(results) {
+        match result {
+            Ok(output) => print!("{}: {}", project.path.green(), output),
+            Err(e) => {
+                failed += 1;
+                println!("{}: {}", project.path.red(), e);
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!("failed to delete branches in {} projects", failed);
+    }
+    Ok(())
+}
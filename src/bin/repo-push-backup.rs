@@ -0,0 +1,174 @@
+extern crate clap;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::*;
+use git2::Repository;
+use rayon::prelude::*;
+use repo_utils::exec;
+use repo_utils::generate;
+use repo_utils::git_ops;
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use std::env;
+use std::path::Path;
+
+/// Push the local branches of repos managed by git-repo to a personal backup remote,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Verbose output, e.g. print the output of git push, and projects without anything to back up
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
+
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    /// url of the backup repo per project, {project} and {path} are replaced by the name and path of the project,
+    /// e.g. "ssh://backup.example.com/~/{project}", defaults to backup.url of the config
+    #[arg(short, long, value_name = "TEMPLATE")]
+    url: Option<String>,
+
+    /// additionally push the stashes, as refs/stashes/<n>
+    #[arg(long, default_value = "false")]
+    stashes: bool,
+}
+
+/// the result of backing up a project
+enum Backup {
+    /// no local branches (and no stashes, if requested)
+    NothingToPush,
+    Pushed {
+        branches: usize,
+        stashes: usize,
+        output: String,
+    },
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, _, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+
+    let url = match args.url.clone().or_else(|| config.backup.url.clone()) {
+        Some(url) => url,
+        None => bail!("no backup url given, pass --url or set backup.url in the config"),
+    };
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| backup(project, &url, args.stashes))
+        .collect();
+
+    let mut pushed = 0;
+    let mut failed = 0;
+    for (project, result) in list_of_projects.iter().zip(results) {
+        match result {
+            Ok(Backup::NothingToPush) => {
+                if args.verbose {
+                    println!("{}: nothing to back up", project.path.green());
+                }
+            }
+            Ok(Backup::Pushed {
+                branches,
+                stashes,
+                output,
+            }) => {
+                pushed += 1;
+                match args.stashes {
+                    true => println!(
+                        "{}: pushed {} branches and {} stashes",
+                        project.path.green(),
+                        branches,
+                        stashes
+                    ),
+                    false => println!("{}: pushed {} branches", project.path.green(), branches),
+                }
+                if args.verbose {
+                    print!("{}", output);
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                println!("{}: {:#}", project.path.red(), e);
+            }
+        }
+    }
+
+    println!();
+    println!("Backed up {}/{} projects", pushed, list_of_projects.len());
+
+    if failed > 0 {
+        bail!("backup failed in {} projects", failed);
+    }
+    Ok(())
+}
+
+/// force-pushes all local branches (and stashes) of the project to its
+/// backup repo, so the backup mirrors the current state
+fn backup(project: &ProjectInfo, url_template: &str, stashes: bool) -> Result<Backup> {
+    let mut repo = Repository::open(&project.absolute_path)?;
+    let branches = repo.branches(Some(git2::BranchType::Local))?.count();
+    let stashes = match stashes {
+        true => git_ops::stash_list(&mut repo)?.len(),
+        false => 0,
+    };
+    if branches == 0 && stashes == 0 {
+        return Ok(Backup::NothingToPush);
+    }
+
+    let url = url_template
+        .replace("{project}", &project.name)
+        .replace("{path}", &project.path);
+    init_local_backup(&url)?;
+
+    let mut argv: Vec<String> = ["git", "push", "--force", "--porcelain", &url]
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+    if branches > 0 {
+        argv.push("refs/heads/*:refs/heads/*".to_string());
+    }
+    for index in 0..stashes {
+        argv.push(format!("stash@{{{0}}}:refs/stashes/{0}", index));
+    }
+
+    let result = exec::direct_command(&argv)?
+        .current_dir(&project.absolute_path)
+        .output()?;
+    let mut output = String::from_utf8_lossy(&result.stdout).to_string();
+    output += &String::from_utf8_lossy(&result.stderr);
+    if !result.status.success() {
+        bail!("{} failed: {}", argv.join(" "), output.trim());
+    }
+    Ok(Backup::Pushed {
+        branches,
+        stashes,
+        output,
+    })
+}
+
+/// backups to a local folder, e.g. on an external disk, don't need any
+/// preparation: a bare repo is created if missing
+fn init_local_backup(url: &str) -> Result<()> {
+    let path = match url.strip_prefix("file://") {
+        Some(path) => Path::new(path),
+        None if !url.contains(':') => Path::new(url),
+        None => return Ok(()),
+    };
+    if !path.exists() {
+        Repository::init_bare(path)?;
+    }
+    Ok(())
+}
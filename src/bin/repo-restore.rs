@@ -1,21 +1,23 @@
 extern crate clap;
 
-use anyhow::{bail, Context, Error, Result, Ok, anyhow};
+use anyhow::{anyhow, Error, Ok, Result};
 use clap::Parser;
 use colored::*;
-use crossbeam::channel::unbounded;
 use dialoguer::Confirm;
-use git2::{Repository, StatusOptions};
-use indicatif::{ParallelProgressIterator, ProgressBar, ProgressStyle};
-use rayon::prelude::*;
-use repo_utils::repo_project_selector::{
-    find_repo_manifests_folder, find_repo_root_folder, select_projects,
-};
-use std::convert::TryInto;
+use git2::Repository;
+use repo_utils::config::Config;
+use repo_utils::exec;
+use repo_utils::generate;
+use repo_utils::git_status::{query_git_statuses, GitStatus};
+use repo_utils::notify::{self, DirtySummary};
+use repo_utils::paths;
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use repo_utils::workspace::Workspace;
 use std::env;
+use std::ffi::OsStr;
+use std::fs;
 use std::path::PathBuf;
-use std::process::{Command};
-use std::str;
 use std::time::Instant;
 
 /// Restore repos managed by git-repo to the last "repo sync" state,
@@ -23,46 +25,59 @@ use std::time::Instant;
 #[derive(Parser, Debug)]
 #[command(author, version, long_about = None)]
 struct Args {
-    /// change working directory (mostly useful for testing)
-    #[arg(short = 'C', long, value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
-    cwd: Option<std::path::PathBuf>,
-
-    /// ignore projects which are not defined in the given manifest file(s)
-    #[arg(short, long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
-    manifest: Option<Vec<std::path::PathBuf>>,
-
-    /// ignore projects which are not part of the given group(s)
-    #[arg(short, long)]
-    group: Option<Vec<String>>,
+    #[command(flatten)]
+    selection: SelectionArgs,
 
     /// Verbose output, e.g. print local path before executing command
-    #[arg(short, long, default_value = "false")]
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
     verbose: bool,
 
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
     /// Dry-run, only lists "dirty" repositories, does not take any actions
     #[arg(short, long, default_value = "false")]
     dry_run: bool,
 
     /// Additionally delete git .lock files
-    #[arg(short, long, default_value = "false")]
+    #[arg(short = 'l', long, default_value = "false")]
     del_git_lock: bool,
+
+    /// Post a summary of the dirty projects as json to the given webhook (Slack, Teams or any other
+    /// consumer), if there are any. Needs curl in PATH
+    #[arg(long, value_name = "WEBHOOK_URL")]
+    notify: Option<String>,
 }
 
 fn main() -> Result<()> {
-    let args = Args::parse();
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
 
-    if let Some(cwd) = &args.cwd {
-        env::set_current_dir(cwd)?;
+    let (config, workspace, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+    if args.notify.is_some() {
+        notify::check_curl()?;
     }
 
-    let list_of_projects = select_projects(false, args.group.clone(), args.manifest.clone())?;
-    let cmd_context = CmdContext::from(args, list_of_projects)?;
+    let cmd_context = CmdContext::from(args, config, workspace, list_of_projects)?;
 
     println!("Selected {} projects", cmd_context.list_of_projects.len());
 
     let dirty_repos = scan_for_dirty_repos(&cmd_context)?;
 
-    if cmd_context.args.dry_run || dirty_repos.is_empty(){
+    if let (Some(url), false) = (&cmd_context.args.notify, dirty_repos.is_empty()) {
+        let summary = DirtySummary::new(
+            &cmd_context.repo_root_folder,
+            cmd_context.list_of_projects.len(),
+            &dirty_repos,
+        );
+        notify::post(url, &summary)?;
+    }
+
+    if cmd_context.args.dry_run || dirty_repos.is_empty() {
         println!("Nothing to be done, bye");
         return Ok(());
     }
@@ -83,61 +98,25 @@ fn main() -> Result<()> {
 fn scan_for_dirty_repos(cmd_context: &CmdContext) -> Result<Vec<GitStatus>> {
     let timestamp_before_scanning = Instant::now();
 
-    // Create a simple streaming channel
-    let (tx, rx) = unbounded();
-
-    let progress_bar = ProgressBar::new(cmd_context.list_of_projects.len() as u64).with_style(
-        ProgressStyle::default_bar()
-            .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7}")?,
-    );
-
-    let _ = cmd_context.list_of_projects
-        .par_iter()
-        .progress_with(progress_bar)
-        .try_for_each(|path| {
-            let repo = Repository::open(cmd_context.repo_root_folder.join(&path))
-                .with_context(|| format!("Failed to open git repo at {:?}", path))?;
-            if repo.is_bare() {
-                bail!("cannot report status on bare repository");
-            }
-
-            let statuses = repo.statuses(Some(&mut default_status_options()))?;
-
-            let last_repo_sync_tree = repo
-                .find_branch(&cmd_context.sync_branch_name, git2::BranchType::Remote)
-                .map(|b| b.get().peel_to_tree())
-                .with_context(|| format!("{:?}", path))??;
-            let head_tree = repo
-                .head()?
-                .peel_to_tree()
-                .with_context(|| format!("{:?}", path))?;
-
-            let local_deltas =
-                repo.diff_tree_to_tree(Some(&last_repo_sync_tree), Some(&head_tree), None)?;
-
-            let _ = tx.send(GitStatus::new(
-                path,
-                !statuses.is_empty(),
-                local_deltas.deltas().len().try_into().unwrap(),
-            ));
-
-            Ok(())
-        })
-        .expect("Querying status failed");
-
-    let mut repo_statuses: Vec<_> = rx.try_iter().collect();
+    let progress_bar = cmd_context
+        .config
+        .ui
+        .progress_bar(cmd_context.list_of_projects.len())?;
+    let mut repo_statuses = query_git_statuses(
+        &cmd_context.repo_root_folder,
+        &cmd_context.list_of_projects,
+        &cmd_context.sync_branch_name,
+        &progress_bar,
+    )?;
     repo_statuses.sort();
 
-//    let repos_with_uncommited_changes = repo_statuses.iter().fold(0, |sum, gs| if gs.uncomitted_changes {sum + 1} else {sum} );
-//    let repos_with_local_commits = repo_statuses.iter().fold(0, |sum, gs| if gs.local_deltas > 0 {sum + 1} else {sum} );
-
     let mut dirty_repos: Vec<GitStatus> = vec![];
 
     repo_statuses.iter().for_each(|gs| {
-        if gs.uncomitted_changes || gs.local_deltas > 0 {
+        if gs.is_dirty() {
             dirty_repos.push(gs.clone());
         }
-        gs.print(cmd_context.args.verbose);
+        print_status(gs, cmd_context.args.verbose);
     });
 
     println!();
@@ -157,11 +136,15 @@ fn restore_dirty_repos(cmd_context: &CmdContext, dirty_repos: Vec<GitStatus>) ->
         println!("Restoring {}", v.path);
 
         if cmd_context.args.del_git_lock {
-            execute_shell_command(cmd_context, v, format!("rm .git/*.lock || true"))?;
+            delete_git_lock_files(cmd_context, v)?;
         }
 
-        execute_shell_command(cmd_context, v, format!("git clean -fd"))?;
-        execute_shell_command(cmd_context, v, format!("git reset --hard {}", cmd_context.sync_branch_name))
+        execute_git_command(cmd_context, v, &["clean", "-fd"])?;
+        execute_git_command(
+            cmd_context,
+            v,
+            &["reset", "--hard", &cmd_context.sync_branch_name],
+        )
     })?;
 
     println!("Restoring done");
@@ -169,55 +152,51 @@ fn restore_dirty_repos(cmd_context: &CmdContext, dirty_repos: Vec<GitStatus>) ->
     Ok(())
 }
 
-
-fn execute_shell_command(cmd_context: &CmdContext, v: &GitStatus, command: String) -> Result<()>  {
-    let output = Command::new("sh")
-        .current_dir(&cmd_context.repo_root_folder.join(&v.path))
-        .arg("-c")
-        .arg(&command)
+fn execute_git_command(cmd_context: &CmdContext, v: &GitStatus, args: &[&str]) -> Result<()> {
+    let argv: Vec<String> = std::iter::once("git")
+        .chain(args.iter().copied())
+        .map(String::from)
+        .collect();
+    let output = exec::direct_command(&argv)?
+        .current_dir(paths::join(&cmd_context.repo_root_folder, &v.path))
         .output()
         .map_err(Error::msg)?;
 
     match output.status.success() {
         true => Ok(()),
-        false => Err(anyhow!("Failed to execute {} with exit code: {:?}:\n{:?}", command, output.status.code().unwrap_or(0), String::from_utf8_lossy(&output.stderr))),
+        false => Err(anyhow!(
+            "Failed to execute {} with exit code: {:?}:\n{:?}",
+            argv.join(" "),
+            output.status.code().unwrap_or(0),
+            String::from_utf8_lossy(&output.stderr)
+        )),
     }
-
-}
-
-fn default_status_options() -> StatusOptions {
-    let mut opts = StatusOptions::new();
-    opts.include_ignored(false).include_untracked(true);
-    opts
-}
-
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
-struct GitStatus {
-    pub path: String,
-    pub uncomitted_changes: bool,
-    pub local_deltas: i32,
 }
 
-impl GitStatus {
-    pub fn new(path: &str, dirty: bool, local_deltas: i32) -> Self {
-        GitStatus {
-            path: path.to_string(),
-            uncomitted_changes: dirty,
-            local_deltas,
+// equivalent of "rm .git/*.lock"; .git is a symlink to .repo/projects on
+// linux, but a gitfile pointing there or a plain folder elsewhere, so let
+// git tell where the git dir is
+fn delete_git_lock_files(cmd_context: &CmdContext, v: &GitStatus) -> Result<()> {
+    let repo = Repository::open(paths::join(&cmd_context.repo_root_folder, &v.path))?;
+    for entry in fs::read_dir(repo.path())? {
+        let path = entry?.path();
+        if path.is_file() && path.extension() == Some(OsStr::new("lock")) {
+            fs::remove_file(path)?;
         }
     }
+    Ok(())
+}
 
-    pub fn print(&self, verbose: bool) {
-        if self.uncomitted_changes {
-            println!("{}: uncommited changes", self.path.red());
-        }
-        if self.local_deltas > 0 {
-            println!("{}: found local commit(s)", self.path.red());
-        }
+fn print_status(status: &GitStatus, verbose: bool) {
+    if status.uncomitted_changes {
+        println!("{}: uncommited changes", status.path.red());
+    }
+    if status.local_commits > 0 {
+        println!("{}: found local commit(s)", status.path.red());
+    }
 
-        if verbose && !self.uncomitted_changes && self.local_deltas == 0 {
-            println!("{}: clean", self.path.green());
-        }
+    if verbose && !status.is_dirty() {
+        println!("{}: clean", status.path.green());
     }
 }
 
@@ -227,38 +206,26 @@ struct CmdContext {
     sync_branch_name: String,
     repo_root_folder: PathBuf,
     args: Args,
-    list_of_projects: Vec<String>,
+    config: Config,
+    list_of_projects: Vec<ProjectInfo>,
 }
 
 impl CmdContext {
-    pub fn from(args: Args, list_of_projects: Vec<String>) -> Result<CmdContext> {
-
-        let sync_branch_name = lookup_sync_branch_name()?;
-        let repo_root_folder: std::path::PathBuf = find_repo_root_folder()?;
-
-        Ok(CmdContext{sync_branch_name, repo_root_folder, args, list_of_projects})
+    pub fn from(
+        args: Args,
+        config: Config,
+        workspace: Workspace,
+        list_of_projects: Vec<ProjectInfo>,
+    ) -> Result<CmdContext> {
+        let sync_branch_name = workspace.sync_branch_name()?;
+        let repo_root_folder = workspace.root_folder().to_path_buf();
+
+        Ok(CmdContext {
+            sync_branch_name,
+            repo_root_folder,
+            args,
+            config,
+            list_of_projects,
+        })
     }
 }
-// The repo tool maintains a branch tracking the last synced state
-// It is typically named "m/<manifest-branch>" where manifest-branch
-// is the branch used for "repo init".
-fn lookup_sync_branch_name() -> Result<String> {
-    // in .repo/manifests
-    //git for-each-ref --format '%(upstream:lstrip=-1)' "$(git symbolic-ref -q HEAD)"
-
-    let manifests_folder = find_repo_manifests_folder()?;
-
-    Command::new("sh")
-        .current_dir(&manifests_folder)
-        .arg("-c")
-        .arg("git for-each-ref --format '%(upstream:lstrip=-1)' \"$(git symbolic-ref -q HEAD)\"")
-        .output()
-        .map_or_else(
-            |e| bail!(e),
-            |o| match o.status.success() {
-                true => Ok(String::from_utf8_lossy(&o.stdout).into_owned()),
-                false => bail!(String::from_utf8_lossy(&o.stderr).into_owned()),
-            },
-        )
-        .map(|s| "m/".to_string() + s.trim())
-}
@@ -0,0 +1,223 @@
+extern crate clap;
+
+use anyhow::Result;
+use clap::Parser;
+use colored::*;
+use git2::{ObjectType, Oid, Repository, TreeWalkMode, TreeWalkResult};
+use rayon::prelude::*;
+use repo_utils::disk_usage::{disk_usage, disk_usage_excluding, human_readable};
+use repo_utils::generate;
+use repo_utils::progress::Progress;
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Report the disk usage of repos managed by git-repo, see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// instead of the usage per project, list the N largest blobs of all projects
+    #[arg(long, value_name = "N")]
+    top: Option<usize>,
+}
+
+/// disk usage of a project
+#[derive(Default)]
+struct Usage {
+    worktree: u64,
+    git_dir: u64,
+    packs: usize,
+}
+
+/// a blob found in the object database of a project
+struct Blob {
+    size: usize,
+    oid: Oid,
+    project: String,
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, _, list_of_projects) = args.selection.select()?;
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    let progress_bar = config.ui.progress_bar(list_of_projects.len())?;
+    match args.top {
+        Some(n) => largest_blobs(&list_of_projects, n, &progress_bar),
+        None => usage(&list_of_projects, &progress_bar),
+    }
+}
+
+fn usage(list_of_projects: &[ProjectInfo], progress: &dyn Progress) -> Result<()> {
+    progress.start(list_of_projects.len() as u64);
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| {
+            let usage = usage_of(project);
+            progress.inc();
+            usage
+        })
+        .collect();
+    progress.finish();
+
+    let mut usages = vec![];
+    for (project, result) in list_of_projects.iter().zip(results) {
+        match result {
+            Ok(usage) => usages.push((project, usage)),
+            Err(e) => println!("{}: {:#}", project.path.red(), e),
+        }
+    }
+    usages.sort_by_key(|(_, usage)| std::cmp::Reverse(usage.worktree + usage.git_dir));
+
+    println!();
+    println!("{:>10} {:>10} {:>6}  project", "worktree", ".git", "packs");
+    let mut total = Usage::default();
+    for (project, usage) in &usages {
+        println!(
+            "{:>10} {:>10} {:>6}  {}",
+            human_readable(usage.worktree),
+            human_readable(usage.git_dir),
+            usage.packs,
+            project.path
+        );
+        total.worktree += usage.worktree;
+        total.git_dir += usage.git_dir;
+        total.packs += usage.packs;
+    }
+    println!(
+        "{:>10} {:>10} {:>6}  {}",
+        human_readable(total.worktree),
+        human_readable(total.git_dir),
+        total.packs,
+        format!("total of {} projects", usages.len()).bold()
+    );
+    Ok(())
+}
+
+fn usage_of(project: &ProjectInfo) -> Result<Usage> {
+    let git_dir = Repository::open(&project.absolute_path)?
+        .path()
+        .to_path_buf();
+    Ok(Usage {
+        worktree: disk_usage_excluding(&project.absolute_path, &[".git"])?,
+        git_dir: disk_usage(&git_dir)?,
+        packs: count_packs(&git_dir.join("objects/pack")),
+    })
+}
+
+fn count_packs(pack_folder: &Path) -> usize {
+    fs::read_dir(pack_folder)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().extension().is_some_and(|ext| ext == "pack"))
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+fn largest_blobs(
+    list_of_projects: &[ProjectInfo],
+    n: usize,
+    progress: &dyn Progress,
+) -> Result<()> {
+    progress.start(list_of_projects.len() as u64);
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| {
+            let blobs = largest_blobs_of(project, n);
+            progress.inc();
+            blobs
+        })
+        .collect();
+    progress.finish();
+
+    let mut blobs = vec![];
+    for (project, result) in list_of_projects.iter().zip(results) {
+        match result {
+            Ok(project_blobs) => blobs.extend(project_blobs),
+            Err(e) => println!("{}: {:#}", project.path.red(), e),
+        }
+    }
+    blobs.sort_by_key(|b| std::cmp::Reverse(b.size));
+    blobs.truncate(n);
+
+    println!();
+    for blob in &blobs {
+        let project = list_of_projects
+            .iter()
+            .find(|p| p.path == blob.project)
+            .expect("blob of a selected project");
+        let path = path_in_head(project, blob.oid).unwrap_or_else(|| "(history only)".to_string());
+        println!(
+            "{:>10}  {:.7}  {}/{}",
+            human_readable(blob.size as u64),
+            blob.oid,
+            blob.project,
+            path
+        );
+    }
+    Ok(())
+}
+
+/// the `n` largest blobs in the object database of the project
+fn largest_blobs_of(project: &ProjectInfo, n: usize) -> Result<Vec<Blob>> {
+    let repo = Repository::open(&project.absolute_path)?;
+    let odb = repo.odb()?;
+
+    let mut blobs = vec![];
+    let mut error = None;
+    odb.foreach(|oid| {
+        match odb.read_header(*oid) {
+            Ok((size, ObjectType::Blob)) => blobs.push((size, *oid)),
+            Ok(_) => {}
+            Err(e) => {
+                error = Some(e);
+                return false;
+            }
+        }
+        true
+    })?;
+    if let Some(error) = error {
+        return Err(error.into());
+    }
+
+    // objects might be stored loose and packed at the same time
+    blobs.sort_by(|a, b| b.cmp(a));
+    blobs.dedup();
+    blobs.truncate(n);
+    Ok(blobs
+        .into_iter()
+        .map(|(size, oid)| Blob {
+            size,
+            oid,
+            project: project.path.clone(),
+        })
+        .collect())
+}
+
+/// path of the blob within the tree checked out, if it is part of it
+fn path_in_head(project: &ProjectInfo, oid: Oid) -> Option<String> {
+    let repo = Repository::open(&project.absolute_path).ok()?;
+    let tree = repo.head().ok()?.peel_to_tree().ok()?;
+    let mut path = None;
+    tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+        if entry.id() == oid {
+            path = Some(format!("{}{}", root, entry.name().unwrap_or_default()));
+            return TreeWalkResult::Abort;
+        }
+        TreeWalkResult::Ok
+    })
+    .ok();
+    path
+}
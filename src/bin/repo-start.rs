@@ -0,0 +1,163 @@
+extern crate clap;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::*;
+use git2::{BranchType, Repository};
+use rayon::prelude::*;
+use repo_utils::generate;
+use repo_utils::git_ops;
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use std::env;
+
+/// Start a topic branch in repos managed by git-repo, like "repo start",
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Verbose output, e.g. also list projects which were skipped
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
+
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    /// only start the branch in projects with local changes or commits on top of the last "repo sync"
+    #[arg(long, default_value = "false")]
+    touched: bool,
+
+    /// create the branch at HEAD instead of the last "repo sync" state, keeping local commits
+    #[arg(long, default_value = "false")]
+    head: bool,
+
+    /// name of the branch
+    branch: String,
+}
+
+/// what happened in a project
+enum Outcome {
+    Started(String),
+    /// the branch existed already and was checked out
+    Existed(String),
+    /// the project wasn't touched
+    Skipped,
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, workspace, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+
+    let sync_branch_name = workspace.sync_branch_name()?;
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| start(project, &sync_branch_name, &args))
+        .collect();
+
+    let mut started = 0;
+    let mut existed = vec![];
+    let mut failed = 0;
+    for (project, result) in list_of_projects.iter().zip(results) {
+        match result {
+            Ok(Outcome::Started(output)) => {
+                started += 1;
+                print!("{}: {}", project.path.green(), output);
+            }
+            Ok(Outcome::Existed(output)) => {
+                existed.push(project.path.as_str());
+                print!("{}: already exists, {}", project.path.yellow(), output);
+            }
+            Ok(Outcome::Skipped) => {
+                if args.verbose {
+                    println!("{}: untouched, skipped", project.path.green());
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                println!("{}: {}", project.path.red(), e);
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "Started branch {} in {}/{} projects",
+        args.branch,
+        started,
+        list_of_projects.len()
+    );
+    if !existed.is_empty() {
+        println!("Already existed in: {}", existed.join(", "));
+    }
+
+    if failed > 0 {
+        bail!("failed in {} projects", failed);
+    }
+    Ok(())
+}
+
+fn start(
+    project: &ProjectInfo,
+    sync_branch_name: &str,
+    args: &Args,
+) -> repo_utils::Result<Outcome> {
+    let repo = Repository::open(&project.absolute_path)?;
+    let name = &args.branch;
+
+    if args.touched && !is_touched(&repo, sync_branch_name)? {
+        return Ok(Outcome::Skipped);
+    }
+    if repo.find_branch(name, BranchType::Local).is_ok() {
+        return Ok(Outcome::Existed(git_ops::checkout(&repo, name)?));
+    }
+
+    let output = match args.head {
+        true => {
+            repo.branch(name, &repo.head()?.peel_to_commit()?, false)?;
+            git_ops::checkout(&repo, name)?
+        }
+        false => git_ops::start_branch(&repo, name, sync_branch_name)?,
+    };
+    if let (Some(remote), Some(revision)) = (&project.remote, upstream_revision(project)) {
+        git_ops::set_upstream(&repo, name, remote, revision)?;
+    }
+    Ok(Outcome::Started(output))
+}
+
+/// true if the project has local changes or commits on top of the sync branch
+fn is_touched(repo: &Repository, sync_branch_name: &str) -> repo_utils::Result<bool> {
+    if !git_ops::status(repo)?.is_empty() {
+        return Ok(true);
+    }
+    let head = repo.head()?.peel_to_commit()?.id();
+    let sync = repo
+        .find_branch(sync_branch_name, BranchType::Remote)?
+        .get()
+        .peel_to_commit()?
+        .id();
+    let (ahead, _) = repo.graph_ahead_behind(head, sync)?;
+    Ok(ahead > 0)
+}
+
+/// the branch tracked by the new branch: the revision of the project,
+/// or its upstream if the revision is pinned to a sha
+fn upstream_revision(project: &ProjectInfo) -> Option<&str> {
+    project
+        .revision
+        .as_ref()
+        .filter(|r| !git_ops::is_sha(r))
+        .or(project.upstream.as_ref())
+        .map(|r| r.as_str())
+}
@@ -0,0 +1,112 @@
+extern crate clap;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use colored::*;
+use git2::Repository;
+use rayon::prelude::*;
+use repo_utils::apply::apply_to_repos;
+use repo_utils::generate;
+use repo_utils::git_ops;
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use std::env;
+
+/// Stash local changes of repos managed by git-repo, list and pop the stashes again,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Verbose output, e.g. also list projects without anything to stash
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
+
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    #[command(subcommand)]
+    action: Action,
+}
+
+#[derive(Subcommand, Debug)]
+enum Action {
+    /// stash the local changes of all projects having any
+    Save {
+        /// message describing the stash
+        #[arg(short, long)]
+        message: Option<String>,
+
+        /// stash untracked files as well
+        #[arg(short = 'u', long, default_value = "false")]
+        include_untracked: bool,
+    },
+    /// list the stashes of all projects
+    List,
+    /// apply and drop the latest stash of all projects having any
+    Pop,
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, _, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    let skipped = args.verbose.then_some("nothing to do");
+    match &args.action {
+        Action::Save {
+            message,
+            include_untracked,
+        } => apply_to_repos(&list_of_projects, "Stashed changes", skipped, |repo| {
+            git_ops::stash_save(repo, message.as_deref(), *include_untracked)
+        })
+        .map_err(Into::into),
+        Action::List => list(&list_of_projects),
+        Action::Pop => apply_to_repos(&list_of_projects, "Popped stash", skipped, |repo| {
+            git_ops::stash_pop(repo)
+        })
+        .map_err(Into::into),
+    }
+}
+
+fn list(list_of_projects: &[ProjectInfo]) -> Result<()> {
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| {
+            let mut repo = Repository::open(&project.absolute_path)?;
+            git_ops::stash_list(&mut repo)
+        })
+        .collect();
+
+    let mut stashed = 0;
+    for (project, result) in list_of_projects.iter().zip(results) {
+        match result {
+            Ok(stashes) if stashes.is_empty() => {}
+            Ok(stashes) => {
+                stashed += 1;
+                println!("{}", project.path.yellow());
+                for stash in stashes {
+                    println!("  {}", stash);
+                }
+            }
+            Err(e) => println!("{}: {}", project.path.red(), e),
+        }
+    }
+
+    println!();
+    println!(
+        "{}/{} projects have stashes",
+        stashed,
+        list_of_projects.len()
+    );
+    Ok(())
+}
@@ -1,110 +1,194 @@
 extern crate clap;
 
-use anyhow::{bail, Context, Result};
+use anyhow::{Context, Result};
 use clap::Parser;
 use colored::*;
-use crossbeam::channel::unbounded;
-use git2::{Repository, StatusOptions};
-use indicatif::{ParallelProgressIterator, ProgressBar, ProgressStyle};
 use rayon::prelude::*;
-use repo_utils::repo_project_selector::{find_repo_root_folder, select_projects, find_repo_manifests_folder};
-use std::convert::TryInto;
-use std::{env};
-use std::process::{Command};
-use std::str;
-use std::time::Instant;
+use repo_utils::config::Config;
+use repo_utils::generate;
+use repo_utils::git_status::{
+    query_git_status_with_branch, query_git_statuses, query_git_statuses_with_branch, GitStatus,
+};
+use repo_utils::json_lines;
+use repo_utils::notify::{self, DirtySummary};
+use repo_utils::pager;
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use repo_utils::workspace::Workspace;
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 /// Check if repos managed by git-repo have local-only or uncommited changes,
 /// see https://github.com/elektronenhirn/repo-utils
 #[derive(Parser, Debug)]
 #[command(author, version, long_about = None)]
 struct Args {
-    /// change working directory (mostly useful for testing)
-    #[arg(short = 'C', long, value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
-    cwd: Option<std::path::PathBuf>,
+    #[command(flatten)]
+    selection: SelectionArgs,
 
-    /// ignore projects which are not defined in the given manifest file(s)
-    #[arg(short, long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
-    manifest: Option<Vec<std::path::PathBuf>>,
+    /// Verbose output, e.g. print local path before executing command
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
 
-    /// ignore projects which are not part of the given group(s)
-    #[arg(short, long)]
-    group: Option<Vec<String>>,
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
 
-    /// Verbose output, e.g. print local path before executing command
+    /// Print the annotations (as defined in the manifest) of the listed projects
     #[arg(short, long, default_value = "false")]
-    verbose: bool,
+    annotations: bool,
+
+    /// Print the status of each project as soon as it is known, as one json object
+    /// {path, uncommitted_changes, local_commits, branch, ahead, behind} per line
+    #[arg(long, default_value = "false", conflicts_with = "annotations")]
+    jsonl: bool,
+
+    /// Post a summary of the dirty projects as json to the given webhook (Slack, Teams or any other
+    /// consumer), if there are any. Needs curl in PATH
+    #[arg(long, value_name = "WEBHOOK_URL")]
+    notify: Option<String>,
+
+    /// Additionally record the status of the projects as a snapshot in the given SQLite database,
+    /// which is created if missing
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    sqlite: Option<PathBuf>,
+
+    /// Additionally write metrics in the Prometheus text format into the given file, e.g. for the
+    /// textfile collector of the node exporter
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    metrics: Option<PathBuf>,
+
+    /// Additionally write the status of each project as one row into the given CSV file, with the
+    /// same columns as --jsonl
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    csv: Option<PathBuf>,
+
+    /// Don't pipe the output through $PAGER, even if it is printed to a terminal
+    #[arg(long, default_value = "false")]
+    no_pager: bool,
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+/// tables of the SQLite database written with --sqlite, every run of
+/// repo-status adds one row to runs and one row per project to statuses
+const SQLITE_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    scanned_at INTEGER NOT NULL, -- seconds since the unix epoch
+    workspace TEXT NOT NULL,
+    sync_branch TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS projects (
+    id INTEGER PRIMARY KEY,
+    path TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS statuses (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    uncommitted_changes INTEGER NOT NULL,
+    local_commits INTEGER NOT NULL,
+    branch TEXT, -- NULL if HEAD is detached
+    ahead INTEGER NOT NULL,
+    behind INTEGER NOT NULL,
+    PRIMARY KEY (run_id, project_id)
+);
+";
 
-    if let Some(cwd) = args.cwd {
-        env::set_current_dir(cwd)?;
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
     }
+    let mut args = Args::parse();
 
-    let list_of_projects = select_projects(false, args.group, args.manifest)?;
+    let (config, workspace, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+    if args.notify.is_some() {
+        notify::check_curl()?;
+    }
 
-    println!("Selected {} projects", list_of_projects.len());
+    let timestamp_before_scanning = Instant::now();
+    let statuses = match args.jsonl {
+        // keep stdout clean for the json output
+        true => {
+            eprintln!("Selected {} projects", list_of_projects.len());
+            stream_status(&workspace, &list_of_projects)?
+        }
+        false => {
+            println!("Selected {} projects", list_of_projects.len());
+            status(
+                &workspace,
+                &list_of_projects,
+                args.verbose,
+                args.annotations,
+                args.no_pager,
+                args.csv.is_some() || args.sqlite.is_some(),
+                &config,
+            )?
+        }
+    };
 
-    status(list_of_projects, args.verbose)
+    if let Some(file) = &args.metrics {
+        let duration = timestamp_before_scanning.elapsed();
+        write_metrics(file, &workspace, &statuses, duration)
+            .with_context(|| format!("Failed to write {:?}", file))?;
+    }
+    if let Some(file) = &args.sqlite {
+        export_sqlite(file, &workspace, &list_of_projects, &statuses)
+            .with_context(|| format!("Failed to write {:?}", file))?;
+    }
+    if let Some(file) = &args.csv {
+        write_csv(file, &statuses).with_context(|| format!("Failed to write {:?}", file))?;
+    }
+    notify_if_dirty(args.notify.as_deref(), &workspace, &statuses)
 }
 
-fn status(list_of_projects: Vec<String>, verbose: bool) -> Result<()> {
+fn status(
+    workspace: &Workspace,
+    list_of_projects: &[ProjectInfo],
+    verbose: bool,
+    annotations: bool,
+    no_pager: bool,
+    with_branch: bool,
+    config: &Config,
+) -> Result<Vec<GitStatus>> {
     let timestamp_before_scanning = Instant::now();
 
-    let sync_branch_name = lookup_sync_branch_name()?;
-
-    // Create a simple streaming channel
-    let (tx, rx) = unbounded();
-
-    let progress_bar = ProgressBar::new(list_of_projects.len() as u64).with_style(
-        ProgressStyle::default_bar()
-            .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7}")?,
-    );
-
-    let repo_root_folder = find_repo_root_folder()?;
-
-    let _ = list_of_projects
-        .par_iter()
-        .progress_with(progress_bar)
-        .try_for_each(|path| {
-            let repo = Repository::open(repo_root_folder.join(&path))
-                .with_context(|| format!("Failed to open git repo at {:?}", path))?;
-            if repo.is_bare() {
-                bail!("cannot report status on bare repository");
-            }
-
-            let statuses = repo.statuses(Some(&mut default_status_options()))?;
-            
-            let last_repo_sync_tree = repo.find_branch(&sync_branch_name, git2::BranchType::Remote).map(|b| b.get().peel_to_tree()).with_context(|| format!("{:?}", path))??;
-            let head_tree = repo.head()?.peel_to_tree().with_context(|| format!("{:?}", path))?;
-
-            let local_commits = repo.diff_tree_to_tree(
-                Some(&last_repo_sync_tree),
-                Some(&head_tree),
-                None
-            )?;
-
-            let _ = tx.send(GitStatus::new(path, !statuses.is_empty(),local_commits.deltas().len().try_into().unwrap()));
-
-            Ok(())
-        })
-        .expect("Querying status failed");
-
+    let sync_branch_name = workspace.sync_branch_name()?;
+
+    let progress_bar = config.ui.progress_bar(list_of_projects.len())?;
+    // the branches are exported only, but not printed
+    let query = match with_branch {
+        true => query_git_statuses_with_branch,
+        false => query_git_statuses,
+    };
+    let statuses = query(
+        workspace.root_folder(),
+        list_of_projects,
+        &sync_branch_name,
+        &progress_bar,
+    )?;
+
+    let _pager = pager::start(no_pager)?;
     let mut dirty = 0;
     let mut local_commits = 0;
-    let mut repo_statuses: Vec<_> = rx.try_iter().collect();
-    repo_statuses.sort();
+    let mut repo_statuses: Vec<_> = statuses.into_iter().zip(list_of_projects).collect();
+    repo_statuses.sort_by(|(a, _), (b, _)| a.cmp(b));
 
-    repo_statuses.iter().for_each(|v| {
+    repo_statuses.iter().for_each(|(v, project)| {
         if v.uncomitted_changes {
             dirty += 1;
         }
         if v.local_commits > 0 {
             local_commits += 1;
         }
-        v.print(verbose);
+        print_status(v, verbose);
+        if annotations && (verbose || v.is_dirty()) {
+            print_annotations(project);
+        }
     });
 
     println!();
@@ -117,65 +201,192 @@ fn status(list_of_projects: Vec<String>, verbose: bool) -> Result<()> {
         list_of_projects.len(),
     );
 
-    Ok(())
+    Ok(repo_statuses.into_iter().map(|(s, _)| s).collect())
 }
 
-fn default_status_options() -> StatusOptions {
-    let mut opts = StatusOptions::new();
-    opts.include_ignored(false).include_untracked(true);
-    opts
+/// prints the status of each project as json line as soon as it is known,
+/// in the order the projects are done
+fn stream_status(
+    workspace: &Workspace,
+    list_of_projects: &[ProjectInfo],
+) -> Result<Vec<GitStatus>> {
+    let timestamp_before_scanning = Instant::now();
+
+    let sync_branch_name = workspace.sync_branch_name()?;
+    let statuses = list_of_projects
+        .par_iter()
+        .map(|project| {
+            let status = query_git_status_with_branch(
+                workspace.root_folder(),
+                &project.path,
+                &sync_branch_name,
+            )?;
+            json_lines::print(&status)?;
+            Ok(status)
+        })
+        .collect::<repo_utils::Result<Vec<_>>>()?;
+
+    eprintln!(
+        "Finished in {}s: {}/{} git repos dirty",
+        timestamp_before_scanning.elapsed().as_secs(),
+        statuses.iter().filter(|s| s.is_dirty()).count(),
+        list_of_projects.len(),
+    );
+    Ok(statuses)
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord)]
-struct GitStatus {
-    pub path: String,
-    pub uncomitted_changes: bool,
-    pub local_commits: i32
+/// writes the metrics to a temporary file first, which then replaces
+/// `file`, so a collector never reads a partially written file
+fn write_metrics(
+    file: &Path,
+    workspace: &Workspace,
+    statuses: &[GitStatus],
+    duration: Duration,
+) -> Result<()> {
+    let workspace_label = workspace
+        .root_folder()
+        .display()
+        .to_string()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"");
+    let gauges = [
+        (
+            "repo_projects_total",
+            "Number of projects scanned",
+            statuses.len() as f64,
+        ),
+        (
+            "repo_dirty_total",
+            "Number of projects with uncommitted changes",
+            statuses.iter().filter(|s| s.uncomitted_changes).count() as f64,
+        ),
+        (
+            "repo_local_commits_total",
+            "Number of projects with commits deviating from the last repo sync",
+            statuses.iter().filter(|s| s.local_commits > 0).count() as f64,
+        ),
+        (
+            "scan_duration_seconds",
+            "Time it took to scan the projects",
+            duration.as_secs_f64(),
+        ),
+    ];
+
+    let mut metrics = String::new();
+    for (name, help, value) in gauges {
+        metrics += &format!("# HELP {} {}\n", name, help);
+        metrics += &format!("# TYPE {} gauge\n", name);
+        metrics += &format!("{}{{workspace=\"{}\"}} {}\n", name, workspace_label, value);
+    }
+
+    let mut temporary = file.as_os_str().to_owned();
+    temporary.push(".tmp");
+    fs::write(&temporary, metrics)?;
+    fs::rename(&temporary, file)?;
+    Ok(())
 }
 
-impl GitStatus {
-    pub fn new(path: &str, dirty: bool, local_commits: i32) -> Self {
-        GitStatus {
-            path: path.to_string(),
-            uncomitted_changes: dirty,
-            local_commits,
-        }
+fn write_csv(file: &Path, statuses: &[GitStatus]) -> Result<()> {
+    let mut csv = String::from("path,uncommitted_changes,local_commits,branch,ahead,behind\n");
+    for s in statuses {
+        csv += &format!(
+            "\"{}\",{},{},\"{}\",{},{}\n",
+            s.path.replace('"', "\"\""),
+            s.uncomitted_changes,
+            s.local_commits,
+            s.branch.as_deref().unwrap_or_default().replace('"', "\"\""),
+            s.ahead,
+            s.behind
+        );
     }
+    fs::write(file, csv)?;
+    Ok(())
+}
 
-    pub fn print(&self, verbose: bool) {
-        if self.uncomitted_changes {
-            println!("{}: uncommited changes", self.path.red());
-        }
-        if self.local_commits > 0 {
-            println!("{}: {} local commits", self.path.red(), self.local_commits);
-        } 
-        
-        if verbose && !self.uncomitted_changes && self.local_commits == 0{
-            println!("{}: clean", self.path.green());
-        }
+/// appends the statuses as snapshot of this run to the SQLite database
+/// at `file`, so trends can be queried across runs
+fn export_sqlite(
+    file: &Path,
+    workspace: &Workspace,
+    list_of_projects: &[ProjectInfo],
+    statuses: &[GitStatus],
+) -> Result<()> {
+    let names: HashMap<&str, &str> = list_of_projects
+        .iter()
+        .map(|p| (p.path.as_str(), p.name.as_str()))
+        .collect();
+    let scanned_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+
+    let mut db = Connection::open(file)?;
+    db.execute_batch(SQLITE_SCHEMA)?;
+    let tx = db.transaction()?;
+    tx.execute(
+        "INSERT INTO runs (scanned_at, workspace, sync_branch) VALUES (?1, ?2, ?3)",
+        params![
+            scanned_at,
+            workspace.root_folder().display().to_string(),
+            workspace.sync_branch_name()?
+        ],
+    )?;
+    let run_id = tx.last_insert_rowid();
+    for status in statuses {
+        let path = status.path.as_str();
+        let name = names.get(path).copied().unwrap_or(path);
+        let project_id: i64 = tx.query_row(
+            "INSERT INTO projects (path, name) VALUES (?1, ?2)
+             ON CONFLICT (path) DO UPDATE SET name = excluded.name
+             RETURNING id",
+            params![status.path, name],
+            |row| row.get(0),
+        )?;
+        tx.execute(
+            "INSERT INTO statuses
+                 (run_id, project_id, uncommitted_changes, local_commits, branch, ahead, behind)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                run_id,
+                project_id,
+                status.uncomitted_changes,
+                status.local_commits,
+                status.branch,
+                status.ahead as i64,
+                status.behind as i64
+            ],
+        )?;
     }
+    tx.commit()?;
+    Ok(())
 }
 
-// The repo tool maintains a branch tracking the last synced state
-// It is typically named "m/<manifest-branch>" where manifest-branch
-// is the branch used for "repo init".
-fn lookup_sync_branch_name() -> Result<String> {
-    // in .repo/manifests
-    //git for-each-ref --format '%(upstream:lstrip=-1)' "$(git symbolic-ref -q HEAD)"
-
-    let manifests_folder = find_repo_manifests_folder()?;
-
-    Command::new("sh")
-            .current_dir(&manifests_folder)
-            .arg("-c")
-            .arg("git for-each-ref --format '%(upstream:lstrip=-1)' \"$(git symbolic-ref -q HEAD)\"")
-            .output()
-            .map_or_else(|e| bail!(e), |o| {
-                match o.status.success() {
-                    true => Ok(String::from_utf8_lossy(&o.stdout).into_owned()),
-                    false => bail!(String::from_utf8_lossy(&o.stderr).into_owned())
-                }
-            })
-            .map(|s| "m/".to_string() + s.trim())
+/// posts the dirty ones of `statuses` to the webhook, if any
+fn notify_if_dirty(url: Option<&str>, workspace: &Workspace, statuses: &[GitStatus]) -> Result<()> {
+    let dirty: Vec<GitStatus> = statuses.iter().filter(|s| s.is_dirty()).cloned().collect();
+    if let (Some(url), false) = (url, dirty.is_empty()) {
+        let summary = DirtySummary::new(workspace.root_folder(), statuses.len(), &dirty);
+        notify::post(url, &summary)?;
+    }
+    Ok(())
 }
 
+fn print_status(status: &GitStatus, verbose: bool) {
+    if status.uncomitted_changes {
+        println!("{}: uncommited changes", status.path.red());
+    }
+    if status.local_commits > 0 {
+        println!(
+            "{}: {} local commits",
+            status.path.red(),
+            status.local_commits
+        );
+    }
+
+    if verbose && !status.is_dirty() {
+        println!("{}: clean", status.path.green());
+    }
+}
+
+fn print_annotations(project: &ProjectInfo) {
+    for (name, value) in &project.annotations {
+        println!("    {}: {}", name, value);
+    }
+}
@@ -0,0 +1,164 @@
+extern crate clap;
+
+use anyhow::{bail, Result};
+use clap::{Parser, Subcommand};
+use colored::*;
+use git2::Repository;
+use rayon::prelude::*;
+use repo_utils::apply::apply_to_repos;
+use repo_utils::generate;
+use repo_utils::git_ops;
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use std::env;
+
+/// Create, delete, verify and list tags of repos managed by git-repo,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Verbose output, e.g. also list projects the action didn't apply to
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
+
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    #[command(subcommand)]
+    action: Action,
+}
+
+#[derive(Subcommand, Debug)]
+enum Action {
+    /// create an annotated tag at HEAD of all projects
+    Create {
+        /// name of the tag
+        name: String,
+
+        /// message of the tag, defaults to its name
+        #[arg(short, long)]
+        message: Option<String>,
+
+        /// create a signed tag, using git and its gpg configuration
+        #[arg(short, long, default_value = "false")]
+        sign: bool,
+    },
+    /// delete the tag from all projects having it
+    Delete {
+        /// name of the tag
+        name: String,
+    },
+    /// verify that all projects have the tag
+    Verify {
+        /// name of the tag
+        name: String,
+    },
+    /// list the tags of all projects
+    List {
+        /// only list tags matching the glob, e.g. "v1.*"
+        pattern: Option<String>,
+    },
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, _, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    let skipped = args.verbose.then_some("no such tag");
+    match &args.action {
+        Action::Create {
+            name,
+            message,
+            sign,
+        } => apply_to_repos(&list_of_projects, "Created tag", skipped, |repo| {
+            let message = message.as_deref().unwrap_or(name);
+            git_ops::create_tag(repo, name, message, *sign).map(Some)
+        })
+        .map_err(Into::into),
+        Action::Delete { name } => {
+            apply_to_repos(&list_of_projects, "Deleted tag", skipped, |repo| {
+                git_ops::delete_tag(repo, name)
+            })
+            .map_err(Into::into)
+        }
+        Action::Verify { name } => verify(&list_of_projects, name, args.verbose),
+        Action::List { pattern } => list(&list_of_projects, pattern.as_deref()),
+    }
+}
+
+fn verify(list_of_projects: &[ProjectInfo], name: &str, verbose: bool) -> Result<()> {
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| {
+            let repo = Repository::open(&project.absolute_path)?;
+            git_ops::tags(&repo, Some(name))
+        })
+        .collect();
+
+    let mut missing = 0;
+    for (project, result) in list_of_projects.iter().zip(results) {
+        match result {
+            Ok(tags) if tags.iter().any(|t| t == name) => {
+                if verbose {
+                    println!("{}: tagged", project.path.green());
+                }
+            }
+            Ok(_) => {
+                missing += 1;
+                println!("{}: tag {} is missing", project.path.red(), name);
+            }
+            Err(e) => {
+                missing += 1;
+                println!("{}: {}", project.path.red(), e);
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "Found tag {} in {}/{} projects",
+        name,
+        list_of_projects.len() - missing,
+        list_of_projects.len()
+    );
+
+    if missing > 0 {
+        bail!("tag {} is missing in {} projects", name, missing);
+    }
+    Ok(())
+}
+
+fn list(list_of_projects: &[ProjectInfo], pattern: Option<&str>) -> Result<()> {
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| {
+            let repo = Repository::open(&project.absolute_path)?;
+            git_ops::tags(&repo, pattern)
+        })
+        .collect();
+
+    for (project, result) in list_of_projects.iter().zip(results) {
+        match result {
+            Ok(tags) if tags.is_empty() => {}
+            Ok(tags) => {
+                println!("{}", project.path.yellow());
+                for tag in tags {
+                    println!("  {}", tag);
+                }
+            }
+            Err(e) => println!("{}: {}", project.path.red(), e),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,200 @@
+extern crate clap;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use colored::*;
+use git2::Repository;
+use rayon::prelude::*;
+use repo_utils::exec;
+use repo_utils::generate;
+use repo_utils::git_ops;
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use std::env;
+
+/// Upload the local commits of repos managed by git-repo to Gerrit for review,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Verbose output, e.g. print the output of git push, and projects without local commits
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
+
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    /// topic of the uploaded changes
+    #[arg(short, long)]
+    topic: Option<String>,
+
+    /// add the given reviewer(s), by email or username
+    #[arg(short, long, value_name = "REVIEWER")]
+    reviewer: Option<Vec<String>>,
+
+    /// upload the changes as work in progress
+    #[arg(long, default_value = "false")]
+    wip: bool,
+
+    /// do everything except actually pushing
+    #[arg(short = 'n', long, default_value = "false")]
+    dry_run: bool,
+}
+
+/// the result of uploading a project
+enum Upload {
+    /// HEAD has no commits on top of the last "repo sync"
+    NothingToUpload,
+    Uploaded {
+        commits: usize,
+        target: String,
+        output: String,
+    },
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, workspace, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+
+    let sync_branch_name = workspace.sync_branch_name()?;
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| upload(project, &sync_branch_name, &args))
+        .collect();
+
+    let mut uploaded = 0;
+    let mut failed = 0;
+    for (project, result) in list_of_projects.iter().zip(results) {
+        match result {
+            Ok(Upload::NothingToUpload) => {
+                if args.verbose {
+                    println!("{}: nothing to upload", project.path.green());
+                }
+            }
+            Ok(Upload::Uploaded {
+                commits,
+                target,
+                output,
+            }) => {
+                uploaded += 1;
+                println!(
+                    "{}: {} commits uploaded to {}",
+                    project.path.green(),
+                    commits,
+                    target
+                );
+                if args.verbose {
+                    print!("{}", output);
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                println!("{}: {:#}", project.path.red(), e);
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "Uploaded {}/{} projects{}",
+        uploaded,
+        list_of_projects.len(),
+        match args.dry_run {
+            true => " (dry-run)",
+            false => "",
+        }
+    );
+
+    if failed > 0 {
+        bail!("upload failed in {} projects", failed);
+    }
+    Ok(())
+}
+
+/// pushes HEAD to refs/for/<dest-branch> of the project's remote, like
+/// `repo upload` does, if it has commits on top of the sync branch
+fn upload(project: &ProjectInfo, sync_branch_name: &str, args: &Args) -> Result<Upload> {
+    let repo = Repository::open(&project.absolute_path)?;
+    let head = repo.head()?.peel_to_commit()?.id();
+    let sync = repo
+        .revparse_single(&format!("refs/remotes/{}", sync_branch_name))?
+        .peel_to_commit()?
+        .id();
+    let (commits, _) = repo.graph_ahead_behind(head, sync)?;
+    if commits == 0 {
+        return Ok(Upload::NothingToUpload);
+    }
+
+    let remote = match &project.remote {
+        Some(remote) => remote,
+        None => bail!("no remote defined in the manifest"),
+    };
+    let dest_branch = match dest_branch(project) {
+        Some(dest_branch) => dest_branch,
+        None => bail!("no revision or dest-branch defined in the manifest"),
+    };
+    let target = format!("refs/for/{}{}", dest_branch, push_options(args));
+
+    let mut argv = vec!["git".to_string(), "push".to_string()];
+    if args.dry_run {
+        argv.push("--dry-run".to_string());
+    }
+    argv.push(remote.clone());
+    argv.push(format!("HEAD:{}", target));
+
+    let result = exec::direct_command(&argv)?
+        .current_dir(&project.absolute_path)
+        .output()?;
+    let mut output = String::from_utf8_lossy(&result.stdout).to_string();
+    output += &String::from_utf8_lossy(&result.stderr);
+    if !result.status.success() {
+        bail!("{} failed: {}", argv.join(" "), output.trim());
+    }
+
+    Ok(Upload::Uploaded {
+        commits,
+        target,
+        output,
+    })
+}
+
+/// the branch changes are reviewed for, like `repo upload` picks it: the
+/// dest-branch, or the revision if that is a branch, or the upstream
+fn dest_branch(project: &ProjectInfo) -> Option<String> {
+    let branch = project
+        .dest_branch
+        .as_ref()
+        .or_else(|| project.revision.as_ref().filter(|r| !git_ops::is_sha(r)))
+        .or(project.upstream.as_ref())?;
+    Some(branch.trim_start_matches("refs/heads/").to_string())
+}
+
+/// Gerrit's push options, e.g. "%topic=foo,r=alice@example.com,wip"
+fn push_options(args: &Args) -> String {
+    let mut options = vec![];
+    if let Some(topic) = &args.topic {
+        options.push(format!("topic={}", topic));
+    }
+    for reviewer in args.reviewer.iter().flatten() {
+        options.push(format!("r={}", reviewer));
+    }
+    if args.wip {
+        options.push("wip".to_string());
+    }
+    match options.is_empty() {
+        true => String::new(),
+        false => format!("%{}", options.join(",")),
+    }
+}
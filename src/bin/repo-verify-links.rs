@@ -0,0 +1,141 @@
+extern crate clap;
+
+use anyhow::Result;
+use clap::Parser;
+use colored::*;
+use repo_utils::generate;
+use repo_utils::paths;
+use repo_utils::repo_project_selector::FileOperation;
+use repo_utils::selection::SelectionArgs;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+/// Verify the copyfile and linkfile elements of the manifest: copies have
+/// to match their sources, links have to point to them,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Verbose output, e.g. also list copies and links which are fine
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
+
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, workspace, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    let repo_root_folder = workspace.root_folder();
+    let manifest = workspace.manifest()?;
+
+    let mut checked = 0;
+    let mut problems = 0;
+    for project in list_of_projects
+        .iter()
+        .filter_map(|info| manifest.find_project(&info.path))
+    {
+        let project_folder = paths::join(repo_root_folder, &project.path);
+        let operations = project
+            .copyfiles
+            .iter()
+            .map(|c| (c, verify_copy(&project_folder, repo_root_folder, c)))
+            .chain(
+                project
+                    .linkfiles
+                    .iter()
+                    .map(|l| (l, verify_link(&project_folder, repo_root_folder, l))),
+            );
+
+        for (operation, result) in operations {
+            checked += 1;
+            match result {
+                Ok(()) => {
+                    if args.verbose {
+                        println!("{}: {} is up to date", project.path.green(), operation.dest);
+                    }
+                }
+                Err(problem) => {
+                    problems += 1;
+                    println!("{}: {}", project.path.red(), problem);
+                }
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "Found {} problems in {} copyfile/linkfile elements",
+        problems, checked
+    );
+
+    if problems > 0 {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn verify_copy(
+    project_folder: &Path,
+    repo_root_folder: &Path,
+    copyfile: &FileOperation,
+) -> Result<(), String> {
+    let source = fs::read(project_folder.join(&copyfile.src))
+        .map_err(|e| format!("source {} can't be read: {}", copyfile.src, e))?;
+    let copy = fs::read(repo_root_folder.join(&copyfile.dest))
+        .map_err(|e| format!("copy {} can't be read: {}", copyfile.dest, e))?;
+
+    match source == copy {
+        true => Ok(()),
+        false => Err(format!(
+            "copy {} is stale, it differs from {}",
+            copyfile.dest, copyfile.src
+        )),
+    }
+}
+
+fn verify_link(
+    project_folder: &Path,
+    repo_root_folder: &Path,
+    linkfile: &FileOperation,
+) -> Result<(), String> {
+    let link = repo_root_folder.join(&linkfile.dest);
+    let is_link = fs::symlink_metadata(&link)
+        .map_err(|_| format!("link {} is missing", linkfile.dest))?
+        .file_type()
+        .is_symlink();
+    if !is_link {
+        return Err(format!("{} is not a link", linkfile.dest));
+    }
+
+    let source = project_folder
+        .join(&linkfile.src)
+        .canonicalize()
+        .map_err(|e| format!("source {} can't be resolved: {}", linkfile.src, e))?;
+    let target = link
+        .canonicalize()
+        .map_err(|_| format!("link {} is dangling", linkfile.dest))?;
+
+    match source == target {
+        true => Ok(()),
+        false => Err(format!(
+            "link {} points to {:?} instead of {}",
+            linkfile.dest, target, linkfile.src
+        )),
+    }
+}
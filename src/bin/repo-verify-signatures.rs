@@ -0,0 +1,249 @@
+extern crate clap;
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use colored::*;
+use git2::Repository;
+use rayon::prelude::*;
+use repo_utils::generate;
+use repo_utils::git_ops::{self, CommitSignature, SignatureStatus};
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Verify the signatures of commits in repos managed by git-repo against a list of allowed keys,
+/// see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Verbose output, e.g. also list compliant commits
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
+
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    /// file listing the allowed key ids or fingerprints, one per line; without, any valid signature is accepted
+    #[arg(short, long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    allowed_keys: Option<std::path::PathBuf>,
+
+    /// verify all commits since the last "repo sync" instead of HEAD only
+    #[arg(long, default_value = "false")]
+    since_sync: bool,
+
+    /// Print the report as json array of {path, commit, summary, status, key, compliant}
+    #[arg(long, default_value = "false")]
+    json: bool,
+}
+
+/// a verified commit, as reported
+struct Verdict {
+    signature: CommitSignature,
+    /// None if compliant, the reason otherwise
+    violation: Option<String>,
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, workspace, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+
+    let upstream = match args.since_sync {
+        true => Some(format!("refs/remotes/{}", workspace.sync_branch_name()?)),
+        false => None,
+    };
+    let allowed_keys = match &args.allowed_keys {
+        Some(file) => Some(load_allowed_keys(file)?),
+        None => None,
+    };
+
+    // keep stdout clean for the json output
+    match args.json {
+        true => eprintln!("Selected {} projects", list_of_projects.len()),
+        false => println!("Selected {} projects", list_of_projects.len()),
+    }
+
+    let results: Vec<_> = list_of_projects
+        .par_iter()
+        .map(|project| {
+            let repo = Repository::open(&project.absolute_path)?;
+            let signatures = git_ops::commit_signatures(&repo, upstream.as_deref())?;
+            Ok(signatures
+                .into_iter()
+                .map(|signature| Verdict {
+                    violation: check(&signature, allowed_keys.as_deref()),
+                    signature,
+                })
+                .collect())
+        })
+        .collect();
+
+    match args.json {
+        true => print_json(&list_of_projects, &results)?,
+        false => print_report(&list_of_projects, &results, args.verbose),
+    }
+
+    let failed = results.iter().filter(|r| r.is_err()).count();
+    let violations = results
+        .iter()
+        .flatten()
+        .flatten()
+        .filter(|v| v.violation.is_some())
+        .count();
+    if failed > 0 {
+        bail!("failed to verify {} projects", failed);
+    }
+    if violations > 0 {
+        bail!("{} commits are not compliant", violations);
+    }
+    Ok(())
+}
+
+/// a long gpg key id, short ones are too easy to collide with
+const MIN_KEY_LEN: usize = 16;
+
+/// reads the allowed keys, empty lines and lines starting with # are ignored
+fn load_allowed_keys(file: &Path) -> Result<Vec<String>> {
+    let content = fs::read_to_string(file)
+        .with_context(|| format!("Failed to read allowed keys from {}", file.display()))?;
+    let allowed_keys: Vec<String> = content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_uppercase)
+        .collect();
+    if let Some(short) = allowed_keys.iter().find(|k| k.len() < MIN_KEY_LEN) {
+        bail!(
+            "{}: allowed key {} is too short, expected a long key id ({} hex digits) or a fingerprint",
+            file.display(),
+            short,
+            MIN_KEY_LEN
+        );
+    }
+    Ok(allowed_keys)
+}
+
+/// returns why the commit is not compliant, None if it is
+fn check(signature: &CommitSignature, allowed_keys: Option<&[String]>) -> Option<String> {
+    if !signature.status.is_valid() {
+        return Some(describe(signature.status).to_string());
+    }
+    let key = signature.key.to_uppercase();
+    let fingerprint = signature.fingerprint.to_uppercase();
+    let is_allowed = |allowed: &String| *allowed == key || *allowed == fingerprint;
+    match allowed_keys {
+        Some(allowed_keys) if !allowed_keys.iter().any(is_allowed) => Some(format!(
+            "signed with key {} which is not allowed",
+            signature.key
+        )),
+        _ => None,
+    }
+}
+
+fn describe(status: SignatureStatus) -> &'static str {
+    match status {
+        SignatureStatus::Good => "good signature",
+        SignatureStatus::UnknownValidity => "good signature of unknown validity",
+        SignatureStatus::Bad => "bad signature",
+        SignatureStatus::Expired => "signature expired",
+        SignatureStatus::ExpiredKey => "signed with an expired key",
+        SignatureStatus::RevokedKey => "signed with a revoked key",
+        SignatureStatus::CannotCheck => "signature can't be checked, key is missing",
+        SignatureStatus::Unsigned => "unsigned",
+    }
+}
+
+fn print_report(list_of_projects: &[ProjectInfo], results: &[Result<Vec<Verdict>>], verbose: bool) {
+    let mut commits = 0;
+    let mut compliant = 0;
+    let mut projects_with_violations = 0;
+    for (project, result) in list_of_projects.iter().zip(results) {
+        let verdicts = match result {
+            Ok(verdicts) => verdicts,
+            Err(e) => {
+                println!("{}: {:#}", project.path.red(), e);
+                continue;
+            }
+        };
+        commits += verdicts.len();
+        compliant += verdicts.iter().filter(|v| v.violation.is_none()).count();
+
+        let shown: Vec<_> = verdicts
+            .iter()
+            .filter(|v| verbose || v.violation.is_some())
+            .collect();
+        if shown.iter().any(|v| v.violation.is_some()) {
+            projects_with_violations += 1;
+            println!("{}", project.path.red());
+        } else if !shown.is_empty() {
+            println!("{}", project.path.green());
+        }
+        for verdict in shown {
+            let signature = &verdict.signature;
+            match &verdict.violation {
+                Some(violation) => println!(
+                    "  {:.7} {}: {}",
+                    signature.oid,
+                    signature.summary,
+                    violation.red()
+                ),
+                None => println!(
+                    "  {:.7} {}: signed with key {}",
+                    signature.oid, signature.summary, signature.key
+                ),
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "{}/{} commits compliant, violations in {}/{} projects",
+        compliant,
+        commits,
+        projects_with_violations,
+        list_of_projects.len()
+    );
+}
+
+/// Prints the report as a json array to stdout
+fn print_json(list_of_projects: &[ProjectInfo], results: &[Result<Vec<Verdict>>]) -> Result<()> {
+    let mut records = vec![];
+    for (project, result) in list_of_projects.iter().zip(results) {
+        match result {
+            Ok(verdicts) => records.extend(verdicts.iter().map(|verdict| JsonRecord {
+                path: &project.path,
+                commit: verdict.signature.oid.to_string(),
+                summary: &verdict.signature.summary,
+                status: describe(verdict.signature.status),
+                key: &verdict.signature.key,
+                compliant: verdict.violation.is_none(),
+            })),
+            Err(e) => eprintln!("{}: {:#}", project.path.red(), e),
+        }
+    }
+    serde_json::to_writer_pretty(io::stdout(), &records)?;
+    println!();
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    path: &'a str,
+    commit: String,
+    summary: &'a str,
+    status: &'static str,
+    key: &'a str,
+    compliant: bool,
+}
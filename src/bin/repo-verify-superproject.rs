@@ -0,0 +1,152 @@
+extern crate clap;
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Parser;
+use colored::*;
+use git2::{FileMode, Oid, Repository};
+use repo_utils::generate;
+use repo_utils::repo_project_selector::ProjectInfo;
+use repo_utils::selection::SelectionArgs;
+use repo_utils::workspace::Workspace;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Verify that the checked out commits of all projects match the gitlinks
+/// recorded in the manifest's superproject, see https://github.com/elektronenhirn/repo-utils
+#[derive(Parser, Debug)]
+#[command(author, version, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// Verbose output, e.g. also list projects matching the superproject
+    #[arg(short, long, default_value = "false", overrides_with = "no_verbose")]
+    verbose: bool,
+
+    /// don't be verbose even if enabled in the config
+    #[arg(long, overrides_with = "verbose")]
+    no_verbose: bool,
+
+    /// git repo of the superproject, defaults to the one repo keeps in .repo/exp-superproject
+    #[arg(long, value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
+    superproject_git_dir: Option<PathBuf>,
+
+    /// revision of the superproject to compare with, defaults to the one given in the manifest
+    #[arg(long)]
+    revision: Option<String>,
+}
+
+fn main() -> Result<()> {
+    if generate::print_if_requested::<Args>(env!("CARGO_BIN_NAME"))? {
+        return Ok(());
+    }
+    let mut args = Args::parse();
+
+    let (config, workspace, list_of_projects) = args.selection.select()?;
+    args.verbose = !args.no_verbose && (args.verbose || config.defaults.verbose);
+
+    println!("Selected {} projects", list_of_projects.len());
+
+    let manifest = workspace.manifest()?;
+    let superproject = manifest
+        .superproject
+        .as_ref()
+        .ok_or_else(|| anyhow!("the manifest does not define a superproject"))?;
+
+    let git_dir = match &args.superproject_git_dir {
+        Some(dir) => dir.clone(),
+        None => find_superproject_git_dir(&workspace)?,
+    };
+    let revision = args
+        .revision
+        .as_deref()
+        .or_else(|| manifest.superproject_revision())
+        .ok_or_else(|| anyhow!("no revision given for superproject {}", superproject.name))?;
+
+    let divergences = verify(&list_of_projects, &git_dir, revision, args.verbose)?;
+
+    println!();
+    println!(
+        "{}/{} projects diverge from superproject {} at {}",
+        divergences,
+        list_of_projects.len(),
+        superproject.name,
+        revision
+    );
+
+    if divergences > 0 {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// The repo tool fetches the superproject into a bare repo
+/// named *superproject.git in .repo/exp-superproject
+fn find_superproject_git_dir(workspace: &Workspace) -> Result<PathBuf> {
+    let folder = workspace.repo_folder().join("exp-superproject");
+    if folder.is_dir() {
+        for entry in fs::read_dir(&folder)? {
+            let path = entry?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if path.is_dir() && name.ends_with("superproject.git") {
+                return Ok(path);
+            }
+        }
+    }
+    bail!("no superproject found in {:?}", folder)
+}
+
+/// compares the HEAD of every project with the gitlink at the project's
+/// path in the superproject, returns the number of divergent projects
+fn verify(
+    list_of_projects: &[ProjectInfo],
+    git_dir: &Path,
+    revision: &str,
+    verbose: bool,
+) -> Result<usize> {
+    let superproject = Repository::open(git_dir)
+        .with_context(|| format!("Failed to open superproject at {:?}", git_dir))?;
+    let tree = superproject
+        .revparse_single(&format!("refs/heads/{}", revision))
+        .or_else(|_| superproject.revparse_single(revision))
+        .and_then(|object| object.peel_to_tree())
+        .with_context(|| format!("Unknown superproject revision {}", revision))?;
+
+    let mut divergences = 0;
+    for project in list_of_projects {
+        let path = &project.path;
+        let head = Repository::open(&project.absolute_path)
+            .and_then(|repo| Ok(repo.head()?.peel_to_commit()?.id()))
+            .with_context(|| format!("Failed to lookup HEAD of {:?}", path))?;
+
+        let gitlink: Option<Oid> = tree
+            .get_path(Path::new(path))
+            .ok()
+            .filter(|entry| entry.filemode() == i32::from(FileMode::Commit))
+            .map(|entry| entry.id());
+
+        match gitlink {
+            Some(id) if id == head => {
+                if verbose {
+                    println!("{}: {:.7}", path.green(), head);
+                }
+            }
+            Some(id) => {
+                divergences += 1;
+                println!(
+                    "{}: checked out {:.7}, superproject has {:.7}",
+                    path.red(),
+                    head,
+                    id
+                );
+            }
+            None => {
+                divergences += 1;
+                println!("{}: not part of the superproject", path.red());
+            }
+        }
+    }
+    Ok(divergences)
+}
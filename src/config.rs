@@ -0,0 +1,272 @@
+use crate::error::{Error, Result, ResultExt};
+use crate::repo_project_selector::find_repo_root_folder;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+const CONFIG_SUBFOLDER: &str = "repo-utils";
+pub const WORKSPACE_CONFIG_FILE_NAME: &str = ".repo-utils.toml";
+
+/// environment variables overriding config keys, given as (variable, section, key)
+pub const ENV_OVERRIDES: &[(&str, &str, &str)] = &[
+    ("REPO_UTILS_GROUPS", "defaults", "groups"),
+    ("REPO_UTILS_JOBS", "defaults", "jobs"),
+    ("REPO_UTILS_VERBOSE", "defaults", "verbose"),
+    ("REPO_UTILS_MANIFEST_CACHE", "defaults", "manifest_cache"),
+    ("REPO_UTILS_COLOR", "ui", "color"),
+    ("REPO_UTILS_PROGRESS", "ui", "progress"),
+    ("REPO_UTILS_FORALL_RETRIES", "forall", "retries"),
+    ("REPO_UTILS_FORALL_RETRY_BACKOFF", "forall", "retry_backoff"),
+    ("REPO_UTILS_FORALL_TIMEOUT", "forall", "timeout"),
+    ("REPO_UTILS_BACKUP_URL", "backup", "url"),
+];
+
+/// User configuration, read from the first existing one of
+/// $XDG_CONFIG_HOME/repo-utils/config.toml (defaults to ~/.config)
+/// and <dir>/repo-utils/config.toml for each dir in $XDG_CONFIG_DIRS
+/// (defaults to /etc/xdg). A .repo-utils.toml in the root folder of the
+/// workspace overrides the user configuration key by key, the variables
+/// listed in [ENV_OVERRIDES] override both. Keys which aren't given
+/// keep their defaults, unknown keys are reported as error.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub defaults: DefaultsConfig,
+    pub ui: UiConfig,
+    pub forall: ForallConfig,
+    pub backup: BackupConfig,
+}
+
+/// defaults for the options shared by the tools, options
+/// given on the command line take precedence
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DefaultsConfig {
+    /// like -g/--group
+    pub groups: Option<Vec<String>>,
+    /// like -j/--jobs
+    pub jobs: Option<usize>,
+    /// like -v/--verbose
+    pub verbose: bool,
+    /// keep parsed manifests in .repo as well, see [crate::manifest_cache]
+    pub manifest_cache: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiConfig {
+    /// force colored output on or off, detected from the terminal if not set
+    pub color: Option<bool>,
+    /// show progress bars while scanning projects
+    pub progress: bool,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        UiConfig {
+            color: None,
+            progress: true,
+        }
+    }
+}
+
+/// defaults for the options of repo-forall with the same name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ForallConfig {
+    pub retries: u32,
+    pub retry_backoff: u64,
+    pub timeout: Option<u64>,
+}
+
+/// defaults for the options of repo-push-backup with the same name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupConfig {
+    pub url: Option<String>,
+}
+
+impl Config {
+    /// loads the user configuration, the one of the workspace the current
+    /// working directory is part of and the environment, see [Config]
+    pub fn load() -> Result<Config> {
+        let workspace_config =
+            find_repo_root_folder().map(|root| root.join(WORKSPACE_CONFIG_FILE_NAME));
+        Config::load_layered_with_env(
+            &user_config_paths(),
+            workspace_config.ok().as_deref(),
+            env::vars(),
+        )
+    }
+
+    /// loads the first existing file of `paths`, falls back
+    /// to the defaults if none of them exists
+    pub fn load_from(paths: &[PathBuf]) -> Result<Config> {
+        Config::load_layered(paths, None)
+    }
+
+    /// loads the first existing file of `user_paths` and overrides
+    /// its keys with the ones given in `workspace_path`, if it exists
+    pub fn load_layered(user_paths: &[PathBuf], workspace_path: Option<&Path>) -> Result<Config> {
+        Config::load_layered_with_env(user_paths, workspace_path, std::iter::empty())
+    }
+
+    /// same as [Config::load_layered], additionally the given environment
+    /// variables override the keys they are mapped to in [ENV_OVERRIDES]
+    pub fn load_layered_with_env(
+        user_paths: &[PathBuf],
+        workspace_path: Option<&Path>,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Config> {
+        let user_path = user_paths.iter().find(|path| path.is_file());
+        let workspace_path = workspace_path.filter(|path| path.is_file());
+
+        let mut merged = toml::Table::new();
+        for path in user_path
+            .map(PathBuf::as_path)
+            .into_iter()
+            .chain(workspace_path)
+        {
+            merge(&mut merged, read_table(path)?);
+        }
+        merge(&mut merged, env_table(vars)?);
+        toml::Value::Table(merged)
+            .try_into()
+            .map_err(|e: toml::de::Error| Error::Config(e.to_string()))
+    }
+
+    pub fn from_file(path: &Path) -> Result<Config> {
+        let content =
+            fs::read_to_string(path).with_context(|| format!("Unable to read {:?}", path))?;
+        Config::parse(&content).with_context(|| format!("Invalid config file {:?}", path))
+    }
+
+    pub fn parse(content: &str) -> Result<Config> {
+        toml::from_str(content).map_err(|e| Error::Config(e.to_string()))
+    }
+}
+
+/// reads the given file as table, after validating it on its own,
+/// so errors are reported for the file they occur in
+fn read_table(path: &Path) -> Result<toml::Table> {
+    let content = fs::read_to_string(path).with_context(|| format!("Unable to read {:?}", path))?;
+    Config::parse(&content).with_context(|| format!("Invalid config file {:?}", path))?;
+    content
+        .parse()
+        .map_err(|e: toml::de::Error| Error::Config(e.to_string()))
+}
+
+/// collects the overrides given by environment variables, values are
+/// parsed like in the config file, groups are given comma separated
+fn env_table(vars: impl IntoIterator<Item = (String, String)>) -> Result<toml::Table> {
+    let mut table = toml::Table::new();
+    for (var, value) in vars {
+        let (section, key) = match ENV_OVERRIDES.iter().find(|(name, _, _)| *name == var) {
+            Some((_, section, key)) => (section.to_string(), key.to_string()),
+            None => continue,
+        };
+        let value = match key.as_str() {
+            "groups" => toml::Value::Array(
+                value
+                    .split(',')
+                    .filter(|g| !g.is_empty())
+                    .map(|g| toml::Value::String(g.to_string()))
+                    .collect(),
+            ),
+            _ => format!("value = {}", value)
+                .parse::<toml::Table>()
+                .ok()
+                .and_then(|mut t| t.remove("value"))
+                .unwrap_or(toml::Value::String(value)),
+        };
+
+        let mut override_table = toml::Table::new();
+        override_table.insert(
+            section,
+            toml::Value::Table(std::iter::once((key, value)).collect()),
+        );
+        // validated on its own, so errors name the variable
+        toml::Value::Table(override_table.clone())
+            .try_into::<Config>()
+            .map_err(|e| Error::Config(format!("Invalid value of {}: {}", var, e)))?;
+        merge(&mut table, override_table);
+    }
+    Ok(table)
+}
+
+/// recursively merges `overrides` into `base`, values of `overrides` win
+fn merge(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => {
+                merge(base, overrides)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+impl UiConfig {
+    /// applies the color setting, NO_COLOR and CLICOLOR_FORCE
+    /// given in the environment take precedence
+    pub fn apply(&self) {
+        let env_decides =
+            env::var_os("NO_COLOR").is_some() || env::var_os("CLICOLOR_FORCE").is_some();
+        if let (Some(color), false) = (self.color, env_decides) {
+            colored::control::set_override(color);
+        }
+    }
+
+    /// the progress bar used while scanning `len` projects,
+    /// hidden if progress bars are disabled
+    pub fn progress_bar(&self, len: usize) -> Result<ProgressBar> {
+        if !self.progress {
+            return Ok(ProgressBar::hidden());
+        }
+        Ok(ProgressBar::new(len as u64).with_style(
+            ProgressStyle::default_bar()
+                .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7}")
+                .map_err(|e| Error::Config(e.to_string()))?,
+        ))
+    }
+}
+
+/// limits the number of projects processed in parallel,
+/// as many as CPUs are available by default
+pub fn init_jobs(jobs: Option<usize>) -> Result<()> {
+    if let Some(jobs) = jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()
+            .map_err(|e| Error::Config(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// candidates for the user configuration, ordered by precedence
+pub fn user_config_paths() -> Vec<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+    let config_dirs = env::var("XDG_CONFIG_DIRS")
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "/etc/xdg".to_string());
+
+    config_home
+        .into_iter()
+        .chain(
+            config_dirs
+                .split(':')
+                .filter(|d| !d.is_empty())
+                .map(PathBuf::from),
+        )
+        .map(|dir| dir.join(CONFIG_SUBFOLDER).join(CONFIG_FILE_NAME))
+        .collect()
+}
@@ -0,0 +1,26 @@
+/// Parses a YYYY-MM-DD date into seconds since the epoch (UTC), e.g.
+/// to compare it with commit times. Usable as clap value parser.
+pub fn parse_date(date: &str) -> Result<i64, String> {
+    let parts: Vec<&str> = date.split('-').collect();
+    let invalid = || format!("invalid date {:?}, expected YYYY-MM-DD", date);
+    let (year, month, day) = match parts.as_slice() {
+        [year, month, day] => (
+            year.parse::<i64>().map_err(|_| invalid())?,
+            month.parse::<i64>().map_err(|_| invalid())?,
+            day.parse::<i64>().map_err(|_| invalid())?,
+        ),
+        _ => return Err(invalid()),
+    };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(invalid());
+    }
+
+    // days since 1970-01-01 in the proleptic gregorian calendar
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+    Ok(days * 86400)
+}
@@ -0,0 +1,42 @@
+use crate::error::{Result, ResultExt};
+use std::fs;
+use std::path::Path;
+
+/// the size of all files below `path`, symlinks aren't followed
+pub fn disk_usage(path: &Path) -> Result<u64> {
+    disk_usage_excluding(path, &[])
+}
+
+/// same as [disk_usage], but entries named like one of `excluded`
+/// (e.g. ".git") are skipped
+pub fn disk_usage_excluding(path: &Path, excluded: &[&str]) -> Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(path).with_context(|| format!("Unable to read {:?}", path))? {
+        let entry = entry?;
+        if excluded.iter().any(|e| entry.file_name() == *e) {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            size += disk_usage_excluding(&entry.path(), excluded)?;
+        } else if file_type.is_file() {
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok(size)
+}
+
+/// formats the given number of bytes with binary units, e.g. "1.5 MiB"
+pub fn human_readable(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
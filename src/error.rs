@@ -0,0 +1,110 @@
+use std::fmt;
+use std::io;
+
+/// Errors of the library layer. The binaries wrap them into anyhow,
+/// library consumers can match on the kind of failure.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// neither the given folder nor any of its parents contains .repo
+    #[error("no .repo folder found")]
+    NoWorkspace,
+
+    /// a manifest or the project.list is missing or invalid,
+    /// or projects can't be selected as requested
+    #[error("{0}")]
+    Manifest(String),
+
+    /// a git operation failed
+    #[error("{}", with_context(.context, .error))]
+    Git {
+        context: Option<String>,
+        error: git2::Error,
+    },
+
+    /// reading or writing a file failed
+    #[error("{}", with_context(.context, .error))]
+    Io {
+        context: Option<String>,
+        error: io::Error,
+    },
+
+    /// the configuration is invalid
+    #[error("{0}")]
+    Config(String),
+
+    /// a command couldn't be executed
+    #[error("{0}")]
+    Command(String),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+fn with_context(context: &Option<String>, error: &dyn fmt::Display) -> String {
+    match context {
+        Some(context) => format!("{}: {}", context, error),
+        None => error.to_string(),
+    }
+}
+
+impl Error {
+    /// describes what was going on when the error occured
+    pub fn context(self, context: impl Into<String>) -> Error {
+        let context = context.into();
+        match self {
+            Error::NoWorkspace => Error::NoWorkspace,
+            Error::Manifest(message) => Error::Manifest(format!("{}: {}", context, message)),
+            Error::Git {
+                context: inner,
+                error,
+            } => Error::Git {
+                context: Some(chain(context, inner)),
+                error,
+            },
+            Error::Io {
+                context: inner,
+                error,
+            } => Error::Io {
+                context: Some(chain(context, inner)),
+                error,
+            },
+            Error::Config(message) => Error::Config(format!("{}: {}", context, message)),
+            Error::Command(message) => Error::Command(format!("{}: {}", context, message)),
+        }
+    }
+}
+
+fn chain(outer: String, inner: Option<String>) -> String {
+    match inner {
+        Some(inner) => format!("{}: {}", outer, inner),
+        None => outer,
+    }
+}
+
+impl From<git2::Error> for Error {
+    fn from(error: git2::Error) -> Self {
+        Error::Git {
+            context: None,
+            error,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io {
+            context: None,
+            error,
+        }
+    }
+}
+
+/// Adds context to errors, like anyhow's Context does
+pub(crate) trait ResultExt<T> {
+    fn with_context<F: FnOnce() -> String>(self, context: F) -> Result<T>;
+}
+
+impl<T, E: Into<Error>> ResultExt<T> for std::result::Result<T, E> {
+    fn with_context<F: FnOnce() -> String>(self, context: F) -> Result<T> {
+        self.map_err(|e| e.into().context(context()))
+    }
+}
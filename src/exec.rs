@@ -0,0 +1,157 @@
+use crate::error::{Error, Result};
+use std::io;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Child, Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Command executing the given command line with the platform's
+/// shell, i.e. `sh -c` on unix-like systems and `cmd /C` on windows
+pub fn shell_command(command_line: &str) -> Command {
+    let mut command = Command::new(shell());
+    command.arg(shell_flag()).arg(command_line);
+    command
+}
+
+/// Command executing the given script file with the platform's shell
+pub fn script_command(script: &Path) -> Command {
+    let mut command = Command::new(shell());
+    if cfg!(windows) {
+        command.arg(shell_flag());
+    }
+    command.arg(script);
+    command
+}
+
+/// Command executing argv[0] directly, without any shell involved
+pub fn direct_command(argv: &[String]) -> Result<Command> {
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| Error::Command("no command given".to_string()))?;
+    let mut command = Command::new(program);
+    command.args(args);
+    Ok(command)
+}
+
+#[cfg(windows)]
+fn shell() -> std::ffi::OsString {
+    std::env::var_os("COMSPEC").unwrap_or_else(|| "cmd.exe".into())
+}
+
+#[cfg(not(windows))]
+fn shell() -> std::ffi::OsString {
+    "sh".into()
+}
+
+fn shell_flag() -> &'static str {
+    match cfg!(windows) {
+        true => "/C",
+        false => "-c",
+    }
+}
+
+/// Like Command::output(), but feeds the given data into stdin and
+/// kills the child process if it doesn't finish within the given timeout.
+/// With `live` set, the output is additionally passed through as it arrives.
+///
+/// On unix, a command with timeout runs in a process group of its own, so
+/// the processes it started (e.g. a hanging git fetch started by the shell)
+/// are killed along with it. On windows, only the child itself is killed.
+pub fn output_with_timeout(
+    mut command: Command,
+    stdin: Option<&[u8]>,
+    timeout: Option<Duration>,
+    live: bool,
+) -> Result<Output> {
+    #[cfg(unix)]
+    if timeout.is_some() {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    let mut child = command
+        .stdin(match stdin {
+            Some(_) => Stdio::piped(),
+            None => Stdio::null(),
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    if let (Some(mut pipe), Some(data)) = (child.stdin.take(), stdin) {
+        let data = data.to_vec();
+        // the child might not consume all of it, which is fine
+        thread::spawn(move || pipe.write_all(&data));
+    }
+
+    // drain the pipes in the background, otherwise the child blocks
+    // as soon as it fills up the pipe buffers
+    let stdout = read_to_end_in_background(child.stdout.take(), live.then(io::stdout));
+    let stderr = read_to_end_in_background(child.stderr.take(), live.then(io::stderr));
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if let Some(timeout) = timeout {
+            if started.elapsed() >= timeout {
+                kill(&mut child)?;
+                child.wait()?;
+                return Err(Error::Command(format!(
+                    "command timed out after {}s",
+                    timeout.as_secs()
+                )));
+            }
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// kills the child along with its process group, see [output_with_timeout]
+#[cfg(unix)]
+fn kill(child: &mut Child) -> io::Result<()> {
+    // the group id is the pid of the child, which leads the group
+    match unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) } {
+        0 => Ok(()),
+        _ => child.kill(),
+    }
+}
+
+#[cfg(not(unix))]
+fn kill(child: &mut Child) -> io::Result<()> {
+    child.kill()
+}
+
+fn read_to_end_in_background(
+    pipe: Option<impl Read + Send + 'static>,
+    mut echo: Option<impl Write + Send + 'static>,
+) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = vec![];
+        let mut chunk = [0u8; 8192];
+        if let Some(mut pipe) = pipe {
+            loop {
+                match pipe.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        buffer.extend_from_slice(&chunk[..n]);
+                        if let Some(echo) = &mut echo {
+                            let _ = echo.write_all(&chunk[..n]);
+                        }
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(_) => break,
+                }
+            }
+        }
+        buffer
+    })
+}
@@ -0,0 +1,83 @@
+use crate::workspace::Workspace;
+use clap::builder::PossibleValuesParser;
+use clap::{value_parser, Arg, ArgAction, Command, CommandFactory};
+use clap_complete::Shell;
+use std::collections::BTreeSet;
+use std::io;
+use std::path::PathBuf;
+
+/// Handles the hidden options all binaries have to generate their
+/// documentation from the command line definition `A`:
+///
+/// * `--completions SHELL` prints the completion script for the given
+///   shell; generated inside a workspace, the groups of its manifest
+///   are suggested as values of --group
+/// * `--generate-man` prints the man page, in roff format
+///
+/// Returns true if one of them was given, so the binary can quit. The
+/// options are looked for before the command line is parsed as usual,
+/// because arguments which are required otherwise (e.g. a subcommand)
+/// are missing then.
+pub fn print_if_requested<A: CommandFactory>(bin_name: &str) -> io::Result<bool> {
+    let matches = match A::command()
+        .arg(
+            Arg::new("completions")
+                .long("completions")
+                .value_name("SHELL")
+                .value_parser(value_parser!(Shell))
+                .hide(true),
+        )
+        .arg(
+            Arg::new("generate-man")
+                .long("generate-man")
+                .action(ArgAction::SetTrue)
+                .hide(true),
+        )
+        .ignore_errors(true)
+        .try_get_matches()
+    {
+        Ok(matches) => matches,
+        Err(_) => return Ok(false),
+    };
+
+    let mut cmd = A::command().name(bin_name.to_string());
+    if matches.get_flag("generate-man") {
+        clap_mangen::Man::new(cmd).render(&mut io::stdout())?;
+        return Ok(true);
+    }
+    let shell = match matches.get_one::<Shell>("completions") {
+        Some(shell) => *shell,
+        None => return Ok(false),
+    };
+
+    let folder = matches
+        .get_one::<PathBuf>("cwd")
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from("."));
+    if let Some(groups) = workspace_groups(folder) {
+        cmd = suggest_groups(cmd, groups);
+    }
+    clap_complete::generate(shell, &mut cmd, bin_name, &mut io::stdout());
+    Ok(true)
+}
+
+/// the group names used in the manifest of the workspace at `folder`,
+/// plus the groups every project is implicitly part of
+fn workspace_groups(folder: PathBuf) -> Option<BTreeSet<String>> {
+    let workspace = Workspace::discover(&folder).ok()?;
+    let manifest = workspace.manifest().ok()?;
+    let mut groups: BTreeSet<String> = ["all", "default"].iter().map(|g| g.to_string()).collect();
+    for project in &manifest.projects {
+        groups.extend(project.group_names().iter().map(|g| g.to_string()));
+    }
+    Some(groups)
+}
+
+fn suggest_groups(cmd: Command, groups: BTreeSet<String>) -> Command {
+    if !cmd.get_arguments().any(|a| a.get_id() == "group") {
+        return cmd;
+    }
+    cmd.mut_arg("group", |arg| {
+        arg.value_parser(PossibleValuesParser::new(groups))
+    })
+}
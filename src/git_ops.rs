@@ -0,0 +1,668 @@
+use crate::error::{Error, Result, ResultExt};
+use git2::build::CheckoutBuilder;
+use git2::{
+    BranchType, Cred, CredentialType, ErrorCode, FetchOptions, Oid, RemoteCallbacks, Repository,
+    Signature, Sort, StashFlags, Status, StatusOptions,
+};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::process::Command;
+use std::rc::Rc;
+
+/// Built-in replacement for `git status --short`, returns one line
+/// per changed file, prefixed by the usual two-letter status code.
+pub fn status(repo: &Repository) -> Result<String> {
+    let mut opts = StatusOptions::new();
+    opts.include_ignored(false)
+        .include_untracked(true)
+        .recurse_untracked_dirs(true);
+
+    let mut output = String::new();
+    for entry in repo.statuses(Some(&mut opts))?.iter() {
+        let path = entry.path().unwrap_or("<non-utf8 path>");
+        output += &format!("{} {}\n", short_status_code(entry.status()), path);
+    }
+    Ok(output)
+}
+
+fn short_status_code(status: Status) -> String {
+    if status.is_conflicted() {
+        return "UU".to_string();
+    }
+    if status.is_wt_new() && !status.is_index_new() {
+        return "??".to_string();
+    }
+
+    let index = match status {
+        s if s.is_index_new() => 'A',
+        s if s.is_index_modified() => 'M',
+        s if s.is_index_deleted() => 'D',
+        s if s.is_index_renamed() => 'R',
+        s if s.is_index_typechange() => 'T',
+        _ => ' ',
+    };
+    let worktree = match status {
+        s if s.is_wt_modified() => 'M',
+        s if s.is_wt_deleted() => 'D',
+        s if s.is_wt_renamed() => 'R',
+        s if s.is_wt_typechange() => 'T',
+        _ => ' ',
+    };
+    format!("{}{}", index, worktree)
+}
+
+/// Built-in replacement for `git fetch`, fetches the given remotes
+/// or all configured remotes if none are given.
+pub fn fetch(repo: &Repository, remotes: &[String]) -> Result<String> {
+    let mut output = String::new();
+    for stats in fetch_remotes(repo, remotes)? {
+        output += &format!(
+            "{}: received {}/{} objects ({} bytes)\n",
+            stats.remote, stats.received_objects, stats.total_objects, stats.received_bytes
+        );
+    }
+    Ok(output)
+}
+
+/// What fetching a remote transferred
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FetchStats {
+    pub remote: String,
+    pub received_objects: usize,
+    pub total_objects: usize,
+    pub received_bytes: usize,
+    /// the refs created or moved by the fetch, e.g. refs/remotes/origin/main
+    pub updated_refs: Vec<String>,
+}
+
+/// Same as [fetch], but returns the stats of each fetched remote
+pub fn fetch_remotes(repo: &Repository, remotes: &[String]) -> Result<Vec<FetchStats>> {
+    let remotes: Vec<String> = match remotes.is_empty() {
+        true => repo
+            .remotes()?
+            .iter()
+            .flatten()
+            .map(|s| s.to_string())
+            .collect(),
+        false => remotes.to_vec(),
+    };
+
+    let mut fetched = vec![];
+    for name in remotes {
+        let mut remote = repo.find_remote(&name)?;
+        let updated_refs = Rc::new(RefCell::new(vec![]));
+        let mut callbacks = credential_callbacks(repo);
+        let refs = Rc::clone(&updated_refs);
+        callbacks.update_tips(move |refname, _, _| {
+            refs.borrow_mut().push(refname.to_string());
+            true
+        });
+        let mut fetch_options = FetchOptions::new();
+        fetch_options.remote_callbacks(callbacks);
+        remote
+            .fetch(&[] as &[&str], Some(&mut fetch_options), None)
+            .with_context(|| format!("fetching {} failed", name))?;
+
+        let stats = remote.stats();
+        fetched.push(FetchStats {
+            remote: name,
+            received_objects: stats.received_objects(),
+            total_objects: stats.total_objects(),
+            received_bytes: stats.received_bytes(),
+            updated_refs: updated_refs.take(),
+        });
+    }
+    Ok(fetched)
+}
+
+/// Credentials are looked up the same way git would do it: ssh-agent
+/// for ssh remotes, the configured credential helper for http(s).
+pub fn credential_callbacks(repo: &Repository) -> RemoteCallbacks<'static> {
+    let config = repo.config().ok();
+    let mut attempts = 0;
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username_from_url, allowed_types| {
+        // libgit2 keeps asking as long as we hand out credentials,
+        // so give up after a few rejected ones
+        attempts += 1;
+        if attempts > 3 {
+            return Err(git2::Error::from_str("authentication failed"));
+        }
+
+        if allowed_types.contains(CredentialType::SSH_KEY) {
+            return Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"));
+        }
+        if allowed_types.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if let Some(config) = &config {
+                return Cred::credential_helper(config, url, username_from_url);
+            }
+        }
+        Cred::default()
+    });
+    callbacks
+}
+
+/// Built-in replacement for `git branch -D`
+pub fn delete_branches(repo: &Repository, branches: &[String]) -> Result<String> {
+    if branches.is_empty() {
+        return Err(Error::Command("no branch name given".to_string()));
+    }
+
+    let mut output = String::new();
+    for name in branches {
+        let mut branch = repo.find_branch(name, BranchType::Local)?;
+        let sha = branch.get().peel_to_commit()?.id();
+        branch.delete()?;
+        output += &format!("Deleted branch {} (was {:.7}).\n", name, sha);
+    }
+    Ok(output)
+}
+
+/// Built-in replacement for `git checkout <revision>`: local branches
+/// are checked out as such, everything else results in a detached HEAD.
+pub fn checkout(repo: &Repository, revision: &str) -> Result<String> {
+    let (object, reference) = repo.revparse_ext(revision)?;
+    repo.checkout_tree(&object, Some(CheckoutBuilder::new().safe()))?;
+
+    match reference {
+        Some(reference) if reference.is_branch() => {
+            let name = reference.name().ok_or_else(|| {
+                git2::Error::from_str(&format!("invalid branch name: {}", revision))
+            })?;
+            repo.set_head(name)?;
+            Ok(format!("Switched to branch '{}'\n", revision))
+        }
+        _ => {
+            let commit = object.peel_to_commit()?;
+            repo.set_head_detached(commit.id())?;
+            Ok(format!("HEAD is now at {:.7}\n", commit.id()))
+        }
+    }
+}
+
+/// How far a branch diverged from another one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// the branch compared with, e.g. origin/main or m/main
+    pub name: String,
+    /// commits on the branch, but not on the one compared with
+    pub ahead: usize,
+    /// commits on the one compared with, but not on the branch
+    pub behind: usize,
+}
+
+/// A local branch compared with its upstream and the last "repo sync"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchInfo {
+    pub name: String,
+    /// true if the branch is checked out
+    pub is_head: bool,
+    /// None if the branch has no upstream configured
+    pub upstream: Option<Divergence>,
+    /// None if the sync branch doesn't exist
+    pub sync: Option<Divergence>,
+}
+
+/// Lists the local branches of `repo`, like `git branch -vv`, compared
+/// with their upstream and with the given sync branch (e.g. m/main)
+pub fn branches(repo: &Repository, sync_branch_name: &str) -> Result<Vec<BranchInfo>> {
+    let sync = repo
+        .find_branch(sync_branch_name, BranchType::Remote)
+        .ok()
+        .and_then(|b| b.get().target());
+
+    let mut infos = vec![];
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        let name = match branch.name()? {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        let target = match branch.get().target() {
+            Some(target) => target,
+            None => continue,
+        };
+
+        let upstream = match branch.upstream() {
+            Ok(upstream) => match (upstream.name()?, upstream.get().target()) {
+                (Some(upstream_name), Some(upstream_target)) => {
+                    Some(divergence(repo, upstream_name, target, upstream_target)?)
+                }
+                _ => None,
+            },
+            Err(_) => None,
+        };
+        let sync = match sync {
+            Some(sync) => Some(divergence(repo, sync_branch_name, target, sync)?),
+            None => None,
+        };
+
+        infos.push(BranchInfo {
+            name,
+            is_head: branch.is_head(),
+            upstream,
+            sync,
+        });
+    }
+    Ok(infos)
+}
+
+fn divergence(repo: &Repository, name: &str, local: Oid, other: Oid) -> Result<Divergence> {
+    let (ahead, behind) = repo.graph_ahead_behind(local, other)?;
+    Ok(Divergence {
+        name: name.to_string(),
+        ahead,
+        behind,
+    })
+}
+
+/// Built-in replacement for `repo start`: creates the branch `name` at
+/// the given sync branch (e.g. m/main) and checks it out
+pub fn start_branch(repo: &Repository, name: &str, sync_branch_name: &str) -> Result<String> {
+    let commit = repo
+        .find_branch(sync_branch_name, BranchType::Remote)?
+        .get()
+        .peel_to_commit()?;
+    repo.branch(name, &commit, false)?;
+    checkout(repo, name)
+}
+
+/// Configures the upstream of the local branch `name`, like `repo start`
+/// does: `remote` and `revision` as given in the manifest. Unlike
+/// `git branch --set-upstream-to`, the remote branch doesn't need to
+/// be fetched already.
+pub fn set_upstream(repo: &Repository, name: &str, remote: &str, revision: &str) -> Result<()> {
+    let merge = match revision.starts_with("refs/") {
+        true => revision.to_string(),
+        false => format!("refs/heads/{}", revision),
+    };
+    let mut config = repo.config()?;
+    config.set_str(&format!("branch.{}.remote", name), remote)?;
+    config.set_str(&format!("branch.{}.merge", name), &merge)?;
+    Ok(())
+}
+
+/// Built-in replacement for `repo abandon`: deletes the branch `name`,
+/// if it is checked out, HEAD is detached at the given sync branch first
+pub fn abandon_branch(repo: &Repository, name: &str, sync_branch_name: &str) -> Result<String> {
+    let branch = repo.find_branch(name, BranchType::Local)?;
+    let mut output = String::new();
+    if branch.is_head() {
+        output += &checkout(repo, &format!("refs/remotes/{}", sync_branch_name))?;
+    }
+    output += &delete_branches(repo, &[name.to_string()])?;
+    Ok(output)
+}
+
+/// Why a local branch is no longer needed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruneReason {
+    /// all commits of the branch are part of the sync branch
+    Merged,
+    /// the upstream branch configured was deleted on the remote
+    UpstreamGone,
+}
+
+/// A local branch which can be deleted, see [prunable_branches]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrunableBranch {
+    pub name: String,
+    pub reason: PruneReason,
+}
+
+/// Lists the local branches of `repo` which were merged into the given
+/// sync branch (e.g. m/main) or whose upstream branch is gone. The branch
+/// checked out is never listed.
+pub fn prunable_branches(repo: &Repository, sync_branch_name: &str) -> Result<Vec<PrunableBranch>> {
+    let sync = repo
+        .find_branch(sync_branch_name, BranchType::Remote)
+        .ok()
+        .and_then(|b| b.get().target());
+
+    let mut prunable = vec![];
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        if branch.is_head() {
+            continue;
+        }
+        let (name, target) = match (branch.name()?, branch.get().target()) {
+            (Some(name), Some(target)) => (name.to_string(), target),
+            _ => continue,
+        };
+
+        let merged = match sync {
+            Some(sync) => target == sync || repo.graph_descendant_of(sync, target)?,
+            None => false,
+        };
+        // the upstream is configured, but its ref doesn't exist anymore
+        let upstream_gone = branch.upstream().is_err()
+            && branch
+                .get()
+                .name()
+                .is_some_and(|refname| repo.branch_upstream_name(refname).is_ok());
+
+        let reason = match (merged, upstream_gone) {
+            (true, _) => PruneReason::Merged,
+            (_, true) => PruneReason::UpstreamGone,
+            _ => continue,
+        };
+        prunable.push(PrunableBranch { name, reason });
+    }
+    Ok(prunable)
+}
+
+/// Built-in replacement for `git stash push`, returns None if there
+/// are no local changes to stash
+pub fn stash_save(
+    repo: &mut Repository,
+    message: Option<&str>,
+    include_untracked: bool,
+) -> Result<Option<String>> {
+    let signature = repo
+        .signature()
+        .or_else(|_| Signature::now("repo-utils", "repo-utils@localhost"))?;
+    let flags = match include_untracked {
+        true => StashFlags::INCLUDE_UNTRACKED,
+        false => StashFlags::DEFAULT,
+    };
+    match repo.stash_save2(&signature, message, Some(flags)) {
+        Ok(oid) => Ok(Some(format!("Saved working directory as {:.7}\n", oid))),
+        Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Built-in replacement for `git stash list`
+pub fn stash_list(repo: &mut Repository) -> Result<Vec<String>> {
+    let mut stashes = vec![];
+    repo.stash_foreach(|index, message, _| {
+        stashes.push(format!("stash@{{{}}}: {}", index, message));
+        true
+    })?;
+    Ok(stashes)
+}
+
+/// Built-in replacement for `git stash pop`, returns None if there
+/// is no stash to pop
+pub fn stash_pop(repo: &mut Repository) -> Result<Option<String>> {
+    let mut message = None;
+    repo.stash_foreach(|_, m, _| {
+        message = Some(m.to_string());
+        false
+    })?;
+    match message {
+        Some(message) => {
+            repo.stash_pop(0, None)?;
+            Ok(Some(format!("Popped {}\n", message)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Built-in replacement for `git tag -a <name> -m <message>`, tags HEAD.
+/// Signed tags are created by git itself, as libgit2 can't sign them.
+pub fn create_tag(repo: &Repository, name: &str, message: &str, sign: bool) -> Result<String> {
+    let head = repo.head()?.peel_to_commit()?;
+    if sign {
+        let workdir = repo.workdir().unwrap_or_else(|| repo.path());
+        let output = Command::new("git")
+            .args(["tag", "-s", "-m", message, name])
+            .current_dir(workdir)
+            .output()
+            .with_context(|| "Failed to execute git tag".to_string())?;
+        if !output.status.success() {
+            return Err(Error::Command(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+    } else {
+        let signature = repo
+            .signature()
+            .or_else(|_| Signature::now("repo-utils", "repo-utils@localhost"))?;
+        repo.tag(name, head.as_object(), &signature, message, false)?;
+    }
+    Ok(format!("Tagged {:.7} as {}\n", head.id(), name))
+}
+
+/// Built-in replacement for `git tag -d`, returns None if there is no such tag
+pub fn delete_tag(repo: &Repository, name: &str) -> Result<Option<String>> {
+    let target = match repo.revparse_single(&format!("refs/tags/{}", name)) {
+        Ok(target) => target.id(),
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    repo.tag_delete(name)?;
+    Ok(Some(format!(
+        "Deleted tag '{}' (was {:.7})\n",
+        name, target
+    )))
+}
+
+/// Built-in replacement for `git tag -l [pattern]`
+pub fn tags(repo: &Repository, pattern: Option<&str>) -> Result<Vec<String>> {
+    Ok(repo
+        .tag_names(pattern)?
+        .iter()
+        .flatten()
+        .map(|s| s.to_string())
+        .collect())
+}
+
+/// Looks up a commit by its (abbreviated) sha or by the Change-Id
+/// given in its message, Change-Ids are searched in the history of
+/// all refs. Returns None if the repo doesn't know the commit.
+pub fn find_commit(repo: &Repository, query: &str) -> Result<Option<Oid>> {
+    if is_change_id(query) {
+        let trailer = format!("Change-Id: {}", query);
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_glob("refs/*")?;
+        if let Ok(head) = repo.head() {
+            if let Some(target) = head.target() {
+                revwalk.push(target)?;
+            }
+        }
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+            if commit
+                .message()
+                .is_some_and(|m| m.lines().any(|l| l.trim() == trailer))
+            {
+                return Ok(Some(commit.id()));
+            }
+        }
+        return Ok(None);
+    }
+
+    match repo
+        .revparse_single(query)
+        .and_then(|object| object.peel_to_commit())
+    {
+        Ok(commit) => Ok(Some(commit.id())),
+        Err(e) if matches!(e.code(), ErrorCode::NotFound | ErrorCode::Ambiguous) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// true if `query` looks like a Gerrit Change-Id, e.g. I0123...
+pub fn is_change_id(query: &str) -> bool {
+    query.len() == 41 && query.starts_with('I') && query[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// true if `revision` is a full sha rather than a branch or tag, as
+/// manifests pin revisions
+pub fn is_sha(revision: &str) -> bool {
+    revision.len() == 40 && revision.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// The branches (local and remote) and tags containing the given commit,
+/// by their short names
+pub fn refs_containing(repo: &Repository, oid: Oid) -> Result<Vec<String>> {
+    let mut names = vec![];
+    for reference in repo.references()? {
+        let reference = reference?;
+        if !(reference.is_branch() || reference.is_remote() || reference.is_tag()) {
+            continue;
+        }
+        let target = match reference.peel_to_commit() {
+            Ok(commit) => commit.id(),
+            Err(_) => continue,
+        };
+        if target == oid || repo.graph_descendant_of(target, oid)? {
+            if let Some(name) = reference.shorthand() {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// A commit on HEAD which is not contained in the upstream branch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CherryCommit {
+    pub oid: Oid,
+    pub summary: String,
+    /// true if an equivalent patch was merged upstream under a different sha
+    pub upstreamed: bool,
+}
+
+/// Built-in replacement for `git cherry <upstream>`: lists the commits on
+/// HEAD which are not in `upstream` (oldest first), comparing them with
+/// the commits in `upstream` by patch-id. Merge commits are skipped.
+pub fn cherry(repo: &Repository, upstream: &str) -> Result<Vec<CherryCommit>> {
+    let head = repo.head()?.peel_to_commit()?.id();
+    let upstream = repo.revparse_single(upstream)?.peel_to_commit()?.id();
+
+    let mut upstream_patch_ids = HashSet::new();
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(upstream)?;
+    revwalk.hide(head)?;
+    for oid in revwalk {
+        if let Some(patch_id) = patch_id(repo, oid?)? {
+            upstream_patch_ids.insert(patch_id);
+        }
+    }
+
+    let mut commits = vec![];
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+    revwalk.push(head)?;
+    revwalk.hide(upstream)?;
+    for oid in revwalk {
+        let oid = oid?;
+        let commit = repo.find_commit(oid)?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        commits.push(CherryCommit {
+            oid,
+            summary: commit.summary().unwrap_or_default().to_string(),
+            upstreamed: patch_id(repo, oid)?.is_some_and(|id| upstream_patch_ids.contains(&id)),
+        });
+    }
+    Ok(commits)
+}
+
+/// the patch-id of the changes made by a commit, like `git patch-id`,
+/// None for merge commits
+fn patch_id(repo: &Repository, oid: Oid) -> Result<Option<Oid>> {
+    let commit = repo.find_commit(oid)?;
+    let parent_tree = match commit.parent_count() {
+        0 => None,
+        1 => Some(commit.parent(0)?.tree()?),
+        _ => return Ok(None),
+    };
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    Ok(Some(diff.patchid(None)?))
+}
+
+/// The result of verifying the signature of a commit, see `%G?` in `git log --help`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureStatus {
+    Good,
+    /// good signature, but the validity of the key is unknown to gpg
+    UnknownValidity,
+    Bad,
+    Expired,
+    ExpiredKey,
+    RevokedKey,
+    /// the key needed to check the signature is missing
+    CannotCheck,
+    Unsigned,
+}
+
+impl SignatureStatus {
+    fn from_code(code: &str) -> SignatureStatus {
+        match code {
+            "G" => SignatureStatus::Good,
+            "U" => SignatureStatus::UnknownValidity,
+            "B" => SignatureStatus::Bad,
+            "X" => SignatureStatus::Expired,
+            "Y" => SignatureStatus::ExpiredKey,
+            "R" => SignatureStatus::RevokedKey,
+            "E" => SignatureStatus::CannotCheck,
+            _ => SignatureStatus::Unsigned,
+        }
+    }
+
+    /// true if the signature was made by the key it claims
+    pub fn is_valid(&self) -> bool {
+        matches!(
+            self,
+            SignatureStatus::Good | SignatureStatus::UnknownValidity
+        )
+    }
+}
+
+/// A commit along with the verification result of its (GPG or SSH) signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitSignature {
+    pub oid: Oid,
+    pub summary: String,
+    pub status: SignatureStatus,
+    /// the key used for signing, empty if unsigned
+    pub key: String,
+    /// the fingerprint of the key used for signing, empty if unknown
+    pub fingerprint: String,
+}
+
+/// Verifies the signatures of HEAD, or of all commits since `upstream` if
+/// given. Done by `git log`, so gpg and gpg.ssh.allowedSignersFile are
+/// configured as usual.
+pub fn commit_signatures(
+    repo: &Repository,
+    upstream: Option<&str>,
+) -> Result<Vec<CommitSignature>> {
+    let workdir = repo.workdir().unwrap_or_else(|| repo.path());
+    let mut command = Command::new("git");
+    command
+        .args(["log", "--format=%H%x00%G?%x00%GK%x00%GF%x00%s"])
+        .current_dir(workdir);
+    match upstream {
+        Some(upstream) => command.arg(format!("{}..HEAD", upstream)),
+        None => command.args(["-1", "HEAD"]),
+    };
+    let output = command
+        .output()
+        .with_context(|| "Failed to execute git log".to_string())?;
+    if !output.status.success() {
+        return Err(Error::Command(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    let mut signatures = vec![];
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let fields: Vec<&str> = line.splitn(5, '\0').collect();
+        if let [oid, status, key, fingerprint, summary] = fields.as_slice() {
+            signatures.push(CommitSignature {
+                oid: Oid::from_str(oid)?,
+                summary: summary.to_string(),
+                status: SignatureStatus::from_code(status),
+                key: key.to_string(),
+                fingerprint: fingerprint.to_string(),
+            });
+        }
+    }
+    Ok(signatures)
+}
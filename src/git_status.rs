@@ -0,0 +1,227 @@
+use crate::error::{Error, Result, ResultExt};
+use crate::paths;
+use crate::progress::Progress;
+use crate::repo_project_selector::{find_repo_manifests_folder, ProjectInfo};
+use git2::{Branch, Repository, Status, StatusEntry, StatusOptions};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::convert::TryFrom;
+use std::path::Path;
+
+/// State of a project compared to the last "repo sync"
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Serialize)]
+pub struct GitStatus {
+    pub path: String,
+    #[serde(rename = "uncommitted_changes")]
+    pub uncomitted_changes: bool,
+    pub local_commits: i32,
+    /// the checked out branch, None if HEAD is detached
+    pub branch: Option<String>,
+    /// number of commits of HEAD missing in the sync branch
+    pub ahead: usize,
+    /// number of commits of the sync branch missing in HEAD
+    pub behind: usize,
+}
+
+impl GitStatus {
+    pub fn new(path: &str, dirty: bool, local_commits: i32) -> Self {
+        GitStatus {
+            path: path.to_string(),
+            uncomitted_changes: dirty,
+            local_commits,
+            branch: None,
+            ahead: 0,
+            behind: 0,
+        }
+    }
+
+    /// true if the project has uncommited changes or local commits
+    pub fn is_dirty(&self) -> bool {
+        self.uncomitted_changes || self.local_commits > 0
+    }
+}
+
+/// Queries the status of the git repo at `repo_root_folder/path`:
+/// uncommited changes in the worktree and deltas between HEAD and
+/// the given sync branch. The checked out branch and the commits
+/// ahead/behind are left out, see [query_git_status_with_branch].
+pub fn query_git_status(
+    repo_root_folder: &Path,
+    path: &str,
+    sync_branch_name: &str,
+) -> Result<GitStatus> {
+    query(repo_root_folder, path, sync_branch_name, false)
+}
+
+/// Like [query_git_status], additionally reports the checked out branch
+/// and the number of commits HEAD is ahead/behind the sync branch. Counting
+/// them walks the history, which takes a while in big projects.
+pub fn query_git_status_with_branch(
+    repo_root_folder: &Path,
+    path: &str,
+    sync_branch_name: &str,
+) -> Result<GitStatus> {
+    query(repo_root_folder, path, sync_branch_name, true)
+}
+
+fn query(
+    repo_root_folder: &Path,
+    path: &str,
+    sync_branch_name: &str,
+    with_branch: bool,
+) -> Result<GitStatus> {
+    let repo = Repository::open(paths::join(repo_root_folder, path))
+        .with_context(|| format!("Failed to open git repo at {:?}", path))?;
+    if repo.is_bare() {
+        return Err(git2::Error::from_str("cannot report status on bare repository").into());
+    }
+
+    let statuses = repo.statuses(Some(&mut default_status_options()))?;
+    let workdir = repo.workdir().unwrap_or_else(|| repo.path());
+    let uncommitted_changes = statuses
+        .iter()
+        .any(|entry| !is_nested_project(workdir, &entry));
+
+    let last_repo_sync = repo
+        .find_branch(sync_branch_name, git2::BranchType::Remote)
+        .map(|b| b.get().peel_to_commit())
+        .with_context(|| format!("{:?}", path))??;
+    let head = repo.head()?;
+    let head_commit = head
+        .peel_to_commit()
+        .with_context(|| format!("{:?}", path))?;
+
+    let local_commits = repo.diff_tree_to_tree(
+        Some(&last_repo_sync.tree()?),
+        Some(&head_commit.tree()?),
+        None,
+    )?;
+    let status = GitStatus::new(
+        path,
+        uncommitted_changes,
+        i32::try_from(local_commits.deltas().len()).unwrap_or(i32::MAX),
+    );
+    if !with_branch {
+        return Ok(status);
+    }
+
+    let (ahead, behind) = repo.graph_ahead_behind(head_commit.id(), last_repo_sync.id())?;
+    Ok(GitStatus {
+        branch: match head.is_branch() {
+            true => head.shorthand().map(|b| b.to_string()),
+            false => None,
+        },
+        ahead,
+        behind,
+        ..status
+    })
+}
+
+/// Queries the status of all given projects in parallel, see
+/// [query_git_status]. The statuses are returned in the order of
+/// the projects, every queried project is reported to `progress`.
+pub fn query_git_statuses(
+    repo_root_folder: &Path,
+    projects: &[ProjectInfo],
+    sync_branch_name: &str,
+    progress: &dyn Progress,
+) -> Result<Vec<GitStatus>> {
+    query_all(
+        repo_root_folder,
+        projects,
+        sync_branch_name,
+        false,
+        progress,
+    )
+}
+
+/// Like [query_git_statuses], see [query_git_status_with_branch]
+pub fn query_git_statuses_with_branch(
+    repo_root_folder: &Path,
+    projects: &[ProjectInfo],
+    sync_branch_name: &str,
+    progress: &dyn Progress,
+) -> Result<Vec<GitStatus>> {
+    query_all(repo_root_folder, projects, sync_branch_name, true, progress)
+}
+
+fn query_all(
+    repo_root_folder: &Path,
+    projects: &[ProjectInfo],
+    sync_branch_name: &str,
+    with_branch: bool,
+    progress: &dyn Progress,
+) -> Result<Vec<GitStatus>> {
+    progress.start(projects.len() as u64);
+    let statuses = projects
+        .par_iter()
+        .map(|project| {
+            let status = query(
+                repo_root_folder,
+                &project.path,
+                sync_branch_name,
+                with_branch,
+            );
+            progress.inc();
+            status
+        })
+        .collect();
+    progress.finish();
+    statuses
+}
+
+/// projects can be checked out within other projects, the folder of such
+/// a nested project shows up as untracked in the enclosing one
+fn is_nested_project(workdir: &Path, entry: &StatusEntry) -> bool {
+    entry.status() == Status::WT_NEW
+        && entry
+            .path()
+            .is_some_and(|p| p.ends_with('/') && workdir.join(p).join(".git").exists())
+}
+
+fn default_status_options() -> StatusOptions {
+    let mut opts = StatusOptions::new();
+    opts.include_ignored(false).include_untracked(true);
+    opts
+}
+
+/// The repo tool maintains a branch tracking the last synced state
+/// It is typically named "m/<manifest-branch>" where manifest-branch
+/// is the branch used for "repo init".
+pub fn lookup_sync_branch_name() -> Result<String> {
+    lookup_sync_branch_name_in(&find_repo_manifests_folder()?)
+}
+
+/// same as [lookup_sync_branch_name], but for the given .repo/manifests folder
+pub fn lookup_sync_branch_name_in(manifests_folder: &Path) -> Result<String> {
+    Ok("m/".to_string() + &lookup_manifest_branch_in(manifests_folder)?)
+}
+
+/// The branch of the manifest repo given to "repo init", i.e. the
+/// upstream of the local branch in .repo/manifests, which repo always
+/// names "default".
+pub fn lookup_manifest_branch_in(manifests_folder: &Path) -> Result<String> {
+    // equivalent of running this in .repo/manifests:
+    //git for-each-ref --format '%(upstream:lstrip=-1)' "$(git symbolic-ref -q HEAD)"
+
+    let repo = Repository::open(manifests_folder)
+        .with_context(|| format!("Failed to open git repo at {:?}", manifests_folder))?;
+
+    let head = repo.head()?;
+    if !head.is_branch() {
+        return Err(Error::Manifest(format!(
+            "HEAD of {:?} is not a branch",
+            manifests_folder
+        )));
+    }
+    let upstream = Branch::wrap(head).upstream()?;
+    let upstream_name = upstream
+        .get()
+        .name()
+        .ok_or_else(|| git2::Error::from_str("invalid upstream branch name"))?;
+    Ok(upstream_name
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_string())
+}
@@ -0,0 +1,16 @@
+use crate::error::Result;
+use serde::Serialize;
+use std::io::{self, Write};
+
+/// Prints `record` as one line of json to stdout (JSON Lines), so
+/// consumers can process the results of projects while the remaining
+/// ones are still processed. The line is written and flushed at once,
+/// records printed from multiple threads don't interleave.
+pub fn print<T: Serialize>(record: &T) -> Result<()> {
+    let mut line = serde_json::to_vec(record).map_err(io::Error::from)?;
+    line.push(b'\n');
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(&line)?;
+    stdout.flush()?;
+    Ok(())
+}
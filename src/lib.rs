@@ -1 +1,20 @@
+pub mod apply;
+pub mod config;
+pub mod date;
+pub mod disk_usage;
+pub mod error;
+pub mod exec;
+pub mod generate;
+pub mod git_ops;
+pub mod git_status;
+pub mod json_lines;
+pub mod manifest_cache;
+pub mod notify;
+pub mod pager;
+pub mod paths;
+pub mod progress;
 pub mod repo_project_selector;
+pub mod selection;
+pub mod workspace;
+
+pub use error::{Error, Result};
@@ -0,0 +1,106 @@
+use crate::error::{Error, Result};
+use crate::repo_project_selector::Manifest;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+const CACHE_FILE_NAME: &str = "repo-utils.manifest-cache.json";
+
+/// modification times of a file and, in case it is a symlink, of the link itsself
+type Stamp = (Option<SystemTime>, Option<SystemTime>);
+
+/// A parsed manifest together with the modification times of all
+/// files it has been parsed from
+#[derive(Serialize, Deserialize, Clone)]
+struct CacheEntry {
+    stamps: Vec<(PathBuf, Stamp)>,
+    manifest: Manifest,
+}
+
+impl CacheEntry {
+    fn new(repo_folder: &Path, manifest: &Manifest) -> Self {
+        let mut paths = manifest.files.clone();
+        // catches added or removed local manifests
+        paths.push(repo_folder.join("local_manifests"));
+        paths.dedup();
+
+        CacheEntry {
+            stamps: paths.into_iter().map(|p| (p.clone(), stamp(&p))).collect(),
+            manifest: manifest.clone(),
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        self.stamps.iter().all(|(path, s)| &stamp(path) == s)
+    }
+}
+
+fn stamp(path: &Path) -> Stamp {
+    (
+        fs::metadata(path).and_then(|m| m.modified()).ok(),
+        fs::symlink_metadata(path).and_then(|m| m.modified()).ok(),
+    )
+}
+
+fn in_process_cache() -> &'static Mutex<HashMap<PathBuf, CacheEntry>> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, CacheEntry>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Returns the manifest of the workspace at `repo_folder` (the .repo
+/// folder) from the cache, as long as none of the files it has been
+/// parsed from changed since. Otherwise `parse` is called and its
+/// result is cached. With `use_disk` the cache is additionally kept in
+/// .repo, so it survives between invocations.
+pub fn load_or_parse(
+    repo_folder: &Path,
+    use_disk: bool,
+    parse: impl FnOnce() -> Result<Manifest>,
+) -> Result<Manifest> {
+    let mut cache = in_process_cache()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    if let Some(entry) = cache.get(repo_folder).filter(|e| e.is_valid()) {
+        return Ok(entry.manifest.clone());
+    }
+
+    if use_disk {
+        if let Some(entry) = read_from_disk(repo_folder).filter(|e| e.is_valid()) {
+            let manifest = entry.manifest.clone();
+            cache.insert(repo_folder.to_path_buf(), entry);
+            return Ok(manifest);
+        }
+    }
+
+    let manifest = parse()?;
+    let entry = CacheEntry::new(repo_folder, &manifest);
+    if use_disk {
+        // the cache is an optimization only, failing to write it is fine
+        let _ = write_to_disk(repo_folder, &entry);
+    }
+    cache.insert(repo_folder.to_path_buf(), entry);
+    Ok(manifest)
+}
+
+/// drops all manifests cached in-process
+pub fn clear() {
+    in_process_cache()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clear();
+}
+
+fn read_from_disk(repo_folder: &Path) -> Option<CacheEntry> {
+    let content = fs::read(repo_folder.join(CACHE_FILE_NAME)).ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+fn write_to_disk(repo_folder: &Path, entry: &CacheEntry) -> Result<()> {
+    let content = serde_json::to_vec(entry).map_err(|e| Error::Manifest(e.to_string()))?;
+    fs::write(repo_folder.join(CACHE_FILE_NAME), content)?;
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use crate::error::{Error, Result};
+use crate::exec;
+use crate::git_status::GitStatus;
+use serde::Serialize;
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// the program the webhook is posted with, looked up in PATH
+const CURL: &str = "curl";
+
+/// a webhook which doesn't answer within this time is considered down
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Summary of the dirty projects of a workspace, as posted to a webhook.
+/// Slack and Teams show `text`, generic consumers can use the other
+/// fields.
+#[derive(Serialize, Debug)]
+pub struct DirtySummary<'a> {
+    pub text: String,
+    pub workspace: String,
+    pub total: usize,
+    pub dirty: &'a [GitStatus],
+}
+
+impl<'a> DirtySummary<'a> {
+    /// `dirty` are the dirty ones of the `total` projects scanned
+    pub fn new(root_folder: &Path, total: usize, dirty: &'a [GitStatus]) -> Self {
+        let paths: Vec<&str> = dirty.iter().map(|s| s.path.as_str()).collect();
+        DirtySummary {
+            text: format!(
+                "{}/{} projects of {} deviate from the last repo sync: {}",
+                dirty.len(),
+                total,
+                root_folder.display(),
+                paths.join(", ")
+            ),
+            workspace: root_folder.display().to_string(),
+            total,
+            dirty,
+        }
+    }
+}
+
+/// Fails if curl, which [post] needs, isn't installed. Meant to be
+/// called upfront, before the projects are scanned.
+pub fn check_curl() -> Result<()> {
+    Command::new(CURL)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|_| ())
+        .map_err(|e| missing_curl(e.into()))
+}
+
+/// Posts `summary` as json to the webhook at `url`, with curl
+pub fn post(url: &str, summary: &DirtySummary) -> Result<()> {
+    let payload = serde_json::to_vec(summary).map_err(io::Error::from)?;
+    let argv: Vec<String> = [
+        CURL,
+        "--silent",
+        "--show-error",
+        "--fail",
+        "--header",
+        "Content-Type: application/json",
+        "--data-binary",
+        "@-",
+        url,
+    ]
+    .iter()
+    .map(|a| a.to_string())
+    .collect();
+
+    let output = exec::output_with_timeout(
+        exec::direct_command(&argv)?,
+        Some(&payload),
+        Some(TIMEOUT),
+        false,
+    )
+    .map_err(missing_curl)?;
+    match output.status.success() {
+        true => Ok(()),
+        false => Err(Error::Command(format!(
+            "notifying {} failed: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))),
+    }
+}
+
+fn missing_curl(error: Error) -> Error {
+    match error {
+        Error::Io { error, .. } if error.kind() == io::ErrorKind::NotFound => {
+            Error::Command(format!(
+                "notifying needs {}, which isn't installed or not in PATH",
+                CURL
+            ))
+        }
+        error => error,
+    }
+}
@@ -0,0 +1,83 @@
+use crate::error::Result;
+use std::io;
+
+/// Like git, text output is piped through `$PAGER` (`less` if not set)
+/// while it is written to a terminal. `LESS` defaults to `FRX`, so less
+/// quits right away if the output fits on the screen and passes colors
+/// through.
+pub struct Pager {
+    child: std::process::Child,
+    #[cfg(unix)]
+    stdout: libc::c_int,
+}
+
+/// Redirects stdout into a newly started pager, unless `disabled`, stdout
+/// isn't a terminal or `$PAGER` is empty or `cat`. The output is handed
+/// over to the pager when the returned guard is dropped, which then waits
+/// for the user to quit the pager.
+#[cfg(unix)]
+pub fn start(disabled: bool) -> Result<Option<Pager>> {
+    use crate::exec;
+    use std::env;
+    use std::io::{IsTerminal, Write};
+    use std::os::unix::io::AsRawFd;
+    use std::process::Stdio;
+
+    let pager = env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+    if disabled || !io::stdout().is_terminal() || pager.trim().is_empty() || pager == "cat" {
+        return Ok(None);
+    }
+
+    // whether to colorize is detected from stdout, which is a pipe once
+    // it is redirected, so decide before
+    colored::control::set_override(colored::control::SHOULD_COLORIZE.should_colorize());
+
+    let mut command = exec::shell_command(&pager);
+    if env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+    if env::var_os("LV").is_none() {
+        command.env("LV", "-c");
+    }
+    let mut child = command.stdin(Stdio::piped()).spawn()?;
+    let pipe = child.stdin.take().expect("stdin of the pager is piped");
+
+    io::stdout().flush()?;
+    // SAFETY: only file descriptors owned by this process are duplicated
+    let stdout = unsafe {
+        let stdout = libc::dup(libc::STDOUT_FILENO);
+        if stdout < 0 || libc::dup2(pipe.as_raw_fd(), libc::STDOUT_FILENO) < 0 {
+            let error = io::Error::last_os_error();
+            let _ = child.kill();
+            return Err(error.into());
+        }
+        // quitting the pager before all output is written ends the
+        // process quietly, instead of failing to print
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+        stdout
+    };
+    Ok(Some(Pager { child, stdout }))
+}
+
+/// Paging isn't supported on this platform, output is printed as is
+#[cfg(not(unix))]
+pub fn start(_disabled: bool) -> Result<Option<Pager>> {
+    Ok(None)
+}
+
+impl Drop for Pager {
+    fn drop(&mut self) {
+        use std::io::Write;
+
+        let _ = io::stdout().flush();
+        // restoring stdout closes the pipe, which tells the pager
+        // that all output is written
+        #[cfg(unix)]
+        // SAFETY: self.stdout is the duplicate of stdout made in start()
+        unsafe {
+            libc::dup2(self.stdout, libc::STDOUT_FILENO);
+            libc::close(self.stdout);
+        }
+        let _ = self.child.wait();
+    }
+}
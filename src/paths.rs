@@ -0,0 +1,74 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// on windows, longer paths have to be given in the extended-length form
+/// `\\?\C:\...`, e.g. the ones of projects deep inside a Yocto workspace
+#[cfg(windows)]
+const MAX_PATH: usize = 260;
+
+/// Like fs::canonicalize, but on windows the extended-length prefix
+/// fs::canonicalize always adds is dropped again if the path is short
+/// enough without, since many programs can't deal with it (e.g. cmd.exe
+/// refuses it as working directory).
+pub fn canonicalize(path: &Path) -> io::Result<PathBuf> {
+    fs::canonicalize(path).map(shorten)
+}
+
+/// The absolute path of `relative` (e.g. the path of a project, always
+/// separated by '/' in the manifest) below `root`, in the extended-length
+/// form on windows if it's too long otherwise.
+pub fn join(root: &Path, relative: &str) -> PathBuf {
+    let mut path = root.to_path_buf();
+    // extended-length paths take '\' as separator only
+    path.extend(relative.split('/').filter(|c| !c.is_empty() && *c != "."));
+    lengthen(path)
+}
+
+#[cfg(windows)]
+fn shorten(path: PathBuf) -> PathBuf {
+    use std::path::{Component, Prefix};
+
+    let short = match (path.components().next(), path.to_str()) {
+        (Some(Component::Prefix(prefix)), Some(text)) => match prefix.kind() {
+            Prefix::VerbatimDisk(_) => Some(PathBuf::from(&text[r"\\?\".len()..])),
+            Prefix::VerbatimUNC(..) => {
+                Some(PathBuf::from(format!(r"\\{}", &text[r"\\?\UNC\".len()..])))
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+    match short {
+        Some(short) if short.as_os_str().len() < MAX_PATH => short,
+        _ => path,
+    }
+}
+
+#[cfg(not(windows))]
+fn shorten(path: PathBuf) -> PathBuf {
+    path
+}
+
+#[cfg(windows)]
+fn lengthen(path: PathBuf) -> PathBuf {
+    use std::path::{Component, Prefix};
+
+    if path.as_os_str().len() < MAX_PATH {
+        return path;
+    }
+    let long = match (path.components().next(), path.to_str()) {
+        (Some(Component::Prefix(prefix)), Some(text)) => match prefix.kind() {
+            Prefix::Disk(_) => Some(PathBuf::from(format!(r"\\?\{}", text))),
+            Prefix::UNC(..) => Some(PathBuf::from(format!(r"\\?\UNC\{}", &text[r"\\".len()..]))),
+            _ => None,
+        },
+        _ => None,
+    };
+    long.unwrap_or(path)
+}
+
+#[cfg(not(windows))]
+fn lengthen(path: PathBuf) -> PathBuf {
+    path
+}
@@ -0,0 +1,35 @@
+use indicatif::ProgressBar;
+
+/// Receives the progress of long running operations, e.g. while the
+/// status of all projects is queried. Steps might be reported from
+/// multiple threads concurrently.
+pub trait Progress: Sync {
+    /// `len` steps are about to be processed
+    fn start(&self, _len: u64) {}
+
+    /// one more step is done
+    fn inc(&self) {}
+
+    /// all steps are done
+    fn finish(&self) {}
+}
+
+/// Progress drawn as bar on the terminal
+impl Progress for ProgressBar {
+    fn start(&self, len: u64) {
+        self.set_length(len);
+    }
+
+    fn inc(&self) {
+        ProgressBar::inc(self, 1);
+    }
+
+    fn finish(&self) {
+        ProgressBar::finish(self);
+    }
+}
+
+/// Ignores all progress, e.g. for library consumers without terminal
+pub struct NoProgress;
+
+impl Progress for NoProgress {}
@@ -1,65 +1,341 @@
-use anyhow::{anyhow, bail, Result};
-use serde::Deserialize;
+use crate::config::Config;
+use crate::error::{Error, Result, ResultExt};
+use crate::manifest_cache;
+use crate::paths;
+use crate::workspace::Workspace;
+use clap::ValueEnum;
+use git2::Repository;
+use glob::{MatchOptions, Pattern};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use serde_xml_rs::from_reader;
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::fs::File;
+use std::io;
 use std::io::BufRead;
 use std::io::BufReader;
+use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
 
-/// The repo-tool keeps a list of synced projects at
-/// .repo/project.list
-/// This function can filter the list of projects by groups
-/// and/or manifest files. If a group *and* manifest filter
-/// are given, the list will contain the intersection.
-/// Additionally the function can include the manifest repo
-/// itsself into the list (.repo/manifests).
-pub fn select_projects(
+/// Selects projects of the workspace, based on the list of synced
+/// projects the repo-tool keeps at .repo/project.list:
+///
+/// ```no_run
+/// # use repo_utils::repo_project_selector::ProjectSelector;
+/// let projects = ProjectSelector::new()
+///     .groups(vec!["electrical".to_string()])
+///     .path_globs(vec!["vendor/**".to_string()])
+///     .select()?;
+/// # Ok::<(), repo_utils::Error>(())
+/// ```
+///
+/// The list can be filtered by groups, manifest files, glob patterns
+/// matching the project's path and/or a regex matching the project's
+/// name. If multiple filters are given, the list will contain the
+/// intersection, or the union with [FilterMode::Union].
+#[derive(Debug, Clone, Default)]
+pub struct ProjectSelector {
     include_manifest_repo: bool,
-    filter_by_groups: Option<Vec<String>>,
-    filter_by_manifest_files: Option<Vec<PathBuf>>,
-) -> Result<Vec<String>> {
-    let projects_on_disk = lines_from_file(find_project_list()?)?;
-    let mut selected_projects = projects_on_disk;
-
-    if let Some(groups) = filter_by_groups {
-        let manifest = parse_manifest(&find_repo_folder()?.join("manifest.xml"))?;
-        selected_projects = selected_projects
-            .drain(..)
-            .filter(|path| {
-                let project = manifest.find_project(path);
-                project.is_some() && project.unwrap().in_any_given_group(&groups)
-            })
-            .collect();
+    include_missing: bool,
+    scan_filesystem: bool,
+    groups: Option<Vec<String>>,
+    manifest_files: Option<Vec<PathBuf>>,
+    path_globs: Option<Vec<String>>,
+    name_regex: Option<Regex>,
+    filter_mode: FilterMode,
+}
+
+/// decides whether the project at the given path is selected
+type PathFilter = Box<dyn Fn(&str) -> bool>;
+
+/// How multiple filters of a [ProjectSelector] are combined
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum FilterMode {
+    /// select projects matching all filters
+    #[default]
+    Intersection,
+    /// select projects matching any of the filters
+    Union,
+}
+
+impl ProjectSelector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// additionally select the manifest repo itsself (.repo/manifests)
+    pub fn include_manifest_repo(mut self, include: bool) -> Self {
+        self.include_manifest_repo = include;
+        self
+    }
+
+    /// additionally select projects defined in the manifest which
+    /// haven't been synced (yet), they are appended in manifest order
+    pub fn include_missing(mut self, include: bool) -> Self {
+        self.include_missing = include;
+        self
+    }
+
+    /// additionally discover projects by scanning the workspace for git
+    /// repos, for workspaces with a missing or stale project.list
+    pub fn scan_filesystem(mut self, scan: bool) -> Self {
+        self.scan_filesystem = scan;
+        self
+    }
+
+    /// only select projects matching the given group filter,
+    /// see [Project::matches_groups]
+    pub fn groups(mut self, groups: impl Into<Option<Vec<String>>>) -> Self {
+        self.groups = groups.into();
+        self
+    }
+
+    /// only select projects defined in the given manifest files
+    /// (relative to .repo/manifests)
+    pub fn manifest_files(mut self, manifest_files: impl Into<Option<Vec<PathBuf>>>) -> Self {
+        self.manifest_files = manifest_files.into();
+        self
+    }
+
+    /// only select projects whose path matches any of the given globs
+    pub fn path_globs(mut self, path_globs: impl Into<Option<Vec<String>>>) -> Self {
+        self.path_globs = path_globs.into();
+        self
+    }
+
+    /// only select projects whose name matches the given regex
+    pub fn name_regex(mut self, name_regex: impl Into<Option<Regex>>) -> Self {
+        self.name_regex = name_regex.into();
+        self
+    }
+
+    /// how multiple filters are combined, defaults to intersection
+    pub fn filter_mode(mut self, filter_mode: FilterMode) -> Self {
+        self.filter_mode = filter_mode;
+        self
+    }
+
+    /// returns the paths of the selected projects of the
+    /// workspace the current working directory is part of
+    pub fn select(&self) -> Result<Vec<String>> {
+        self.select_in(&Workspace::current()?)
     }
 
-    if let Some(manifest_files) = filter_by_manifest_files {
-        let repo_manifests_folder = find_repo_manifests_folder()?;
-        let mut aggregated_manifest = Manifest::empty();
-        for manifest_file in manifest_files {
-            let manifest = parse_manifest(&repo_manifests_folder.join(&manifest_file))?;
-            aggregated_manifest.append(&manifest);
+    /// returns the paths of the selected projects of the given workspace
+    pub fn select_in(&self, workspace: &Workspace) -> Result<Vec<String>> {
+        let mut selected_projects = match self.scan_filesystem {
+            true => {
+                let mut projects = workspace.project_list().unwrap_or_default();
+                for path in scan_for_projects(workspace.root_folder())? {
+                    if !projects.contains(&path) {
+                        projects.push(path);
+                    }
+                }
+                projects
+            }
+            false => workspace.project_list()?,
+        };
+
+        if self.include_missing {
+            let manifest = workspace.manifest()?;
+            for project in &manifest.projects {
+                if !selected_projects.contains(&project.path) {
+                    selected_projects.push(project.path.clone());
+                }
+            }
         }
-        selected_projects = selected_projects
-            .drain(..)
-            .filter(|p| aggregated_manifest.contains_project(p))
-            .collect();
+
+        let mut filters: Vec<PathFilter> = vec![];
+
+        if let Some(groups) = self.groups.clone() {
+            let manifest = workspace.manifest()?.clone();
+            filters.push(Box::new(move |path| {
+                manifest
+                    .find_project(path)
+                    .is_some_and(|p| p.matches_groups(&groups))
+            }));
+        }
+
+        if let Some(manifest_files) = &self.manifest_files {
+            let repo_manifests_folder = workspace.manifests_folder();
+            let mut aggregated_manifest = Manifest::empty();
+            for manifest_file in manifest_files {
+                let manifest = parse_manifest_in(
+                    &repo_manifests_folder.join(manifest_file),
+                    &repo_manifests_folder,
+                )?;
+                aggregated_manifest.append(&manifest);
+            }
+            filters.push(Box::new(move |path| {
+                aggregated_manifest.contains_project(path)
+            }));
+        }
+
+        if let Some(path_globs) = &self.path_globs {
+            let patterns = path_globs
+                .iter()
+                .map(|g| {
+                    Pattern::new(g)
+                        .map_err(|e| Error::Manifest(format!("Invalid glob {:?}: {}", g, e)))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let options = MatchOptions {
+                require_literal_separator: true,
+                ..MatchOptions::new()
+            };
+            filters.push(Box::new(move |path| {
+                patterns.iter().any(|p| p.matches_with(path, options))
+            }));
+        }
+
+        if let Some(name_regex) = self.name_regex.clone() {
+            let manifest = workspace.manifest()?.clone();
+            filters.push(Box::new(move |path| {
+                manifest
+                    .find_project(path)
+                    .is_some_and(|p| name_regex.is_match(&p.name))
+            }));
+        }
+
+        if !filters.is_empty() {
+            selected_projects.retain(|path| match self.filter_mode {
+                FilterMode::Intersection => filters.iter().all(|f| f(path)),
+                FilterMode::Union => filters.iter().any(|f| f(path)),
+            });
+        }
+
+        if self.include_manifest_repo {
+            selected_projects.push(".repo/manifests".to_string());
+        }
+
+        Ok(selected_projects)
     }
 
-    if include_manifest_repo {
-        selected_projects.push(".repo/manifests".to_string());
+    /// same as [ProjectSelector::select], but returns the selected
+    /// projects together with their attributes from the manifest
+    pub fn select_infos(&self) -> Result<Vec<ProjectInfo>> {
+        self.select_infos_in(&Workspace::current()?)
     }
 
-    Ok(selected_projects)
+    /// same as [ProjectSelector::select_in], but returns the selected
+    /// projects together with their attributes from the manifest
+    pub fn select_infos_in(&self, workspace: &Workspace) -> Result<Vec<ProjectInfo>> {
+        let paths = self.select_in(workspace)?;
+        let manifest = workspace.manifest()?;
+
+        Ok(paths
+            .into_iter()
+            .map(|path| ProjectInfo::new(manifest, workspace.root_folder(), path))
+            .collect())
+    }
 }
 
-fn lines_from_file(filename: impl AsRef<Path>) -> Result<Vec<String>> {
+/// A selected project with its attributes resolved from the manifest
+/// (defaults applied). Projects which aren't defined in the manifest
+/// (e.g. the manifest repo itself) are named after their path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProjectInfo {
+    pub path: String,
+    pub name: String,
+    pub groups: Vec<String>,
+    pub revision: Option<String>,
+    pub remote: Option<String>,
+    pub upstream: Option<String>,
+    pub dest_branch: Option<String>,
+    pub annotations: BTreeMap<String, String>,
+    pub absolute_path: PathBuf,
+}
+
+impl ProjectInfo {
+    pub fn new(manifest: &Manifest, repo_root_folder: &Path, path: String) -> Self {
+        let project = manifest.find_project(&path);
+        let owned = |s: Option<&str>| s.map(|s| s.to_string());
+        ProjectInfo {
+            name: project.map_or_else(|| path.clone(), |p| p.name.clone()),
+            groups: project.map_or_else(Vec::new, |p| {
+                p.group_names().iter().map(|g| g.to_string()).collect()
+            }),
+            revision: owned(project.and_then(|p| manifest.revision_of(p))),
+            remote: owned(project.and_then(|p| manifest.remote_of(p))),
+            upstream: owned(project.and_then(|p| manifest.upstream_of(p))),
+            dest_branch: owned(project.and_then(|p| manifest.dest_branch_of(p))),
+            annotations: project.map_or_else(BTreeMap::new, |p| {
+                p.annotation_map()
+                    .into_iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect()
+            }),
+            absolute_path: paths::join(repo_root_folder, &path),
+            path,
+        }
+    }
+}
+
+/// Shorthand for [ProjectSelector], kept for compatibility
+pub fn select_projects(
+    include_manifest_repo: bool,
+    filter_by_groups: Option<Vec<String>>,
+    filter_by_manifest_files: Option<Vec<PathBuf>>,
+    filter_by_path_globs: Option<Vec<String>>,
+    filter_by_name_regex: Option<Regex>,
+) -> Result<Vec<String>> {
+    ProjectSelector::new()
+        .include_manifest_repo(include_manifest_repo)
+        .groups(filter_by_groups)
+        .manifest_files(filter_by_manifest_files)
+        .path_globs(filter_by_path_globs)
+        .name_regex(filter_by_name_regex)
+        .select()
+}
+
+/// the path of a project as given in the manifest, separated by '/'
+fn project_path(folder: &Path) -> String {
+    let components: Vec<_> = folder.iter().map(|c| c.to_string_lossy()).collect();
+    components.join("/")
+}
+
+/// Walks the given workspace and returns the paths (relative to
+/// the workspace) of all git repos found, except the ones in .repo
+pub fn scan_for_projects(repo_root_folder: &Path) -> Result<Vec<String>> {
+    let mut projects = vec![];
+    let mut folders = vec![PathBuf::new()];
+    while let Some(folder) = folders.pop() {
+        let absolute_folder = repo_root_folder.join(&folder);
+        if absolute_folder.is_symlink() {
+            // a project linked in from elsewhere, links aren't walked
+            // any further as they might lead in circles
+            projects.push(project_path(&folder));
+            continue;
+        }
+        let mut subfolders = vec![];
+        for entry in fs::read_dir(absolute_folder)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let file_type = entry.file_type()?;
+            if name == ".git" {
+                projects.push(project_path(&folder));
+            } else if (name != ".repo" && file_type.is_dir())
+                || (file_type.is_symlink() && entry.path().join(".git").exists())
+            {
+                subfolders.push(folder.join(name));
+            }
+        }
+        // projects can be nested, so keep on walking
+        subfolders.sort();
+        folders.extend(subfolders.into_iter().rev());
+    }
+    projects.retain(|p| !p.is_empty());
+    Ok(projects)
+}
+
+pub(crate) fn lines_from_file(filename: impl AsRef<Path>) -> Result<Vec<String>> {
     BufReader::new(File::open(filename)?)
         .lines()
         .collect::<Result<Vec<_>, _>>()
-        .map_err(anyhow::Error::msg)
+        .map_err(Error::from)
 }
 
 /// returns a path pointing to he project.list file in
@@ -69,7 +345,9 @@ pub fn find_project_list() -> Result<PathBuf> {
     let find_project_list = find_repo_folder()?.join("project.list");
     match find_project_list.is_file() {
         true => Ok(find_project_list),
-        false => Err(anyhow!("no project.list in .repo found")),
+        false => Err(Error::Manifest(
+            "no project.list in .repo found".to_string(),
+        )),
     }
 }
 
@@ -93,51 +371,142 @@ pub fn find_repo_manifests_folder() -> Result<PathBuf> {
 /// or io::Error in case the .repo folder couldn't been
 /// found in the cwd or any of its parent folders.
 pub fn find_repo_root_folder() -> Result<PathBuf> {
-    let cwd = env::current_dir()?;
-    for parent in cwd.ancestors() {
-        for entry in fs::read_dir(&parent)? {
+    find_repo_root_folder_from(&env::current_dir()?)
+}
+
+/// same as [find_repo_root_folder], but starts looking in `path`
+/// instead of the cwd
+pub fn find_repo_root_folder_from(path: &Path) -> Result<PathBuf> {
+    for parent in path.ancestors() {
+        for entry in fs::read_dir(parent)? {
             let entry = entry?;
             if entry.path().is_dir() && entry.file_name() == ".repo" {
                 return Ok(parent.to_path_buf());
             }
         }
     }
-    bail!("no .repo folder found")
+    Err(Error::NoWorkspace)
+}
+
+/// Parses .repo/manifest.xml (including all its includes) and merges
+/// the overlays found in .repo/local_manifests in sorted order, which
+/// results in the same set of projects "repo sync" works on.
+///
+/// The result is cached in-process, and on disk as well if enabled
+/// by `manifest_cache` in the defaults of the configuration.
+pub fn parse_repo_manifest() -> Result<Manifest> {
+    let use_disk_cache = Config::load()?.defaults.manifest_cache;
+    parse_repo_manifest_in(&find_repo_folder()?, use_disk_cache)
+}
+
+/// same as [parse_repo_manifest], but for the given .repo folder,
+/// with `use_disk_cache` the cache is kept on disk as well
+pub fn parse_repo_manifest_in(repo_folder: &Path, use_disk_cache: bool) -> Result<Manifest> {
+    manifest_cache::load_or_parse(repo_folder, use_disk_cache, || {
+        parse_repo_manifest_uncached(repo_folder)
+    })
+}
+
+fn parse_repo_manifest_uncached(repo_folder: &Path) -> Result<Manifest> {
+    let mut manifest = parse_manifest_in(
+        &repo_folder.join("manifest.xml"),
+        &repo_folder.join("manifests"),
+    )?;
+
+    let local_manifests_folder = repo_folder.join("local_manifests");
+    if local_manifests_folder.is_dir() {
+        let mut local_manifests = fs::read_dir(&local_manifests_folder)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        local_manifests.retain(|path| path.extension().is_some_and(|ext| ext == "xml"));
+        local_manifests.sort();
+
+        for path in local_manifests {
+            let local_manifest =
+                parse(&path).with_context(|| format!("Failed to parse {:?}", path))?;
+            manifest.append(&local_manifest);
+        }
+    }
+    Ok(manifest)
 }
 
 pub fn parse_manifest(path: &Path) -> Result<Manifest> {
-    let file = File::open(path).map_err(|e| anyhow!("Unable to open {:?}: {}", path, e))?;
-    let reader = BufReader::new(file);
-    let mut manifest: Manifest = from_reader(reader)?;
-    let includes: Vec<String> = manifest.includes.iter().map(|i| i.name.clone()).collect();
-    for include in &includes {
-        let path = find_repo_manifests_folder()?.join(include);
-        let child = parse(&path).map_err(|e| anyhow!("Failed to parse {}: {}", include, e))?;
+    parse_manifest_with(path, &find_repo_manifests_folder)
+}
+
+/// same as [parse_manifest], but includes are resolved relative
+/// to the given .repo/manifests folder
+pub fn parse_manifest_in(path: &Path, manifests_folder: &Path) -> Result<Manifest> {
+    parse_manifest_with(path, &|| Ok(manifests_folder.to_path_buf()))
+}
+
+/// the manifests folder is looked up only if there are includes
+fn parse_manifest_with(
+    path: &Path,
+    manifests_folder: &dyn Fn() -> Result<PathBuf>,
+) -> Result<Manifest> {
+    let mut manifest = parse_file(path)?;
+    let own_projects = manifest.projects.len();
+    for include in manifest.includes.clone() {
+        let path = manifests_folder()?.join(&include.name);
+        let mut child =
+            parse(&path).with_context(|| format!("Failed to parse {}", include.name))?;
+        child.add_groups_of(&include);
         manifest.append(&child);
     }
+    manifest.remove_included_projects(own_projects);
+    manifest.apply_extensions(&manifest.extend_projects.clone());
     Ok(manifest)
 }
 
 pub fn parse(path: &Path) -> Result<Manifest> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    let mut manifest: Manifest = from_reader(reader)?;
-    let includes: Vec<String> = manifest.includes.iter().map(|i| i.name.clone()).collect();
-    for include in &includes {
-        let path = path.with_file_name(include);
-        let child = parse(&path).map_err(|e| anyhow!("Failed to parse {}: {}", include, e))?;
+    let mut manifest = parse_file(path)?;
+    let own_projects = manifest.projects.len();
+    for include in manifest.includes.clone() {
+        let path = path.with_file_name(&include.name);
+        let mut child =
+            parse(&path).with_context(|| format!("Failed to parse {}", include.name))?;
+        child.add_groups_of(&include);
         manifest.append(&child);
     }
+    manifest.remove_included_projects(own_projects);
+    manifest.apply_extensions(&manifest.extend_projects.clone());
+    Ok(manifest)
+}
+
+/// parses a single manifest file without following its includes
+pub fn parse_file(path: &Path) -> Result<Manifest> {
+    let file = File::open(path).with_context(|| format!("Unable to open {:?}", path))?;
+    let reader = BufReader::new(file);
+    let mut manifest: Manifest = from_reader(reader).map_err(|e| Error::Manifest(e.to_string()))?;
+    // like in the repo tool, the path of a project defaults to its name
+    for project in manifest.projects.iter_mut().filter(|p| p.path.is_empty()) {
+        project.path = project.name.clone();
+    }
+    manifest.files.push(path.to_path_buf());
     Ok(manifest)
 }
 
 /// OO representation of a repo-tool's manifest xml element
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Manifest {
     #[serde(rename = "project", default)]
     pub projects: Vec<Project>,
     #[serde(rename = "include", default)]
     pub includes: Vec<Include>,
+    #[serde(rename = "default", default)]
+    pub defaults: Option<Defaults>,
+    #[serde(rename = "remote", default)]
+    pub remotes: Vec<Remote>,
+    #[serde(rename = "remove-project", default)]
+    pub remove_projects: Vec<RemoveProject>,
+    #[serde(rename = "extend-project", default)]
+    pub extend_projects: Vec<ExtendProject>,
+    #[serde(default)]
+    pub superproject: Option<Superproject>,
+    /// the manifest files this manifest has been aggregated from
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
 }
 
 impl Manifest {
@@ -145,12 +514,226 @@ impl Manifest {
         Manifest {
             projects: vec![],
             includes: vec![],
+            defaults: None,
+            remotes: vec![],
+            remove_projects: vec![],
+            extend_projects: vec![],
+            superproject: None,
+            files: vec![],
         }
     }
 
+    /// Merges `manifest` into this one. Its remove-project elements apply
+    /// to the projects collected so far only, not to the ones it defines
+    /// itself or which are appended later on.
     pub fn append(&mut self, manifest: &Manifest) {
+        self.apply_removals(&manifest.remove_projects);
         let projects = &manifest.projects;
         self.projects.extend(projects.iter().cloned());
+        self.remotes.extend(manifest.remotes.iter().cloned());
+        self.files.extend(manifest.files.iter().cloned());
+        if self.defaults.is_none() {
+            self.defaults = manifest.defaults.clone();
+        }
+        if self.superproject.is_none() {
+            self.superproject = manifest.superproject.clone();
+        }
+        self.apply_extensions(&manifest.extend_projects);
+    }
+
+    /// drops all projects matching one of the given remove-project elements
+    pub fn apply_removals(&mut self, removals: &[RemoveProject]) {
+        self.projects
+            .retain(|project| !removals.iter().any(|r| r.matches(project)));
+    }
+
+    /// applies the manifest's own remove-project elements to the projects
+    /// it includes, i.e. all but the first `own_projects`; its own projects
+    /// are kept, as they might be the replacements of removed ones
+    fn remove_included_projects(&mut self, own_projects: usize) {
+        let mut included = self.projects.split_off(own_projects);
+        included.retain(|project| !self.remove_projects.iter().any(|r| r.matches(project)));
+        self.projects.extend(included);
+    }
+
+    /// merges the attributes of the given extend-project elements
+    /// into the matching projects
+    pub fn apply_extensions(&mut self, extensions: &[ExtendProject]) {
+        for extension in extensions {
+            self.projects
+                .iter_mut()
+                .filter(|project| extension.matches(project))
+                .for_each(|project| extension.extend(project));
+        }
+    }
+
+    /// the groups of an include element apply to all projects
+    /// of the included manifest
+    pub fn add_groups_of(&mut self, include: &Include) {
+        if let Some(groups) = &include.groups {
+            self.projects.iter_mut().for_each(|p| p.add_groups(groups));
+        }
+    }
+
+    pub fn find_remote(&self, name: &str) -> Option<&Remote> {
+        self.remotes.iter().find(|r| r.name == name)
+    }
+
+    /// name of the project's remote, falls back to the default remote
+    pub fn remote_of<'a>(&'a self, project: &'a Project) -> Option<&'a str> {
+        project
+            .remote
+            .as_deref()
+            .or_else(|| self.defaults.as_ref()?.remote.as_deref())
+    }
+
+    /// revision of the superproject, falls back to the default revision
+    pub fn superproject_revision(&self) -> Option<&str> {
+        let superproject = self.superproject.as_ref()?;
+        superproject
+            .revision
+            .as_deref()
+            .or_else(|| self.defaults.as_ref()?.revision.as_deref())
+    }
+
+    /// revision expression of the project, falls back to the revision
+    /// of the project's remote and then to the default revision
+    pub fn revision_of<'a>(&'a self, project: &'a Project) -> Option<&'a str> {
+        project
+            .revision
+            .as_deref()
+            .or_else(|| {
+                let remote = self.find_remote(self.remote_of(project)?)?;
+                remote.revision.as_deref()
+            })
+            .or_else(|| self.defaults.as_ref()?.revision.as_deref())
+    }
+
+    /// upstream of the project, falls back to the default upstream
+    pub fn upstream_of<'a>(&'a self, project: &'a Project) -> Option<&'a str> {
+        project
+            .upstream
+            .as_deref()
+            .or_else(|| self.defaults.as_ref()?.upstream.as_deref())
+    }
+
+    /// branch changes are uploaded to, falls back to the default dest-branch
+    pub fn dest_branch_of<'a>(&'a self, project: &'a Project) -> Option<&'a str> {
+        project
+            .dest_branch
+            .as_deref()
+            .or_else(|| self.defaults.as_ref()?.dest_branch.as_deref())
+    }
+
+    /// Writes the remotes, the default element and the projects as
+    /// manifest xml. Includes, remove-project and extend-project elements
+    /// are not written, they are expected to be resolved already.
+    pub fn write_xml(&self, w: &mut impl Write) -> io::Result<()> {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(w, "<manifest>")?;
+        for remote in &self.remotes {
+            let attributes = [
+                ("name", Some(&remote.name)),
+                ("alias", remote.alias.as_ref()),
+                ("fetch", Some(&remote.fetch)),
+                ("pushurl", remote.pushurl.as_ref()),
+                ("review", remote.review.as_ref()),
+                ("revision", remote.revision.as_ref()),
+            ];
+            writeln!(w, "  <remote{}/>", xml_attributes(&attributes))?;
+        }
+        if let Some(defaults) = &self.defaults {
+            let sync_j = defaults.sync_j.map(|j| j.to_string());
+            let attributes = [
+                ("remote", defaults.remote.as_ref()),
+                ("revision", defaults.revision.as_ref()),
+                ("upstream", defaults.upstream.as_ref()),
+                ("dest-branch", defaults.dest_branch.as_ref()),
+                ("sync-j", sync_j.as_ref()),
+            ];
+            writeln!(w, "  <default{}/>", xml_attributes(&attributes))?;
+        }
+        if let Some(superproject) = &self.superproject {
+            let attributes = [
+                ("name", Some(&superproject.name)),
+                ("remote", superproject.remote.as_ref()),
+                ("revision", superproject.revision.as_ref()),
+            ];
+            writeln!(w, "  <superproject{}/>", xml_attributes(&attributes))?;
+        }
+        for project in &self.projects {
+            let attributes = [
+                ("name", Some(&project.name)),
+                ("path", Some(&project.path)),
+                ("remote", project.remote.as_ref()),
+                ("revision", project.revision.as_ref()),
+                ("upstream", project.upstream.as_ref()),
+                ("dest-branch", project.dest_branch.as_ref()),
+                ("groups", project.groups.as_ref()),
+            ];
+            if project.copyfiles.is_empty()
+                && project.linkfiles.is_empty()
+                && project.annotations.is_empty()
+            {
+                writeln!(w, "  <project{}/>", xml_attributes(&attributes))?;
+                continue;
+            }
+            writeln!(w, "  <project{}>", xml_attributes(&attributes))?;
+            let children = project
+                .copyfiles
+                .iter()
+                .map(|c| ("copyfile", c))
+                .chain(project.linkfiles.iter().map(|l| ("linkfile", l)));
+            for (element, operation) in children {
+                let attributes = [
+                    ("src", Some(&operation.src)),
+                    ("dest", Some(&operation.dest)),
+                ];
+                writeln!(w, "    <{}{}/>", element, xml_attributes(&attributes))?;
+            }
+            for annotation in &project.annotations {
+                let attributes = [
+                    ("name", Some(&annotation.name)),
+                    ("value", Some(&annotation.value)),
+                    ("keep", annotation.keep.as_ref()),
+                ];
+                writeln!(w, "    <annotation{}/>", xml_attributes(&attributes))?;
+            }
+            writeln!(w, "  </project>")?;
+        }
+        writeln!(w, "</manifest>")
+    }
+
+    /// Same as "repo manifest -r": the revision of every given project is
+    /// replaced by the sha of its HEAD, the original revision is kept as
+    /// upstream. Projects which are not defined in the manifest are skipped.
+    pub fn snapshot(&self, list_of_projects: &[ProjectInfo]) -> Result<Manifest> {
+        let mut snapshot = Manifest::empty();
+        snapshot.remotes = self.remotes.clone();
+        snapshot.defaults = self.defaults.clone();
+
+        for info in list_of_projects {
+            let path = &info.path;
+            let mut project = match self.find_project(path) {
+                Some(project) => project.clone(),
+                None => continue,
+            };
+
+            let repo = Repository::open(&info.absolute_path)
+                .with_context(|| format!("Failed to open git repo at {:?}", path))?;
+            let head = repo
+                .head()
+                .and_then(|h| h.peel_to_commit())
+                .with_context(|| format!("{:?} has no HEAD commit", path))?;
+
+            if project.upstream.is_none() {
+                project.upstream = self.revision_of(&project).map(|r| r.to_string());
+            }
+            project.revision = Some(head.id().to_string());
+            snapshot.projects.push(project);
+        }
+
+        Ok(snapshot)
     }
 
     pub fn contains_project(&self, local_path: &str) -> bool {
@@ -160,33 +743,247 @@ impl Manifest {
     pub fn find_project(&self, local_path: &str) -> Option<&Project> {
         self.projects.iter().find(|p| p.path == local_path)
     }
+
+    /// sorts the given project paths in the order the projects are
+    /// defined in the manifest, unknown paths are moved to the end
+    pub fn sort_by_manifest_order(&self, mut paths: Vec<String>) -> Vec<String> {
+        paths.sort_by_key(|path| self.position_of(path).unwrap_or(usize::MAX));
+        paths
+    }
+
+    /// index of the project in the list of projects of the manifest
+    pub fn position_of(&self, local_path: &str) -> Option<usize> {
+        self.projects.iter().position(|p| p.path == local_path)
+    }
+}
+
+fn xml_attributes(attributes: &[(&str, Option<&String>)]) -> String {
+    attributes
+        .iter()
+        .filter_map(|(key, value)| Some(format!(r#" {}="{}""#, key, xml_escape(value.as_ref()?))))
+        .collect()
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
 
 /// OO representation of a repo-tool's project xml element
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Project {
     pub name: String,
+    #[serde(default)]
     pub path: String,
     pub groups: Option<String>,
+    pub revision: Option<String>,
+    pub remote: Option<String>,
+    pub upstream: Option<String>,
+    #[serde(rename = "dest-branch")]
+    pub dest_branch: Option<String>,
+    #[serde(rename = "copyfile", default)]
+    pub copyfiles: Vec<FileOperation>,
+    #[serde(rename = "linkfile", default)]
+    pub linkfiles: Vec<FileOperation>,
+    #[serde(rename = "annotation", default)]
+    pub annotations: Vec<Annotation>,
 }
 
 impl Project {
     pub fn in_any_given_group(&self, test_for_groups: &[String]) -> bool {
-        let project_groups: Vec<String> = self
-            .groups
-            .as_ref()
-            .unwrap_or(&String::new())
-            .split(&[',', ' '][..])
-            .map(|s| s.to_string())
-            .collect();
-        project_groups
+        self.group_names()
             .iter()
             .any(|g| test_for_groups.iter().any(|other| g == other))
     }
+
+    /// Evaluates a group filter the way the repo tool does it: every
+    /// entry (entries may be comma separated lists) which is one of the
+    /// project's groups selects the project, entries prefixed with '-'
+    /// deselect it again. The last matching entry wins, an empty filter
+    /// equals "default".
+    pub fn matches_groups(&self, filter: &[String]) -> bool {
+        let project_groups = self.expanded_group_names();
+        let mut filter: Vec<&str> = filter
+            .iter()
+            .flat_map(|g| g.split(','))
+            .filter(|g| !g.is_empty())
+            .collect();
+        if filter.is_empty() {
+            filter.push("default");
+        }
+
+        let mut matched = false;
+        for group in filter {
+            if let Some(excluded) = group.strip_prefix('-') {
+                if project_groups.iter().any(|g| g == excluded) {
+                    matched = false;
+                }
+            } else if project_groups.iter().any(|g| g == group) {
+                matched = true;
+            }
+        }
+        matched
+    }
+
+    /// the project's groups plus the implicit ones the repo tool adds:
+    /// "all", "name:<name>", "path:<path>" and "default" unless the
+    /// project is marked as "notdefault"
+    pub fn expanded_group_names(&self) -> Vec<String> {
+        let mut groups: Vec<String> = self.group_names().iter().map(|g| g.to_string()).collect();
+        groups.push("all".to_string());
+        groups.push(format!("name:{}", self.name));
+        groups.push(format!("path:{}", self.path));
+        if !groups.iter().any(|g| g == "notdefault") {
+            groups.push("default".to_string());
+        }
+        groups
+    }
+
+    /// the project's annotations as map of name to value
+    pub fn annotation_map(&self) -> BTreeMap<&str, &str> {
+        self.annotations
+            .iter()
+            .map(|a| (a.name.as_str(), a.value.as_str()))
+            .collect()
+    }
+
+    /// adds the given (comma or space separated) groups, skipping
+    /// the ones the project is already part of
+    pub fn add_groups(&mut self, groups: &str) {
+        let mut names: Vec<String> = self.group_names().iter().map(|g| g.to_string()).collect();
+        for group in groups.split(&[',', ' '][..]).filter(|g| !g.is_empty()) {
+            if !names.iter().any(|g| g == group) {
+                names.push(group.to_string());
+            }
+        }
+        self.groups = Some(names.join(","));
+    }
+
+    /// the groups attribute split into its (comma or space separated) names
+    pub fn group_names(&self) -> Vec<&str> {
+        self.groups
+            .as_deref()
+            .unwrap_or_default()
+            .split(&[',', ' '][..])
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+}
+
+/// OO representation of a repo-tool's default xml element
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Defaults {
+    pub remote: Option<String>,
+    pub revision: Option<String>,
+    pub upstream: Option<String>,
+    #[serde(rename = "dest-branch")]
+    pub dest_branch: Option<String>,
+    #[serde(rename = "sync-j")]
+    pub sync_j: Option<u32>,
+}
+
+/// OO representation of a repo-tool's remote xml element
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Remote {
+    pub name: String,
+    pub alias: Option<String>,
+    pub fetch: String,
+    pub pushurl: Option<String>,
+    pub review: Option<String>,
+    pub revision: Option<String>,
+}
+
+/// OO representation of a repo-tool's copyfile and linkfile xml
+/// elements: src is relative to the project, dest relative to the
+/// root of the workspace
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FileOperation {
+    pub src: String,
+    pub dest: String,
+}
+
+/// OO representation of a repo-tool's annotation xml element
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Annotation {
+    pub name: String,
+    pub value: String,
+    pub keep: Option<String>,
+}
+
+/// OO representation of a repo-tool's superproject xml element
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Superproject {
+    pub name: String,
+    pub remote: Option<String>,
+    pub revision: Option<String>,
+}
+
+/// OO representation of a repo-tool's remove-project xml element
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemoveProject {
+    pub name: String,
+    pub path: Option<String>,
+}
+
+impl RemoveProject {
+    /// a removal matches by name, and by path if one is given
+    pub fn matches(&self, project: &Project) -> bool {
+        project.name == self.name && self.path.as_ref().is_none_or(|p| p == &project.path)
+    }
+}
+
+/// OO representation of a repo-tool's extend-project xml element
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExtendProject {
+    pub name: String,
+    pub path: Option<String>,
+    #[serde(rename = "dest-path")]
+    pub dest_path: Option<String>,
+    pub groups: Option<String>,
+    pub revision: Option<String>,
+    pub remote: Option<String>,
+    pub upstream: Option<String>,
+    #[serde(rename = "dest-branch")]
+    pub dest_branch: Option<String>,
+}
+
+impl ExtendProject {
+    /// an extension matches by name, and by path if one is given
+    pub fn matches(&self, project: &Project) -> bool {
+        project.name == self.name && self.path.as_ref().is_none_or(|p| p == &project.path)
+    }
+
+    /// adds the extension's groups to the project and overrides
+    /// all other attributes given by the extension, dest-path moves
+    /// the project
+    pub fn extend(&self, project: &mut Project) {
+        if let Some(groups) = &self.groups {
+            project.add_groups(groups);
+        }
+        if let Some(dest_path) = &self.dest_path {
+            project.path = dest_path.clone();
+        }
+        if self.revision.is_some() {
+            project.revision = self.revision.clone();
+        }
+        if self.remote.is_some() {
+            project.remote = self.remote.clone();
+        }
+        if self.upstream.is_some() {
+            project.upstream = self.upstream.clone();
+        }
+        if self.dest_branch.is_some() {
+            project.dest_branch = self.dest_branch.clone();
+        }
+    }
 }
 
 /// OO representation of a repo-tool's include xml element
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Include {
     pub name: String,
+    pub groups: Option<String>,
 }
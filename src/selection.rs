@@ -0,0 +1,98 @@
+use crate::config::{init_jobs, Config};
+use crate::error::Result;
+use crate::repo_project_selector::{FilterMode, ProjectInfo, ProjectSelector};
+use crate::workspace::Workspace;
+use regex::Regex;
+use std::env;
+use std::path::PathBuf;
+
+/// The options all binaries select the projects to work on with,
+/// meant to be flattened into their own ones:
+///
+/// ```no_run
+/// # use clap::Parser;
+/// # use repo_utils::selection::SelectionArgs;
+/// #[derive(Parser)]
+/// struct Args {
+///     #[command(flatten)]
+///     selection: SelectionArgs,
+/// }
+///
+/// let mut args = Args::parse();
+/// let (config, workspace, list_of_projects) = args.selection.select()?;
+/// # Ok::<(), repo_utils::Error>(())
+/// ```
+#[derive(clap::Args, Debug, Clone)]
+pub struct SelectionArgs {
+    /// change working directory (mostly useful for testing)
+    #[arg(short = 'C', long, value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
+    pub cwd: Option<PathBuf>,
+
+    /// ignore projects which are not defined in the given manifest file(s)
+    #[arg(short, long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub manifest: Option<Vec<PathBuf>>,
+
+    /// ignore projects which are not part of the given group(s), exclude groups with a leading '-', e.g. "electrical,-test"
+    #[arg(short, long, allow_hyphen_values = true)]
+    pub group: Option<Vec<String>>,
+
+    /// ignore projects whose path doesn't match any of the given glob(s), e.g. "vendor/**"
+    #[arg(long, value_name = "GLOB")]
+    pub path: Option<Vec<String>>,
+
+    /// ignore projects whose name (as defined in the manifest) doesn't match the given regex
+    #[arg(long, value_name = "REGEX")]
+    pub project_regex: Option<Regex>,
+
+    /// additionally discover projects by scanning for git repos, e.g. if .repo/project.list is missing or stale
+    #[arg(long, default_value = "false")]
+    pub scan: bool,
+
+    /// how the filters above are combined
+    #[arg(long, value_enum, default_value_t = FilterMode::Intersection)]
+    pub filter_mode: FilterMode,
+
+    /// number of projects processed in parallel, defaults to the number of CPUs
+    #[arg(short, long, value_name = "N")]
+    pub jobs: Option<usize>,
+}
+
+impl SelectionArgs {
+    /// the selector for the projects given by the options
+    pub fn selector(&self) -> ProjectSelector {
+        ProjectSelector::new()
+            .scan_filesystem(self.scan)
+            .groups(self.group.clone())
+            .manifest_files(self.manifest.clone())
+            .path_globs(self.path.clone())
+            .name_regex(self.project_regex.clone())
+            .filter_mode(self.filter_mode)
+    }
+
+    /// Changes into the --cwd given, loads the configuration and applies
+    /// its ui settings and its defaults for the options not given, then
+    /// selects the projects of the current workspace.
+    pub fn select(&mut self) -> Result<(Config, Workspace, Vec<ProjectInfo>)> {
+        self.select_with(|selector| selector)
+    }
+
+    /// Like [SelectionArgs::select], with the selector adjusted by `adjust`
+    /// first, e.g. to include the projects which are not checked out.
+    pub fn select_with(
+        &mut self,
+        adjust: impl FnOnce(ProjectSelector) -> ProjectSelector,
+    ) -> Result<(Config, Workspace, Vec<ProjectInfo>)> {
+        if let Some(cwd) = &self.cwd {
+            env::set_current_dir(cwd)?;
+        }
+
+        let config = Config::load()?;
+        config.ui.apply();
+        self.group = self.group.take().or_else(|| config.defaults.groups.clone());
+        init_jobs(self.jobs.or(config.defaults.jobs))?;
+
+        let workspace = Workspace::current()?.manifest_cache(config.defaults.manifest_cache);
+        let list_of_projects = workspace.select(&adjust(self.selector()))?;
+        Ok((config, workspace, list_of_projects))
+    }
+}
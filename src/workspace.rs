@@ -0,0 +1,114 @@
+use crate::error::{Error, Result, ResultExt};
+use crate::git_status::lookup_sync_branch_name_in;
+use crate::paths;
+use crate::repo_project_selector::{
+    find_repo_root_folder_from, lines_from_file, parse_repo_manifest_in, Manifest, ProjectInfo,
+    ProjectSelector,
+};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// A workspace managed by the repo tool, i.e. a folder containing .repo:
+///
+/// ```no_run
+/// # use repo_utils::repo_project_selector::ProjectSelector;
+/// # use repo_utils::workspace::Workspace;
+/// let workspace = Workspace::discover("/path/to/aosp".as_ref())?;
+/// for project in workspace.select(&ProjectSelector::new())? {
+///     println!("{} at {}", project.name, project.revision.unwrap_or_default());
+/// }
+/// # Ok::<(), repo_utils::Error>(())
+/// ```
+///
+/// The manifest is parsed on first access only.
+#[derive(Debug)]
+pub struct Workspace {
+    root_folder: PathBuf,
+    manifest: OnceLock<Manifest>,
+    manifest_cache: bool,
+}
+
+impl Workspace {
+    /// the workspace `path` is part of, i.e. the first of `path` and its
+    /// parent folders containing .repo. The folders along `path` as given
+    /// are looked at before the ones along `path` with its symlinks
+    /// resolved, since a project linked into the workspace from elsewhere
+    /// (e.g. from another disk) lies outside of it once resolved.
+    pub fn discover(path: &Path) -> Result<Workspace> {
+        let resolved =
+            paths::canonicalize(path).with_context(|| format!("Unable to resolve {:?}", path))?;
+        let root_folder = match find_repo_root_folder_from(&std::path::absolute(path)?) {
+            Ok(root_folder) => paths::canonicalize(&root_folder)?,
+            Err(_) => find_repo_root_folder_from(&resolved)?,
+        };
+        Ok(Workspace {
+            root_folder,
+            manifest: OnceLock::new(),
+            manifest_cache: false,
+        })
+    }
+
+    /// the workspace the current working directory is part of. The cwd
+    /// has its symlinks resolved, so $PWD is preferred if it names the
+    /// same folder, as it keeps the path the shell took.
+    pub fn current() -> Result<Workspace> {
+        let cwd = env::current_dir()?;
+        let pwd = env::var_os("PWD")
+            .map(PathBuf::from)
+            .filter(|pwd| paths::canonicalize(pwd).ok() == paths::canonicalize(&cwd).ok());
+        Workspace::discover(&pwd.unwrap_or(cwd))
+    }
+
+    /// keep the parsed manifest in .repo as well, so further invocations
+    /// skip parsing it, see [crate::manifest_cache]
+    pub fn manifest_cache(mut self, enabled: bool) -> Self {
+        self.manifest_cache = enabled;
+        self
+    }
+
+    /// the folder containing .repo
+    pub fn root_folder(&self) -> &Path {
+        &self.root_folder
+    }
+
+    /// the .repo folder
+    pub fn repo_folder(&self) -> PathBuf {
+        self.root_folder.join(".repo")
+    }
+
+    /// the .repo/manifests folder
+    pub fn manifests_folder(&self) -> PathBuf {
+        self.repo_folder().join("manifests")
+    }
+
+    /// the manifest of the workspace, including its includes and local manifests
+    pub fn manifest(&self) -> Result<&Manifest> {
+        if let Some(manifest) = self.manifest.get() {
+            return Ok(manifest);
+        }
+        let manifest = parse_repo_manifest_in(&self.repo_folder(), self.manifest_cache)?;
+        Ok(self.manifest.get_or_init(|| manifest))
+    }
+
+    /// paths of the synced projects, as listed in .repo/project.list
+    pub fn project_list(&self) -> Result<Vec<String>> {
+        let project_list = self.repo_folder().join("project.list");
+        match project_list.is_file() {
+            true => lines_from_file(project_list),
+            false => Err(Error::Manifest(
+                "no project.list in .repo found".to_string(),
+            )),
+        }
+    }
+
+    /// the branch tracking the state of the last "repo sync", e.g. m/main
+    pub fn sync_branch_name(&self) -> Result<String> {
+        lookup_sync_branch_name_in(&self.manifests_folder())
+    }
+
+    /// the projects of this workspace selected by `selector`
+    pub fn select(&self, selector: &ProjectSelector) -> Result<Vec<ProjectInfo>> {
+        selector.select_infos_in(self)
+    }
+}
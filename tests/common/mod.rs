@@ -0,0 +1,101 @@
+#![allow(dead_code)]
+
+use git2::{Repository, Signature};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// creates a fresh git repo in a temporary folder, with one commit
+/// adding a README file
+pub fn init_repo() -> (TempDir, Repository) {
+    let dir = TempDir::new().unwrap();
+    let repo = Repository::init(dir.path()).unwrap();
+    commit_file(&repo, "README", "initial");
+    (dir, repo)
+}
+
+pub fn commit_file(repo: &Repository, name: &str, content: &str) -> git2::Oid {
+    let signature = Signature::now("Tester", "tester@example.com").unwrap();
+    commit_file_as(repo, name, content, &signature, &signature)
+}
+
+/// like [commit_file], with the given author and committer, e.g. to
+/// commit at a given time
+pub fn commit_file_as(
+    repo: &Repository,
+    name: &str,
+    content: &str,
+    author: &Signature,
+    committer: &Signature,
+) -> git2::Oid {
+    let workdir = repo.workdir().unwrap();
+    fs::write(workdir.join(name), content).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(name)).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let parents: Vec<_> = repo
+        .head()
+        .ok()
+        .and_then(|h| h.peel_to_commit().ok())
+        .into_iter()
+        .collect();
+    let parents: Vec<_> = parents.iter().collect();
+    repo.commit(Some("HEAD"), author, committer, "commit", &tree, &parents)
+        .unwrap()
+}
+
+/// creates a workspace in a temporary folder: a .repo folder with a
+/// manifest defining the given projects (plus `extra_manifest_xml`),
+/// a project.list and a git repo with one commit for each project
+pub fn init_workspace(paths: &[&str], extra_manifest_xml: &str) -> (TempDir, Vec<Repository>) {
+    let dir = TempDir::new().unwrap();
+    let repo_folder = dir.path().join(".repo");
+    fs::create_dir_all(repo_folder.join("manifests")).unwrap();
+
+    let mut manifest = String::from("<manifest>\n");
+    manifest += "  <remote name=\"origin\" fetch=\"https://example.com\"/>\n";
+    manifest += "  <default remote=\"origin\" revision=\"main\"/>\n";
+    for path in paths {
+        manifest += &format!("  <project name=\"{0}\" path=\"{0}\"/>\n", path);
+    }
+    manifest += extra_manifest_xml;
+    manifest += "</manifest>\n";
+    fs::write(repo_folder.join("manifest.xml"), manifest).unwrap();
+    fs::write(repo_folder.join("project.list"), paths.join("\n")).unwrap();
+
+    let repos = paths
+        .iter()
+        .map(|path| {
+            let repo = Repository::init(dir.path().join(path)).unwrap();
+            commit_file(&repo, "README", path);
+            repo
+        })
+        .collect();
+    (dir, repos)
+}
+
+/// turns .repo/manifests of the workspace at `root` into a git repo whose
+/// branch default tracks origin/main, like "repo init" does, and points the
+/// sync branch m/main of all `repos` to their current HEAD
+pub fn init_sync_branch(root: &Path, repos: &[Repository]) {
+    let manifests = Repository::init(root.join(".repo/manifests")).unwrap();
+    let head = commit_file(&manifests, "default.xml", "<manifest/>");
+    manifests
+        .remote("origin", "https://example.com/manifest")
+        .unwrap();
+    manifests
+        .reference("refs/remotes/origin/main", head, false, "fetch")
+        .unwrap();
+    let mut default = manifests
+        .branch("default", &manifests.find_commit(head).unwrap(), true)
+        .unwrap();
+    default.set_upstream(Some("origin/main")).unwrap();
+    manifests.set_head("refs/heads/default").unwrap();
+
+    for repo in repos {
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.reference("refs/remotes/m/main", head.id(), false, "sync")
+            .unwrap();
+    }
+}
@@ -0,0 +1,208 @@
+mod common;
+
+use repo_utils::config::Config;
+use repo_utils::Error;
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_defaults() {
+    let config = Config::parse("").unwrap();
+
+    assert_eq!(config, Config::default());
+    assert_eq!(config.ui.color, None);
+    assert!(config.ui.progress);
+    assert_eq!(config.defaults.groups, None);
+    assert_eq!(config.defaults.jobs, None);
+    assert_eq!(config.forall.retries, 0);
+    assert_eq!(config.forall.timeout, None);
+}
+
+#[test]
+fn test_parse_sections() {
+    let config = Config::parse(
+        r#"
+        [ui]
+        color = false
+        progress = false
+
+        [forall]
+        retries = 2
+        timeout = 60
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.ui.color, Some(false));
+    assert!(!config.ui.progress);
+    assert_eq!(config.forall.retries, 2);
+    assert_eq!(config.forall.retry_backoff, 0);
+    assert_eq!(config.forall.timeout, Some(60));
+}
+
+#[test]
+fn test_unknown_keys_are_rejected() {
+    let error = Config::parse("[ui]\ncolour = true\n").unwrap_err();
+    assert!(error.to_string().contains("colour"), "{}", error);
+    assert!(matches!(error, Error::Config(_)));
+
+    assert!(Config::parse("[unknown]\n").is_err());
+}
+
+#[test]
+fn test_load_from_first_existing_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let user = dir.path().join("user.toml");
+    let system = dir.path().join("system.toml");
+    fs::write(&system, "[forall]\nretries = 1\n").unwrap();
+
+    let paths = [user.clone(), system.clone()];
+    assert_eq!(Config::load_from(&paths).unwrap().forall.retries, 1);
+
+    fs::write(&user, "[forall]\nretries = 3\n").unwrap();
+    assert_eq!(Config::load_from(&paths).unwrap().forall.retries, 3);
+
+    fs::write(&user, "[forall]\nretries = \"many\"\n").unwrap();
+    let error = format!("{:#}", Config::load_from(&paths).unwrap_err());
+    assert!(error.contains("user.toml"), "{}", error);
+
+    assert_eq!(Config::load_from(&[]).unwrap(), Config::default());
+}
+
+#[test]
+fn test_workspace_config_overrides_user_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let user = dir.path().join("config.toml");
+    let workspace = dir.path().join(".repo-utils.toml");
+    fs::write(
+        &user,
+        "[ui]\nprogress = false\n[forall]\nretries = 1\ntimeout = 10\n",
+    )
+    .unwrap();
+    fs::write(&workspace, "[forall]\nretries = 5\n").unwrap();
+
+    let user_paths = [user];
+    let config = Config::load_layered(&user_paths, Some(&workspace)).unwrap();
+    assert!(!config.ui.progress);
+    assert_eq!(config.forall.retries, 5);
+    assert_eq!(config.forall.timeout, Some(10));
+
+    // the workspace config applies on its own as well
+    let missing = dir.path().join("missing.toml");
+    let config = Config::load_layered(&[missing], Some(&workspace)).unwrap();
+    assert!(config.ui.progress);
+    assert_eq!(config.forall.retries, 5);
+
+    fs::write(&workspace, "[forall]\nretrys = 5\n").unwrap();
+    let error = format!(
+        "{:#}",
+        Config::load_layered(&user_paths, Some(&workspace)).unwrap_err()
+    );
+    assert!(error.contains(".repo-utils.toml"), "{}", error);
+}
+
+#[test]
+fn test_defaults_apply_unless_given_on_command_line() {
+    let (workspace, _repos) = common::init_workspace(&["app", "lib"], "");
+    let root = workspace.path();
+    fs::write(
+        root.join(".repo-utils.toml"),
+        "[defaults]\ngroups = [\"path:lib\"]\njobs = 1\n",
+    )
+    .unwrap();
+    let user_config_home = tempfile::tempdir().unwrap();
+
+    let selected = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_repo-verify-links"))
+            .arg("-C")
+            .arg(root)
+            .args(args)
+            .env("XDG_CONFIG_HOME", user_config_home.path())
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .next()
+            .unwrap()
+            .to_string()
+    };
+
+    assert_eq!(selected(&[]), "Selected 1 projects");
+    assert_eq!(selected(&["-g", "all"]), "Selected 2 projects");
+}
+
+#[test]
+fn test_environment_overrides_config_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let workspace = dir.path().join(".repo-utils.toml");
+    fs::write(&workspace, "[defaults]\njobs = 2\n[forall]\nretries = 5\n").unwrap();
+    let vars = |vars: &[(&str, &str)]| {
+        vars.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>()
+    };
+
+    let config = Config::load_layered_with_env(
+        &[],
+        Some(&workspace),
+        vars(&[
+            ("REPO_UTILS_JOBS", "4"),
+            ("REPO_UTILS_GROUPS", "myteam,-test"),
+            ("REPO_UTILS_COLOR", "false"),
+            ("REPO_UTILS_MANIFEST_CACHE", "true"),
+            ("REPO_UTILS_BACKUP_URL", "ssh://backup/{project}"),
+            ("HOME", "/home/someone"),
+        ]),
+    )
+    .unwrap();
+    assert_eq!(config.defaults.jobs, Some(4));
+    assert_eq!(
+        config.defaults.groups,
+        Some(vec!["myteam".to_string(), "-test".to_string()])
+    );
+    assert_eq!(config.ui.color, Some(false));
+    assert!(config.defaults.manifest_cache);
+    assert_eq!(config.forall.retries, 5);
+    assert_eq!(config.backup.url.as_deref(), Some("ssh://backup/{project}"));
+
+    let error =
+        Config::load_layered_with_env(&[], None, vars(&[("REPO_UTILS_JOBS", "many")])).unwrap_err();
+    assert!(error.to_string().contains("REPO_UTILS_JOBS"), "{}", error);
+}
+
+#[test]
+fn test_manifest_cache_is_enabled_by_config() {
+    let (workspace, _repos) = common::init_workspace(&["app"], "");
+    let root = workspace.path();
+    let cache_file = root.join(".repo/repo-utils.manifest-cache.json");
+    let user_config_home = tempfile::tempdir().unwrap();
+
+    let run = |env: &[(&str, &str)]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_repo-verify-links"))
+            .arg("-C")
+            .arg(root)
+            .env("XDG_CONFIG_HOME", user_config_home.path())
+            .env_remove("REPO_UTILS_MANIFEST_CACHE")
+            .envs(env.iter().copied())
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+    };
+
+    run(&[]);
+    assert!(!cache_file.exists());
+
+    fs::write(
+        root.join(".repo-utils.toml"),
+        "[defaults]\nmanifest_cache = true\n",
+    )
+    .unwrap();
+    run(&[("REPO_UTILS_MANIFEST_CACHE", "false")]);
+    assert!(!cache_file.exists());
+    run(&[]);
+    assert!(cache_file.exists());
+}
@@ -0,0 +1,28 @@
+use repo_utils::exec::{direct_command, output_with_timeout, shell_command};
+use std::time::Duration;
+
+#[test]
+fn test_shell_command() {
+    let output = shell_command("echo hello").output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "hello");
+}
+
+#[test]
+fn test_direct_command() {
+    let argv = vec!["git".to_string(), "--version".to_string()];
+    let output = direct_command(&argv).unwrap().output().unwrap();
+    assert!(output.status.success());
+
+    assert!(direct_command(&[]).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_output_with_timeout() {
+    let output = output_with_timeout(shell_command("cat"), Some(b"data"), None, false).unwrap();
+    assert_eq!(output.stdout, b"data");
+
+    let timeout = Some(Duration::from_millis(100));
+    assert!(output_with_timeout(shell_command("sleep 5"), None, timeout, false).is_err());
+}
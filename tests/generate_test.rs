@@ -0,0 +1,54 @@
+mod common;
+
+use std::process::Command;
+
+#[test]
+fn test_completions_suggest_groups_of_manifest() {
+    let (dir, _repos) = common::init_workspace(
+        &["app"],
+        "  <project name=\"lib\" path=\"lib\" groups=\"electrical,test\"/>\n",
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-status"))
+        .arg("-C")
+        .arg(dir.path())
+        .args(["--completions", "fish"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("complete -c repo-status"), "{}", stdout);
+    // fish lists the possible values one per line, as value\t'description'
+    assert!(stdout.contains(r"electrical\t"), "{}", stdout);
+    assert!(stdout.contains(r"default\t"), "{}", stdout);
+}
+
+#[test]
+fn test_completions_without_required_arguments() {
+    let dir = tempfile::tempdir().unwrap();
+
+    // outside of a workspace, and without the subcommand repo-stash requires
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-stash"))
+        .arg("-C")
+        .arg(dir.path())
+        .args(["--completions", "bash"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("complete -F _repo__stash"), "{}", stdout);
+    assert!(stdout.contains("save"), "{}", stdout);
+}
+
+#[test]
+fn test_generate_man_page() {
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-forall"))
+        .arg("--generate-man")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(".TH repo-forall 1"), "{}", stdout);
+    assert!(stdout.contains(r"\-\-fail\-fast"), "{}", stdout);
+    assert!(!stdout.contains("completions"), "{}", stdout);
+}
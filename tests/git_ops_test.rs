@@ -0,0 +1,199 @@
+mod common;
+
+use git2::Repository;
+use repo_utils::git_ops;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_status() {
+    let (dir, repo) = setup();
+
+    assert_eq!(git_ops::status(&repo).unwrap(), "");
+
+    fs::write(dir.path().join("README"), "changed").unwrap();
+    fs::write(dir.path().join("NEW"), "new").unwrap();
+    assert_eq!(git_ops::status(&repo).unwrap(), "?? NEW\n M README\n");
+}
+
+#[test]
+fn test_is_sha() {
+    assert!(git_ops::is_sha("0123456789abcdef0123456789ABCDEF01234567"));
+    assert!(!git_ops::is_sha("0123456"));
+    assert!(!git_ops::is_sha("main"));
+    assert!(!git_ops::is_sha(
+        "I0123456789abcdef0123456789abcdef01234567"
+    ));
+}
+
+#[test]
+fn test_delete_branches() {
+    let (_dir, repo) = setup();
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.branch("topic", &head, false).unwrap();
+
+    let output = git_ops::delete_branches(&repo, &["topic".to_string()]).unwrap();
+    assert!(output.starts_with("Deleted branch topic"));
+    assert!(repo.find_branch("topic", git2::BranchType::Local).is_err());
+    assert!(git_ops::delete_branches(&repo, &["topic".to_string()]).is_err());
+}
+
+#[test]
+fn test_checkout() {
+    let (_dir, repo) = setup();
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.branch("topic", &head, false).unwrap();
+
+    git_ops::checkout(&repo, "topic").unwrap();
+    assert_eq!(repo.head().unwrap().shorthand(), Some("topic"));
+
+    git_ops::checkout(&repo, &head.id().to_string()).unwrap();
+    assert!(repo.head_detached().unwrap());
+}
+
+#[test]
+fn test_branches() {
+    let (_dir, repo) = setup();
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.reference("refs/remotes/m/main", head.id(), false, "sync")
+        .unwrap();
+    git_ops::start_branch(&repo, "topic", "m/main").unwrap();
+    common::commit_file(&repo, "README", "changed");
+
+    let branches = git_ops::branches(&repo, "m/main").unwrap();
+    let topic = branches.iter().find(|b| b.name == "topic").unwrap();
+    assert!(topic.is_head);
+    assert_eq!(topic.upstream, None);
+    assert_eq!(
+        topic.sync,
+        Some(git_ops::Divergence {
+            name: "m/main".to_string(),
+            ahead: 1,
+            behind: 0
+        })
+    );
+
+    let branches = git_ops::branches(&repo, "m/unknown").unwrap();
+    assert!(branches.iter().all(|b| b.sync.is_none()));
+}
+
+#[test]
+fn test_set_upstream() {
+    let (_dir, repo) = setup();
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.branch("topic", &head, false).unwrap();
+    repo.remote("origin", "https://example.com/app").unwrap();
+
+    git_ops::set_upstream(&repo, "topic", "origin", "main").unwrap();
+
+    let config = repo.config().unwrap().snapshot().unwrap();
+    assert_eq!(config.get_str("branch.topic.remote").unwrap(), "origin");
+    assert_eq!(
+        config.get_str("branch.topic.merge").unwrap(),
+        "refs/heads/main"
+    );
+    assert_eq!(
+        repo.branch_upstream_name("refs/heads/topic")
+            .unwrap()
+            .as_str(),
+        Some("refs/remotes/origin/main")
+    );
+}
+
+#[test]
+fn test_start_and_abandon_branch() {
+    let (_dir, repo) = setup();
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.reference("refs/remotes/m/main", head.id(), false, "sync")
+        .unwrap();
+
+    git_ops::start_branch(&repo, "topic", "m/main").unwrap();
+    assert_eq!(repo.head().unwrap().shorthand(), Some("topic"));
+    assert!(git_ops::start_branch(&repo, "topic", "m/main").is_err());
+
+    let output = git_ops::abandon_branch(&repo, "topic", "m/main").unwrap();
+    assert!(output.contains("Deleted branch topic"), "{}", output);
+    assert!(repo.head_detached().unwrap());
+    assert!(repo.find_branch("topic", git2::BranchType::Local).is_err());
+}
+
+#[test]
+fn test_cherry() {
+    let (_dir, repo) = setup();
+    let base = repo.head().unwrap().peel_to_commit().unwrap();
+    let local = common::commit_file(&repo, "local", "local");
+    let picked = common::commit_file(&repo, "picked", "picked");
+
+    // the same change, merged upstream under a different sha
+    let mut index = repo
+        .cherrypick_commit(&repo.find_commit(picked).unwrap(), &base, 0, None)
+        .unwrap();
+    let tree = repo.find_tree(index.write_tree_to(&repo).unwrap()).unwrap();
+    let signature = git2::Signature::now("Upstream", "upstream@example.com").unwrap();
+    let upstream = repo
+        .commit(None, &signature, &signature, "picked", &tree, &[&base])
+        .unwrap();
+    repo.reference("refs/remotes/m/main", upstream, false, "sync")
+        .unwrap();
+
+    let commits = git_ops::cherry(&repo, "refs/remotes/m/main").unwrap();
+    assert_eq!(
+        commits,
+        vec![
+            git_ops::CherryCommit {
+                oid: local,
+                summary: "commit".to_string(),
+                upstreamed: false
+            },
+            git_ops::CherryCommit {
+                oid: picked,
+                summary: "commit".to_string(),
+                upstreamed: true
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_prunable_branches() {
+    let (_dir, repo) = setup();
+    let base = repo.head().unwrap().peel_to_commit().unwrap();
+    let head_branch = repo.head().unwrap().shorthand().unwrap().to_string();
+    repo.reference("refs/remotes/m/main", base.id(), false, "sync")
+        .unwrap();
+    repo.branch("merged", &base, false).unwrap();
+
+    let local = repo
+        .find_commit(common::commit_file(&repo, "local", "local"))
+        .unwrap();
+    repo.branch("topic", &local, false).unwrap();
+    let mut gone = repo.branch("gone", &local, false).unwrap();
+    repo.remote("origin", "https://example.com/app").unwrap();
+    repo.reference("refs/remotes/origin/gone", local.id(), false, "fetch")
+        .unwrap();
+    gone.set_upstream(Some("origin/gone")).unwrap();
+    repo.find_reference("refs/remotes/origin/gone")
+        .unwrap()
+        .delete()
+        .unwrap();
+
+    let prunable = git_ops::prunable_branches(&repo, "m/main").unwrap();
+    assert_eq!(
+        prunable,
+        vec![
+            git_ops::PrunableBranch {
+                name: "gone".to_string(),
+                reason: git_ops::PruneReason::UpstreamGone
+            },
+            git_ops::PrunableBranch {
+                name: "merged".to_string(),
+                reason: git_ops::PruneReason::Merged
+            },
+        ]
+    );
+    assert!(prunable.iter().all(|b| b.name != head_branch));
+}
+
+fn setup() -> (TempDir, Repository) {
+    common::init_repo()
+}
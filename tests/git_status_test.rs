@@ -0,0 +1,122 @@
+mod common;
+
+use repo_utils::git_status::{
+    query_git_status, query_git_status_with_branch, query_git_statuses, GitStatus,
+};
+use repo_utils::progress::{NoProgress, Progress};
+use repo_utils::repo_project_selector::ProjectSelector;
+use repo_utils::workspace::Workspace;
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[test]
+fn test_query_git_status() {
+    let (dir, repo) = common::init_repo();
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.reference("refs/remotes/m/main", head.id(), false, "sync")
+        .unwrap();
+    let root = dir.path().parent().unwrap();
+    let path = dir.path().file_name().unwrap().to_str().unwrap();
+
+    let status = query_git_status(root, path, "m/main").unwrap();
+    assert!(!status.is_dirty());
+
+    fs::write(dir.path().join("README"), "changed").unwrap();
+    let status = query_git_status(root, path, "m/main").unwrap();
+    assert!(status.uncomitted_changes);
+    assert_eq!(status.local_commits, 0);
+
+    common::commit_file(&repo, "README", "changed");
+    let status = query_git_status(root, path, "m/main").unwrap();
+    assert!(!status.uncomitted_changes);
+    assert_eq!(status.local_commits, 1);
+    assert!(status.is_dirty());
+    assert_eq!((status.branch, status.ahead), (None, 0));
+
+    let status = query_git_status_with_branch(root, path, "m/main").unwrap();
+    assert_eq!(status.local_commits, 1);
+    assert!(status.branch.is_some());
+    assert_eq!((status.ahead, status.behind), (1, 0));
+
+    assert!(query_git_status(root, path, "m/unknown").is_err());
+}
+
+#[derive(Default)]
+struct CountingProgress {
+    len: AtomicU64,
+    done: AtomicU64,
+}
+
+impl Progress for CountingProgress {
+    fn start(&self, len: u64) {
+        self.len.store(len, Ordering::SeqCst);
+    }
+
+    fn inc(&self) {
+        self.done.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_query_git_statuses() {
+    let (dir, repos) = common::init_workspace(&["c", "a", "b"], "");
+    for repo in &repos {
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.reference("refs/remotes/m/main", head.id(), false, "sync")
+            .unwrap();
+    }
+    fs::write(dir.path().join("a/README"), "changed").unwrap();
+    let workspace = Workspace::discover(dir.path()).unwrap();
+    let projects = workspace.select(&ProjectSelector::new()).unwrap();
+
+    let progress = CountingProgress::default();
+    let statuses =
+        query_git_statuses(workspace.root_folder(), &projects, "m/main", &progress).unwrap();
+
+    // in the order of the projects
+    let paths: Vec<_> = statuses.iter().map(|s| s.path.as_str()).collect();
+    assert_eq!(paths, vec!["c", "a", "b"]);
+    assert!(statuses[1].uncomitted_changes);
+    assert_eq!(progress.len.load(Ordering::SeqCst), 3);
+    assert_eq!(progress.done.load(Ordering::SeqCst), 3);
+
+    assert!(
+        query_git_statuses(workspace.root_folder(), &projects, "m/unknown", &NoProgress).is_err()
+    );
+}
+
+#[test]
+fn test_git_status_serialization() {
+    let status = GitStatus::new("libs/core", true, 2);
+
+    assert_eq!(
+        serde_json::to_value(&status).unwrap(),
+        serde_json::json!({
+            "path": "libs/core",
+            "uncommitted_changes": true,
+            "local_commits": 2,
+            "branch": null,
+            "ahead": 0,
+            "behind": 0
+        })
+    );
+}
+
+#[test]
+fn test_nested_project_is_no_change_of_the_enclosing_one() {
+    let (dir, repos) = common::init_workspace(&["app", "app/plugin"], "");
+    common::init_sync_branch(dir.path(), &repos);
+    fs::write(dir.path().join("app/plugin/README"), "changed").unwrap();
+
+    let app = query_git_status(dir.path(), "app", "m/main").unwrap();
+    assert!(!app.is_dirty(), "{:?}", app);
+    let plugin = query_git_status(dir.path(), "app/plugin", "m/main").unwrap();
+    assert!(plugin.uncomitted_changes);
+
+    fs::write(dir.path().join("app/notes.txt"), "untracked").unwrap();
+    assert!(
+        query_git_status(dir.path(), "app", "m/main")
+            .unwrap()
+            .uncomitted_changes
+    );
+}
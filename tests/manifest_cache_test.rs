@@ -0,0 +1,70 @@
+mod common;
+
+use repo_utils::manifest_cache::{clear, load_or_parse};
+use repo_utils::repo_project_selector::parse_manifest;
+use repo_utils::Error;
+use std::cell::Cell;
+use std::fs::File;
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn test_load_or_parse() {
+    let (workspace, _repos) = common::init_workspace(&["kettle", "toaster"], "");
+    let repo_folder = workspace.path().join(".repo");
+    let manifest_file = repo_folder.join("manifest.xml");
+    let parsed = Cell::new(0);
+    let parse = || {
+        parsed.set(parsed.get() + 1);
+        parse_manifest(&manifest_file)
+    };
+
+    // in-process
+    assert_eq!(
+        load_or_parse(&repo_folder, false, parse)
+            .unwrap()
+            .projects
+            .len(),
+        2
+    );
+    assert_eq!(
+        load_or_parse(&repo_folder, false, parse)
+            .unwrap()
+            .projects
+            .len(),
+        2
+    );
+    assert_eq!(parsed.get(), 1);
+
+    let touch = |offset: u64| {
+        let mtime = SystemTime::now() + Duration::from_secs(offset);
+        File::options()
+            .write(true)
+            .open(&manifest_file)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+    };
+    touch(10);
+    load_or_parse(&repo_folder, false, parse).unwrap();
+    assert_eq!(parsed.get(), 2);
+
+    // on disk
+    clear();
+    load_or_parse(&repo_folder, true, parse).unwrap();
+    assert_eq!(parsed.get(), 3);
+    assert!(repo_folder.join("repo-utils.manifest-cache.json").is_file());
+
+    clear();
+    let cached = load_or_parse(&repo_folder, true, || {
+        Err(Error::Manifest("must not be parsed".to_string()))
+    })
+    .unwrap();
+    assert_eq!(cached.projects.len(), 2);
+
+    touch(20);
+    clear();
+    assert!(load_or_parse(&repo_folder, true, || Err(Error::Manifest(
+        "has to be parsed".to_string()
+    )))
+    .is_err());
+}
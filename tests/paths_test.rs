@@ -0,0 +1,34 @@
+use repo_utils::paths::{canonicalize, join};
+use std::path::Path;
+use tempfile::TempDir;
+
+#[test]
+fn test_join() {
+    let root = Path::new("/workspace");
+    assert_eq!(join(root, "vendor/./lib/"), root.join("vendor").join("lib"));
+    assert_eq!(join(root, ""), root);
+}
+
+#[test]
+fn test_canonicalize() {
+    let dir = TempDir::new().unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+
+    let path = canonicalize(&dir.path().join("sub/..")).unwrap();
+    assert_eq!(path, canonicalize(dir.path()).unwrap());
+    assert!(path.is_absolute());
+    assert!(!path.to_string_lossy().starts_with(r"\\?\"));
+}
+
+#[cfg(windows)]
+#[test]
+fn test_long_paths_are_extended() {
+    let root = Path::new(r"C:\workspace");
+    let deep = vec!["meta-layer"; 30].join("/");
+    assert!(join(root, &deep)
+        .to_string_lossy()
+        .starts_with(r"\\?\C:\workspace\meta-layer\"));
+    assert!(join(Path::new(r"\\server\share"), &deep)
+        .to_string_lossy()
+        .starts_with(r"\\?\UNC\server\share\meta-layer\"));
+}
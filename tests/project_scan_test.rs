@@ -0,0 +1,52 @@
+mod common;
+
+use git2::Repository;
+use repo_utils::repo_project_selector::scan_for_projects;
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_scan_for_projects() {
+    let (workspace, _repos) = common::init_workspace(&["kettle", "kitchen/toaster"], "");
+    let root = workspace.path();
+    Repository::init(root.join("kettle/lid")).unwrap();
+    Repository::init_bare(root.join(".repo/projects/kettle.git")).unwrap();
+    fs::create_dir_all(root.join("cupboard/empty")).unwrap();
+
+    assert_eq!(
+        scan_for_projects(root).unwrap(),
+        vec!["kettle", "kettle/lid", "kitchen/toaster"]
+    );
+}
+
+#[test]
+fn test_select_without_project_list() {
+    let (workspace, _repos) = common::init_workspace(&["kettle", "kitchen/toaster"], "");
+    fs::remove_file(workspace.path().join(".repo/project.list")).unwrap();
+
+    let verify_links = |scan: bool| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_repo-verify-links"));
+        command.arg("-C").arg(workspace.path());
+        if scan {
+            command.arg("--scan");
+        }
+        command.output().unwrap()
+    };
+
+    assert!(!verify_links(false).status.success());
+    let output = verify_links(true);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Selected 2 projects"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_scan_finds_linked_projects() {
+    let (workspace, _repos) = common::init_workspace(&["kettle"], "");
+    let root = workspace.path();
+    let other_disk = tempfile::tempdir().unwrap();
+    Repository::init(other_disk.path().join("oven")).unwrap();
+    std::os::unix::fs::symlink(other_disk.path().join("oven"), root.join("oven")).unwrap();
+
+    assert_eq!(scan_for_projects(root).unwrap(), vec!["kettle", "oven"]);
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use git2::Repository;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn archive(root: &Path, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-archive"))
+        .arg("-C")
+        .arg(root)
+        .args(args)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_bundles_and_snapshot() {
+    let (dir, repos) = common::init_workspace(&["app", "libs/core"], "");
+    let head = repos[1].head().unwrap().peel_to_commit().unwrap().id();
+
+    let stdout = archive(dir.path(), &["out"]);
+    assert!(stdout.contains("Archived 2/2 projects"), "{}", stdout);
+
+    let snapshot = fs::read_to_string(dir.path().join("out/snapshot.xml")).unwrap();
+    assert!(
+        snapshot.contains(&format!("path=\"libs/core\" revision=\"{}\"", head)),
+        "{}",
+        snapshot
+    );
+
+    // libgit2 can't clone from bundles
+    let output = Command::new("git")
+        .args(["clone", "-q", "out/libs/core.bundle", "clone"])
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let cloned = Repository::open(dir.path().join("clone")).unwrap();
+    assert_eq!(cloned.head().unwrap().peel_to_commit().unwrap().id(), head);
+}
+
+#[test]
+fn test_tarballs_are_reproducible() {
+    let (dir, _repos) = common::init_workspace(&["app", "lib"], "");
+
+    archive(dir.path(), &["--format", "tar", "first"]);
+    archive(dir.path(), &["--format", "tar", "second"]);
+
+    let first = fs::read(dir.path().join("first/lib.tar")).unwrap();
+    let second = fs::read(dir.path().join("second/lib.tar")).unwrap();
+    assert!(!first.is_empty());
+    assert_eq!(first, second);
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use git2::{Signature, Time};
+use std::process::Command;
+
+#[test]
+fn test_statistics_per_author() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    common::commit_file(&repos[1], "lib.rs", "fn a() {}\nfn b() {}\nfn c() {}\n");
+
+    let authors = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_repo-authors"))
+            .arg("-C")
+            .arg(dir.path())
+            .args(args)
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let stdout = authors(&["--format", "csv"]);
+    assert_eq!(
+        stdout,
+        "author,commits,insertions,deletions,active_projects\n\
+         \"Tester <tester@example.com>\",3,5,0,2\n"
+    );
+
+    let stdout = authors(&["--format", "json"]);
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(json[0]["author"], "Tester <tester@example.com>");
+    assert_eq!(json[0]["commits"], 3);
+
+    let stdout = authors(&["--since", "2999-01-01"]);
+    assert!(stdout.contains("0 commits by 0 authors"), "{}", stdout);
+
+    let stdout = authors(&["--until", "2999-01-01"]);
+    assert!(stdout.contains("3 commits by 1 authors"), "{}", stdout);
+}
+
+#[test]
+fn test_since_with_rebased_commit() {
+    let (dir, repos) = common::init_workspace(&["app"], "");
+    common::commit_file(&repos[0], "new.rs", "fn new() {}");
+    // authored long ago, committed (rebased) after the commit above
+    let author =
+        Signature::new("Rebaser", "rebaser@example.com", &Time::new(946684800, 0)).unwrap();
+    let committer = Signature::now("Rebaser", "rebaser@example.com").unwrap();
+    common::commit_file_as(&repos[0], "old.rs", "fn old() {}", &author, &committer);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-authors"))
+        .arg("-C")
+        .arg(dir.path())
+        .args(["--since", "2020-01-01", "--format", "csv"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "author,commits,insertions,deletions,active_projects\n\
+         \"Tester <tester@example.com>\",2,2,0,1\n"
+    );
+}
@@ -0,0 +1,167 @@
+mod common;
+
+use git2::{Repository, Signature, Time};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn snapshot(path: &Path, repos: &[(&str, &Repository)]) {
+    let mut manifest = String::from("<manifest>\n");
+    manifest += "  <remote name=\"origin\" fetch=\"https://example.com\"/>\n";
+    manifest += "  <default remote=\"origin\" revision=\"main\"/>\n";
+    for (name, repo) in repos {
+        let head = repo.head().unwrap().target().unwrap();
+        manifest += &format!(
+            "  <project name=\"{0}\" path=\"{0}\" revision=\"{1}\"/>\n",
+            name, head
+        );
+    }
+    manifest += "</manifest>\n";
+    fs::write(path, manifest).unwrap();
+}
+
+#[test]
+fn test_bisect_finds_project_and_commit() {
+    let (dir, repos) = common::init_workspace(&["app", "lib", "tools"], "");
+    let projects = [("app", &repos[0]), ("lib", &repos[1]), ("tools", &repos[2])];
+    let good = dir.path().join("good.xml");
+    let bad = dir.path().join("bad.xml");
+    snapshot(&good, &projects);
+
+    common::commit_file(&repos[0], "app.c", "fine");
+    common::commit_file(&repos[1], "one.c", "fine");
+    let culprit = common::commit_file(&repos[1], "BUG", "broken");
+    common::commit_file(&repos[1], "two.c", "fine");
+    common::commit_file(&repos[2], "tool.c", "fine");
+    snapshot(&bad, &projects);
+    let heads: Vec<_> = repos.iter().map(|r| r.head().unwrap().target()).collect();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-bisect"))
+        .arg("-C")
+        .arg(dir.path())
+        .arg("--good")
+        .arg(&good)
+        .arg("--bad")
+        .arg(&bad)
+        .args(["test", "!", "-f", "lib/BUG"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("First bad project: lib\n"), "{}", stdout);
+    assert!(
+        stdout.contains(&format!("First bad commit: lib {:.7} commit\n", culprit)),
+        "{}",
+        stdout
+    );
+
+    // the workspace is left as it has been before
+    let restored: Vec<_> = repos.iter().map(|r| r.head().unwrap().target()).collect();
+    assert_eq!(restored, heads);
+    assert!(!repos[0].head_detached().unwrap());
+}
+
+#[test]
+fn test_bisect_requires_failing_bad_manifest() {
+    let (dir, repos) = common::init_workspace(&["app"], "");
+    let good = dir.path().join("good.xml");
+    let bad = dir.path().join("bad.xml");
+    snapshot(&good, &[("app", &repos[0])]);
+    common::commit_file(&repos[0], "app.c", "fine");
+    snapshot(&bad, &[("app", &repos[0])]);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-bisect"))
+        .arg("-C")
+        .arg(dir.path())
+        .arg("--good")
+        .arg(&good)
+        .arg("--bad")
+        .arg(&bad)
+        // succeeds with sh and cmd alike
+        .arg("exit 0")
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("passes with the bad snapshot"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_bisect_rollback() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    let projects = [("app", &repos[0]), ("lib", &repos[1])];
+    let good = dir.path().join("good.xml");
+    let bad = dir.path().join("bad.xml");
+
+    // the bad manifest pins lib to a commit older than the good one
+    let broken = common::commit_file(&repos[1], "STATE", "broken");
+    let fixed = common::commit_file(&repos[1], "STATE", "fixed");
+    common::commit_file(&repos[0], "app.c", "fine");
+    snapshot(&good, &projects);
+    repos[1].set_head_detached(broken).unwrap();
+    snapshot(&bad, &projects);
+    repos[1].set_head_detached(fixed).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-bisect"))
+        .arg("-C")
+        .arg(dir.path())
+        .arg("--good")
+        .arg(&good)
+        .arg("--bad")
+        .arg(&bad)
+        .args(["grep", "-q", "fixed", "lib/STATE"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("First bad project: lib\n"), "{}", stdout);
+    assert!(
+        stdout.contains(&format!(
+            "First bad change: lib moved back from {:.7} to {:.7}, dropping 1 commits\n",
+            fixed, broken
+        )),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_bisect_between_dates() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    // 2100-01-01
+    let day = |n: i64| {
+        let time = Time::new(4102444800 + n * 86400 + 43200, 0);
+        Signature::new("Tester", "tester@example.com", &time).unwrap()
+    };
+    let commit = |repo: &Repository, name: &str, content: &str, n: i64| {
+        common::commit_file_as(repo, name, content, &day(n), &day(n))
+    };
+    commit(&repos[1], "one.c", "fine", 0);
+    commit(&repos[0], "app.c", "fine", 2);
+    let culprit = commit(&repos[1], "BUG", "broken", 2);
+    commit(&repos[1], "two.c", "fine", 3);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-bisect"))
+        .arg("-C")
+        .arg(dir.path())
+        .args(["--good-date", "2100-01-02", "--bad-date", "2100-01-05"])
+        .args(["test", "!", "-f", "lib/BUG"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Bisecting 2 projects"), "{}", stdout);
+    assert!(
+        stdout.contains(&format!("First bad commit: lib {:.7} commit\n", culprit)),
+        "{}",
+        stdout
+    );
+}
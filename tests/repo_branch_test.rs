@@ -0,0 +1,72 @@
+mod common;
+
+use std::path::Path;
+use std::process::Command;
+
+fn repo_branch(root: &Path, args: &[&str]) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-branch"))
+        .arg("-C")
+        .arg(root)
+        .args(args)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn test_create_list_checkout_and_delete() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    common::init_sync_branch(dir.path(), &repos);
+    let root = dir.path();
+
+    let (success, stdout) = repo_branch(root, &["--create", "topic"]);
+    assert!(success, "{}", stdout);
+    assert!(
+        stdout.contains("Created branch topic in 2/2 projects"),
+        "{}",
+        stdout
+    );
+
+    common::commit_file(&repos[0], "README", "changed");
+    let (success, stdout) = repo_branch(root, &[]);
+    assert!(success, "{}", stdout);
+    // the padding depends on the name of the initial branch
+    let topic_lines: Vec<_> = stdout
+        .lines()
+        .filter(|l| l.starts_with("  * topic"))
+        .map(|l| l.split_whitespace().skip(2).collect::<Vec<_>>().join(" "))
+        .collect();
+    assert_eq!(
+        topic_lines,
+        vec!["[m/main: ahead 1]", "[m/main: up to date]"],
+        "{}",
+        stdout
+    );
+
+    let (success, stdout) = repo_branch(root, &["--delete", "topic", "--path", "lib"]);
+    assert!(success, "{}", stdout);
+    assert!(
+        stdout.contains("Deleted branch topic in 1/1 projects"),
+        "{}",
+        stdout
+    );
+
+    repos[0]
+        .set_head_detached(repos[0].head().unwrap().target().unwrap())
+        .unwrap();
+    let (success, stdout) = repo_branch(root, &["--checkout", "topic"]);
+    assert!(success, "{}", stdout);
+    assert!(
+        stdout.contains("Checked out branch topic in 1/2 projects"),
+        "{}",
+        stdout
+    );
+    assert_eq!(repos[0].head().unwrap().shorthand(), Some("topic"));
+
+    let (success, _) = repo_branch(root, &["--create", "a", "--delete", "b"]);
+    assert!(!success);
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use std::fs;
+use std::process::{Command, Output};
+
+#[test]
+fn test_checkout_fallbacks() {
+    let (dir, repos) = common::init_workspace(&["app", "lib", "tools"], "");
+    common::init_sync_branch(dir.path(), &repos);
+    let head = repos[1].head().unwrap().peel_to_commit().unwrap();
+    repos[1].branch("topic", &head, false).unwrap();
+    repos[1]
+        .tag_lightweight("v1.0", head.as_object(), false)
+        .unwrap();
+
+    let checkout = |args: &[&str]| -> Output {
+        Command::new(env!("CARGO_BIN_EXE_repo-checkout"))
+            .arg("-C")
+            .arg(dir.path())
+            .args(args)
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap()
+    };
+    let stdout = |output: &Output| String::from_utf8_lossy(&output.stdout).to_string();
+
+    let output = checkout(&["topic"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(stdout(&output).contains("lib: Switched to branch 'topic'"));
+    assert!(stdout(&output).contains("Not found in 2 projects"));
+
+    let output = checkout(&["--detach", "v1.0"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(repos[1].head_detached().unwrap());
+
+    // local changes are protected unless forced
+    fs::write(dir.path().join("tools/README"), "changed").unwrap();
+    let output = checkout(&["--create-missing", "topic"]);
+    assert!(!output.status.success(), "{:?}", output);
+    assert!(stdout(&output).contains("tools: has local changes, skipped"));
+    assert!(stdout(&output).contains("app: created, Switched to branch 'topic'"));
+    assert!(stdout(&output).contains("Created at m/main in 1 projects"));
+    assert_eq!(repos[0].head().unwrap().shorthand(), Some("topic"));
+
+    let output = checkout(&["--create-missing", "--force", "topic"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(repos[2].head().unwrap().shorthand(), Some("topic"));
+    assert_eq!(
+        fs::read_to_string(dir.path().join("tools/README")).unwrap(),
+        "changed"
+    );
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use std::process::Command;
+
+#[test]
+fn test_list_local_commits() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    common::init_sync_branch(dir.path(), &repos);
+    let oid = common::commit_file(&repos[1], "lib.rs", "fn main() {}");
+
+    let cherry = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_repo-cherry"))
+            .arg("-C")
+            .arg(dir.path())
+            .args(args)
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let stdout = cherry(&[]);
+    assert!(
+        stdout.contains(&format!("lib\n  + {:.7} commit\n", oid)),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("app"), "{}", stdout);
+    assert!(
+        stdout.contains("1 local commits in 1/2 projects, 0 already upstream"),
+        "{}",
+        stdout
+    );
+
+    let stdout = cherry(&["--upstream", "HEAD", "-v"]);
+    assert!(stdout.contains("app: up to date"), "{}", stdout);
+    assert!(
+        stdout.contains("0 local commits in 0/2 projects"),
+        "{}",
+        stdout
+    );
+}
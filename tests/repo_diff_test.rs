@@ -0,0 +1,74 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn repo_diff(root: &Path, other: &Path) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-diff"))
+        .arg("-C")
+        .arg(root)
+        .arg("-v")
+        .arg(other)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_diff_against_manifest() {
+    let (dir, repos) = common::init_workspace(&["app", "lib", "old"], "");
+    let app_head = repos[0].head().unwrap().target().unwrap();
+    let lib_base = repos[1].head().unwrap().target().unwrap();
+    repos[1]
+        .reference("refs/remotes/origin/stable", lib_base, false, "fetch")
+        .unwrap();
+    common::commit_file(&repos[1], "README", "local change");
+
+    let other = dir.path().join("other.xml");
+    fs::write(
+        &other,
+        format!(
+            "<manifest>\n\
+             <remote name=\"origin\" fetch=\"https://example.com\"/>\n\
+             <default remote=\"origin\" revision=\"main\"/>\n\
+             <project name=\"app\" path=\"app\" revision=\"{}\"/>\n\
+             <project name=\"lib\" path=\"lib\" revision=\"refs/heads/stable\"/>\n\
+             <project name=\"new\" path=\"new\"/>\n\
+             </manifest>\n",
+            app_head
+        ),
+    )
+    .unwrap();
+
+    let stdout = repo_diff(dir.path(), &other);
+    assert!(stdout.contains("app: same revision\n"), "{}", stdout);
+    assert!(
+        stdout.contains("-> refs/heads/stable (1 commits to drop, 0 commits to get)"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("old: removed\n"), "{}", stdout);
+    assert!(stdout.contains("new: added\n"), "{}", stdout);
+    assert!(
+        stdout.contains("1 same, 1 differing, 1 added, 1 removed, 0 failed"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_diff_against_workspace() {
+    let (dir, _repos) = common::init_workspace(&["app"], "");
+    let (other, other_repos) = common::init_workspace(&["app"], "");
+    common::commit_file(&other_repos[0], "README", "only in the other workspace");
+
+    let stdout = repo_diff(dir.path(), other.path());
+    assert!(
+        stdout.contains("(revision not available locally, fetch first)"),
+        "{}",
+        stdout
+    );
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use std::process::Command;
+
+#[test]
+fn test_fetch_reports_updated_refs() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    let (upstream_dir, upstream) = common::init_repo();
+    repos[0]
+        .remote("origin", upstream_dir.path().to_str().unwrap())
+        .unwrap();
+
+    let fetch = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_repo-fetch"))
+            .arg("-C")
+            .arg(dir.path())
+            .args(args)
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let stdout = fetch(&["-v"]);
+    assert!(stdout.contains("app: origin: 1 refs updated"), "{}", stdout);
+    assert!(stdout.contains("Fetched 2 projects"), "{}", stdout);
+    let head = upstream.head().unwrap();
+    let fetched = repos[0]
+        .find_reference(&format!(
+            "refs/remotes/origin/{}",
+            head.shorthand().unwrap()
+        ))
+        .unwrap();
+    assert_eq!(fetched.target(), head.target());
+
+    let stdout = fetch(&["-v"]);
+    assert!(stdout.contains("app: origin: up to date"), "{}", stdout);
+    assert!(stdout.contains("0 refs updated"), "{}", stdout);
+}
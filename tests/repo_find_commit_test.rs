@@ -0,0 +1,65 @@
+mod common;
+
+use git2::{Repository, Signature};
+use std::path::Path;
+use std::process::Command;
+
+const CHANGE_ID: &str = "I0123456789abcdef0123456789abcdef01234567";
+
+fn commit_with_message(repo: &Repository, message: &str) -> git2::Oid {
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    let signature = Signature::now("Tester", "tester@example.com").unwrap();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &head.tree().unwrap(),
+        &[&head],
+    )
+    .unwrap()
+}
+
+fn find_commit(root: &Path, query: &str) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-find-commit"))
+        .arg("-C")
+        .arg(root)
+        .arg(query)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn test_find_by_sha_and_change_id() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    let oid = commit_with_message(
+        &repos[1],
+        &format!("Fix the bug\n\nChange-Id: {}\n", CHANGE_ID),
+    );
+    let head = repos[1].head().unwrap().peel_to_commit().unwrap();
+    repos[1]
+        .tag_lightweight("v1.0", head.as_object(), false)
+        .unwrap();
+
+    let (success, stdout) = find_commit(dir.path(), &oid.to_string()[..10]);
+    assert!(success, "{}", stdout);
+    assert!(
+        stdout.contains(&format!("lib (lib): {:.7} Fix the bug\n", oid)),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("v1.0"), "{}", stdout);
+    assert!(!stdout.contains("app (app)"), "{}", stdout);
+
+    let (success, stdout) = find_commit(dir.path(), CHANGE_ID);
+    assert!(success, "{}", stdout);
+    assert!(stdout.contains("found in 1 of them"), "{}", stdout);
+
+    let (success, _) = find_commit(dir.path(), "0123456789abcdef");
+    assert!(!success);
+}
@@ -0,0 +1,358 @@
+mod common;
+
+use std::process::Command;
+
+#[test]
+fn test_results_as_json_lines() {
+    let (dir, _repos) = common::init_workspace(&["app", "lib"], "");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-forall"))
+        .arg("-C")
+        .arg(dir.path())
+        .args([
+            "--jsonl",
+            "--no-shell",
+            "--",
+            "git",
+            "rev-parse",
+            "--is-inside-work-tree",
+        ])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut records: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    records.sort_by_key(|r| r["path"].as_str().unwrap().to_string());
+    assert_eq!(records.len(), 2, "{}", stdout);
+    assert_eq!(records[0]["path"], "app");
+    assert_eq!(records[0]["exit_code"], 0);
+    assert_eq!(records[0]["stdout"], "true\n");
+}
+
+#[test]
+fn test_no_pager_if_stdout_is_no_terminal() {
+    let (dir, _repos) = common::init_workspace(&["app"], "");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-forall"))
+        .arg("-C")
+        .arg(dir.path())
+        .args(["--no-shell", "--", "git", "--version"])
+        .env("PAGER", "sed s/^/paged:/")
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("\ngit version"), "{}", stdout);
+    assert!(!stdout.contains("paged:"), "{}", stdout);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_retries() {
+    let (dir, _repos) = common::init_workspace(&["app"], "");
+
+    // fails the first three attempts
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-forall"))
+        .arg("-C")
+        .arg(dir.path())
+        .args(["--retries", "4294967295", "--retry-backoff", "0", "--"])
+        .arg("echo attempt >> attempts; test $(wc -l < attempts) -ge 4")
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    let attempts = std::fs::read_to_string(dir.path().join("app/attempts")).unwrap();
+    assert_eq!(attempts.lines().count(), 4);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_script_file() {
+    let (dir, _repos) = common::init_workspace(&["app", "lib"], "");
+    let script = dir.path().join("touch.sh");
+    std::fs::write(&script, "touch \"$1-$REPO_PROJECT\"\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-forall"))
+        .arg("-C")
+        .arg(dir.path())
+        .arg("--file")
+        .arg(&script)
+        .arg("touched")
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    assert!(dir.path().join("app/touched-app").exists());
+    assert!(dir.path().join("lib/touched-lib").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_timeout_kills_processes_started_by_the_command() {
+    let (dir, _repos) = common::init_workspace(&["app"], "");
+
+    // the trailing true keeps the shell from exec'ing the inner one
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-forall"))
+        .arg("-C")
+        .arg(dir.path())
+        .args(["--timeout", "1", "--"])
+        .arg("sh -c 'sleep 2; touch late'; true")
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("command timed out after 1s"), "{}", stderr);
+    assert!(
+        stderr.contains("1 executions failed, 0/1 succeeded"),
+        "{}",
+        stderr
+    );
+
+    std::thread::sleep(std::time::Duration::from_secs(3));
+    assert!(!dir.path().join("app/late").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_resume() {
+    let (dir, _repos) = common::init_workspace(&["app", "lib"], "");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-forall"))
+        .arg("-C")
+        .arg(dir.path())
+        .args(["--", "test $REPO_PATH != app"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{:?}", output);
+
+    // only the failed project is visited again
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-forall"))
+        .arg("-C")
+        .arg(dir.path())
+        .args(["--resume", "--", "touch resumed"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert!(dir.path().join("app/resumed").exists());
+    assert!(!dir.path().join("lib/resumed").exists());
+
+    // and nothing is left to resume after it succeeded
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-forall"))
+        .arg("-C")
+        .arg(dir.path())
+        .args(["--resume", "--", "true"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Nothing to resume"), "{}", stderr);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_log_dir() {
+    let (dir, _repos) = common::init_workspace(&["app", "vendor/lib"], "");
+    let log_dir = dir.path().join("logs");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-forall"))
+        .arg("-C")
+        .arg(dir.path())
+        .arg("--log-dir")
+        .arg(&log_dir)
+        .args(["--", "echo out-$REPO_PROJECT; echo err-$REPO_PROJECT >&2"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    let log = std::fs::read_to_string(log_dir.join("app.log")).unwrap();
+    assert_eq!(log, "out-app\nerr-app\n");
+    let log = std::fs::read_to_string(log_dir.join("vendor/lib.log")).unwrap();
+    assert_eq!(log, "out-vendor/lib\nerr-vendor/lib\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_stdin_is_delivered_to_each_command() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let (dir, _repos) = common::init_workspace(&["app", "lib"], "");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_repo-forall"))
+        .arg("-C")
+        .arg(dir.path())
+        .args(["--stdin", "--", "cat > received"])
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"line 1\nline 2\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    for path in ["app", "lib"] {
+        let received = std::fs::read_to_string(dir.path().join(path).join("received")).unwrap();
+        assert_eq!(received, "line 1\nline 2\n");
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_quiet_prints_table_of_exit_codes() {
+    let (dir, _repos) = common::init_workspace(&["app", "lib"], "");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-forall"))
+        .arg("-C")
+        .arg(dir.path())
+        .args([
+            "--quiet",
+            "--",
+            "echo noisy; test $REPO_PATH != app || { echo broken >&2; exit 3; }",
+        ])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{:?}", output);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("noisy"), "{}", stdout);
+    let lines: Vec<&str> = stdout.lines().map(str::trim_end).collect();
+    assert!(lines.contains(&"app     3  broken"), "{}", stdout);
+    assert!(lines.contains(&"lib     0"), "{}", stdout);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_results_as_json() {
+    let (dir, _repos) = common::init_workspace(&["app", "lib"], "");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-forall"))
+        .arg("-C")
+        .arg(dir.path())
+        .args([
+            "--json",
+            "--",
+            "echo out-$REPO_PATH; echo err-$REPO_PATH >&2; test $REPO_PATH != app",
+        ])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{:?}", output);
+
+    // nothing but the json array goes to stdout
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut records: Vec<serde_json::Value> = serde_json::from_str(&stdout).unwrap();
+    records.sort_by_key(|r| r["path"].as_str().unwrap().to_string());
+    assert_eq!(records.len(), 2, "{}", stdout);
+    assert_eq!(records[0]["path"], "app");
+    assert_eq!(records[0]["exit_code"], 1);
+    assert_eq!(records[0]["stdout"], "out-app\n");
+    assert_eq!(records[0]["stderr"], "err-app\n");
+    assert!(records[0]["duration_ms"].is_u64());
+    assert_eq!(records[1]["path"], "lib");
+    assert_eq!(records[1]["exit_code"], 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_dedupe_output() {
+    let (dir, _repos) = common::init_workspace(&["app", "lib", "tool"], "");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-forall"))
+        .arg("-C")
+        .arg(dir.path())
+        .args([
+            "--dedupe-output",
+            "--",
+            "if test $REPO_PATH = tool; then echo other; else echo same; fi",
+        ])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("\napp, lib (2):\nsame\n"), "{}", stdout);
+    assert!(stdout.contains("\ntool (1):\nother\n"), "{}", stdout);
+    assert_eq!(stdout.matches("same").count(), 1, "{}", stdout);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_exit_code_distribution() {
+    let (dir, _repos) = common::init_workspace(&["a", "b", "c", "d", "e"], "");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-forall"))
+        .arg("-C")
+        .arg(dir.path())
+        .args(["--", "test $REPO_PATH = e || exit 2"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{:?}", output);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("\nExit codes:\n     0:     1 x (e)\n     2:     4 x (a, b, c, ...)\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_expect_and_fail_on() {
+    let (dir, _repos) = common::init_workspace(&["app", "lib", "tool"], "");
+
+    // all commands exit with 0, their output decides
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-forall"))
+        .arg("-C")
+        .arg(dir.path())
+        .args(["--quiet", "--expect", "^ok", "--fail-on", "WARNING", "--"])
+        .arg("case $REPO_PATH in app) echo ok;; lib) echo nok;; tool) echo ok WARNING;; esac")
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{:?}", output);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().map(str::trim_end).collect();
+    assert!(lines.contains(&"app      0"), "{}", stdout);
+    assert!(
+        lines.contains(&"lib      0  output doesn't match '^ok'"),
+        "{}",
+        stdout
+    );
+    assert!(
+        lines.contains(&"tool     0  output matches 'WARNING'"),
+        "{}",
+        stdout
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("2 executions failed, 1/3 succeeded"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,29 @@
+mod common;
+
+use std::process::Command;
+
+#[test]
+fn test_repack_and_write_commit_graph() {
+    let (dir, _repos) = common::init_workspace(&["app", "lib"], "");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-gc"))
+        .arg("-C")
+        .arg(dir.path())
+        .args(["-t", "repack", "-t", "commit-graph", "-t", "prune", "-v"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("app: "), "{}", stdout);
+    assert!(stdout.contains("reclaimed"), "{}", stdout);
+
+    for project in ["app", "lib"].iter() {
+        let git_dir = dir.path().join(project).join(".git");
+        assert!(git_dir.join("objects/info/commit-graph").is_file());
+        let packs = std::fs::read_dir(git_dir.join("objects/pack")).unwrap();
+        assert!(packs
+            .map(|e| e.unwrap().path())
+            .any(|p| p.extension().is_some_and(|e| e == "pack")));
+    }
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use std::process::Command;
+
+#[test]
+fn test_grep_tracked_files() {
+    let (dir, repos) = common::init_workspace(&["app", "libs/core"], "");
+    common::commit_file(&repos[0], "main.c", "int main() {\n  return FOO;\n}\n");
+    common::commit_file(&repos[1], "core.h", "#define FOO 1\n");
+    common::commit_file(&repos[1], "test.c", "assert(foo);\n");
+    // not tracked, thus not searched
+    std::fs::write(dir.path().join("app/untracked.c"), "FOO\n").unwrap();
+
+    let grep = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_repo-grep"))
+            .arg("-C")
+            .arg(dir.path())
+            .args(args)
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(
+        grep(&["FOO"]),
+        "app/main.c\n2:  return FOO;\n\nlibs/core/core.h\n1:#define FOO 1\n\n2 matches in 2 files\n"
+    );
+    assert_eq!(
+        grep(&["-i", "foo", "--include", "*.c", "--exclude", "test.c"]),
+        "app/main.c\n2:  return FOO;\n\n1 matches in 1 files\n"
+    );
+    assert_eq!(grep(&["nowhere"]), "0 matches in 0 files\n");
+}
+
+#[test]
+fn test_grep_fails_if_a_project_cant_be_searched() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    common::commit_file(&repos[0], "main.c", "FOO\n");
+    std::fs::remove_dir_all(dir.path().join("lib/.git")).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-grep"))
+        .arg("-C")
+        .arg(dir.path())
+        .arg("FOO")
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{:?}", output);
+
+    // the matches of the other projects are printed anyway
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, "app/main.c\n1:FOO\n\n1 matches in 1 files\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("searching failed in 1 projects"),
+        "{}",
+        stderr
+    );
+}
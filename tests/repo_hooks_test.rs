@@ -0,0 +1,57 @@
+mod common;
+
+use std::fs;
+use std::process::{Command, Output};
+
+#[test]
+fn test_install_check_and_remove() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    let template = dir.path().join(".repo/manifests/hooks");
+    fs::create_dir_all(&template).unwrap();
+    fs::write(template.join("commit-msg"), "#!/bin/sh\nexit 0\n").unwrap();
+    fs::write(template.join("pre-push.sample"), "#!/bin/sh\n").unwrap();
+
+    let hooks = |args: &[&str]| -> Output {
+        Command::new(env!("CARGO_BIN_EXE_repo-hooks"))
+            .arg("-C")
+            .arg(dir.path())
+            .args(args)
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap()
+    };
+    let stdout = |output: &Output| String::from_utf8_lossy(&output.stdout).to_string();
+
+    let output = hooks(&["--check"]);
+    assert!(!output.status.success(), "{:?}", output);
+    assert!(stdout(&output).contains("app: commit-msg missing"));
+
+    let output = hooks(&[]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(stdout(&output).contains("Installed hooks in 2/2 projects"));
+    let installed = repos[0].path().join("hooks/commit-msg");
+    assert_eq!(
+        fs::read_to_string(&installed).unwrap(),
+        "#!/bin/sh\nexit 0\n"
+    );
+    assert!(!repos[0].path().join("hooks/pre-push").exists());
+
+    let output = hooks(&["--check"]);
+    assert!(output.status.success(), "{:?}", output);
+
+    // hooks modified locally are updated, but not removed
+    fs::write(repos[1].path().join("hooks/commit-msg"), "#!/bin/sh\n").unwrap();
+    let output = hooks(&["--check"]);
+    assert!(
+        stdout(&output).contains("lib: commit-msg outdated"),
+        "{:?}",
+        output
+    );
+    let output = hooks(&["--remove"]);
+    assert!(stdout(&output).contains("lib: commit-msg modified, kept"));
+    assert!(!installed.exists());
+    assert!(repos[1].path().join("hooks/commit-msg").exists());
+
+    let output = hooks(&["--hook", "pre-commit"]);
+    assert!(!output.status.success(), "{:?}", output);
+}
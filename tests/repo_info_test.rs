@@ -0,0 +1,42 @@
+mod common;
+
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_overview() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    let manifest = dir.path().join(".repo/manifest.xml");
+    let xml = fs::read_to_string(&manifest)
+        .unwrap()
+        .replace("path=\"lib\"/>", "path=\"lib\" groups=\"libs,pdk\"/>");
+    fs::write(&manifest, xml).unwrap();
+    common::init_sync_branch(dir.path(), &repos);
+    fs::write(dir.path().join("app/README"), "changed").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-info"))
+        .arg("-C")
+        .arg(dir.path())
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(
+        stdout.contains("manifest url:      https://example.com/manifest\n"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("manifest branch:   main\n"), "{}", stdout);
+    assert!(stdout.contains("sync branch:       m/main\n"), "{}", stdout);
+    assert!(stdout.contains("last sync:         20"), "{}", stdout);
+    assert!(stdout.contains("projects:          2\n"), "{}", stdout);
+    assert!(stdout.contains("  libs             1\n"), "{}", stdout);
+    assert!(
+        stdout.contains("dirty projects:    1 with uncommitted changes, 0 with local commits"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("disk usage:        "), "{}", stdout);
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_license_files_and_spdx_headers() {
+    let (dir, repos) = common::init_workspace(&["app", "lib", "tools"], "");
+    fs::write(
+        dir.path().join("app/LICENSE"),
+        "MIT License\n\nPermission is hereby granted,\nfree of charge, to any person",
+    )
+    .unwrap();
+    common::commit_file(
+        &repos[1],
+        "lib.c",
+        "/* SPDX-License-Identifier: GPL-2.0-only */\nint main() {}\n",
+    );
+    common::commit_file(&repos[1], "lib.rs", "// SPDX-License-Identifier: MIT\n");
+    fs::write(dir.path().join("lib/MODULE_LICENSE_GPL"), "").unwrap();
+
+    let scan = |format: &str| {
+        let output = Command::new(env!("CARGO_BIN_EXE_repo-license-scan"))
+            .arg("-C")
+            .arg(dir.path())
+            .args(["--format", format])
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let stdout = scan("table");
+    assert!(
+        stdout.contains("app\n  files: LICENSE (MIT)\n"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("  SPDX:  GPL-2.0-only (1), MIT (1)\n"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("tools: no license found"), "{}", stdout);
+    assert!(
+        stdout.contains("No license found in 1/3 projects"),
+        "{}",
+        stdout
+    );
+
+    let stdout = scan("csv");
+    assert!(
+        stdout.contains("\"lib\",\"MODULE_LICENSE_GPL (GPL)\",\"GPL-2.0-only (1), MIT (1)\"\n"),
+        "{}",
+        stdout
+    );
+
+    let json: serde_json::Value = serde_json::from_str(&scan("json")).unwrap();
+    assert_eq!(json[1]["spdx"]["MIT"], 1);
+    assert_eq!(json[0]["license_files"][0]["license"], "MIT");
+
+    // one report per line, in the order the projects are done
+    let stdout = scan("jsonl");
+    let reports: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(reports.len(), 3);
+    let lib = reports.iter().find(|r| r["path"] == "lib").unwrap();
+    assert_eq!(lib["spdx"]["GPL-2.0-only"], 1);
+}
@@ -0,0 +1,45 @@
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn lint(test_data: &str, args: &[&str]) -> Output {
+    let cwd = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data")
+        .join(test_data);
+    Command::new(env!("CARGO_BIN_EXE_repo-manifest-lint"))
+        .arg("-C")
+        .arg(cwd)
+        .args(args)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_clean_manifest() {
+    let output = lint("repo_project_selector", &[]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Found 0 problems"));
+}
+
+#[test]
+fn test_manifest_problems() {
+    let output = lint("repo_manifest_lint", &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(!output.status.success());
+    assert!(stdout.contains("kettle: path is used by multiple projects: kettle, teapot"));
+    assert!(stdout.contains("kettle/lid: nested in project kettle"));
+    assert!(stdout.contains("toaster: unknown remote nowhere"));
+    assert!(stdout.contains("fridge: not part of any group"));
+    assert!(stdout.contains("cup: project is checked out at conflicting revisions: main, large"));
+    assert!(stdout.contains("Found 5 problems"));
+}
+
+#[test]
+fn test_dangling_include() {
+    let output = lint("repo_manifest_lint", &["-m", "dangling.xml"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(!output.status.success());
+    assert!(stdout.contains("included file missing.xml does not exist"));
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use git2::Repository;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// pushes HEAD of `repo` to branch main of the bare repo at `url`
+fn push(repo: &Repository, url: &Path) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(repo.workdir().unwrap())
+        .arg("push")
+        .arg(url)
+        .arg("HEAD:refs/heads/main")
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success());
+}
+
+fn run_mirror(root: &Path, mirrors: &Path) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-mirror"))
+        .arg("-C")
+        .arg(root)
+        .arg(mirrors)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_create_and_update_mirrors() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    common::init_sync_branch(dir.path(), &repos);
+
+    // the manifest's fetch url is relative to the manifest repo
+    let server = tempfile::tempdir().unwrap();
+    for (name, repo) in ["app", "lib"].iter().zip(&repos) {
+        Repository::init_bare(server.path().join(name)).unwrap();
+        push(repo, &server.path().join(name));
+    }
+    let manifest = dir.path().join(".repo/manifest.xml");
+    let xml = fs::read_to_string(&manifest).unwrap();
+    fs::write(&manifest, xml.replace("https://example.com", "..")).unwrap();
+    Repository::open(dir.path().join(".repo/manifests"))
+        .unwrap()
+        .remote_set_url(
+            "origin",
+            &server.path().join("manifest").display().to_string(),
+        )
+        .unwrap();
+
+    let mirrors = tempfile::tempdir().unwrap();
+    let stdout = run_mirror(dir.path(), mirrors.path());
+    assert!(
+        stdout.contains("app: created, sharing objects with the workspace"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("Mirrored 2/2 projects"), "{}", stdout);
+
+    let mirror = Repository::open_bare(mirrors.path().join("lib.git")).unwrap();
+    let head = repos[1].head().unwrap().target().unwrap();
+    assert_eq!(mirror.refname_to_id("refs/heads/main").unwrap(), head);
+    assert!(mirrors
+        .path()
+        .join("lib.git/objects/info/alternates")
+        .exists());
+
+    let head = common::commit_file(&repos[1], "CHANGES", "more");
+    push(&repos[1], &server.path().join("lib"));
+
+    let stdout = run_mirror(dir.path(), mirrors.path());
+    assert!(!stdout.contains("created"), "{}", stdout);
+    assert!(stdout.contains("Mirrored 2/2 projects"), "{}", stdout);
+    assert_eq!(mirror.refname_to_id("refs/heads/main").unwrap(), head);
+}
@@ -1,4 +1,8 @@
-use repo_utils::repo_project_selector::select_projects;
+use regex::Regex;
+use repo_utils::repo_project_selector::{
+    find_repo_folder, find_repo_manifests_folder, parse, parse_file, parse_manifest,
+    parse_repo_manifest, select_projects, FilterMode, ProjectSelector,
+};
 use std::env;
 use std::path::{Path, PathBuf};
 
@@ -41,6 +45,89 @@ fn test_select_projects_with_group_filter() {
     assert_select_projects(false, Some(vec!["chemical".to_string()]), None, "");
 }
 
+#[test]
+fn test_select_projects_with_excluded_groups() {
+    setup();
+
+    // pot is additionally in group kitchen (see local_manifests)
+    assert_select_projects(
+        false,
+        Some(vec!["mechanical,-kitchen".to_string()]),
+        None,
+        "pressureliefvalve",
+    );
+    assert_select_projects(
+        false,
+        Some(vec!["mechanical".to_string(), "-kitchen".to_string()]),
+        None,
+        "pressureliefvalve",
+    );
+    // the last matching entry wins
+    assert_select_projects(
+        false,
+        Some(vec!["-kitchen,mechanical".to_string()]),
+        None,
+        "pressureliefvalve,pot",
+    );
+    assert_select_projects(false, Some(vec!["-electrical".to_string()]), None, "");
+}
+
+#[test]
+fn test_select_projects_with_implicit_groups() {
+    setup();
+
+    // startbutton is marked as notdefault (see local_manifests)
+    assert_select_projects(
+        false,
+        Some(vec!["default".to_string()]),
+        None,
+        "coffeemaker,boiler,pressureliefvalve,pot",
+    );
+    assert_select_projects(
+        false,
+        Some(vec![]),
+        None,
+        "coffeemaker,boiler,pressureliefvalve,pot",
+    );
+    assert_select_projects(
+        false,
+        Some(vec!["all".to_string()]),
+        None,
+        "coffeemaker,boiler,pressureliefvalve,pot,startbutton",
+    );
+    assert_select_projects(
+        false,
+        Some(vec!["all,-notdefault".to_string()]),
+        None,
+        "coffeemaker,boiler,pressureliefvalve,pot",
+    );
+    assert_select_projects(
+        false,
+        Some(vec!["name:boiler,path:pot".to_string()]),
+        None,
+        "boiler,pot",
+    );
+}
+
+#[test]
+fn test_select_projects_with_include_groups() {
+    setup();
+
+    // libs.xml is included with groups="libs"
+    assert_select_projects(
+        false,
+        Some(vec!["libs".to_string()]),
+        None,
+        "boiler,pressureliefvalve,pot,startbutton",
+    );
+    assert_select_projects(
+        false,
+        Some(vec!["libs,-mechanical".to_string()]),
+        None,
+        "boiler,startbutton",
+    );
+}
+
 #[test]
 fn test_select_projects_with_manifest_filter() {
     setup();
@@ -80,17 +167,300 @@ fn test_select_projects_with_all_filters() {
     );
 }
 
+#[test]
+fn test_select_projects_with_path_filter() {
+    setup();
+
+    assert_select_projects_by_path(
+        false,
+        None,
+        None,
+        Some(vec!["p*".to_string(), "boiler".to_string()]),
+        "boiler,pressureliefvalve,pot",
+    );
+    assert_select_projects_by_path(
+        false,
+        Some(vec!["mechanical".to_string()]),
+        None,
+        Some(vec!["*valve".to_string()]),
+        "pressureliefvalve",
+    );
+    assert_select_projects_by_path(false, None, None, Some(vec!["*/*".to_string()]), "");
+    assert!(select_projects(false, None, None, Some(vec!["[".to_string()]), None).is_err());
+}
+
+#[test]
+fn test_select_projects_with_name_regex_filter() {
+    setup();
+
+    let select_by_name = |regex: &str| {
+        select_projects(false, None, None, None, Some(Regex::new(regex).unwrap()))
+            .unwrap()
+            .join(",")
+    };
+    assert_eq!(select_by_name("^(coffee|pot)"), "coffeemaker,pot");
+    assert_eq!(select_by_name("er$"), "coffeemaker,boiler");
+    assert_eq!(select_by_name("^chemical$"), "");
+}
+
+#[test]
+fn test_project_selector() {
+    setup();
+
+    let selection = ProjectSelector::new()
+        .groups(vec!["mechanical".to_string(), "toplevel".to_string()])
+        .path_globs(vec!["p*".to_string(), "coffee*".to_string()])
+        .select()
+        .unwrap();
+    assert_eq!(selection.join(","), "coffeemaker,pressureliefvalve,pot");
+
+    // toaster is defined in the local manifests but wasn't synced
+    let selection = ProjectSelector::new()
+        .include_missing(true)
+        .include_manifest_repo(true)
+        .groups(vec!["kitchen".to_string()])
+        .select()
+        .unwrap();
+    assert_eq!(selection.join(","), "pot,toaster,.repo/manifests");
+}
+
+#[test]
+fn test_project_selector_union() {
+    setup();
+
+    let selector = ProjectSelector::new()
+        .manifest_files(vec![PathBuf::from("../manifest.xml")])
+        .groups(vec!["toplevel".to_string()])
+        .path_globs(vec!["boiler".to_string()]);
+
+    assert_eq!(selector.select().unwrap().join(","), "");
+    assert_eq!(
+        selector
+            .clone()
+            .filter_mode(FilterMode::Union)
+            .select()
+            .unwrap()
+            .join(","),
+        "coffeemaker,boiler,pressureliefvalve,pot,startbutton"
+    );
+
+    let union = ProjectSelector::new()
+        .filter_mode(FilterMode::Union)
+        .manifest_files(vec![PathBuf::from("overlay.xml")])
+        .groups(vec!["toplevel".to_string()])
+        .select()
+        .unwrap();
+    assert_eq!(
+        union.join(","),
+        "coffeemaker,boiler,pressureliefvalve,startbutton"
+    );
+}
+
+#[test]
+fn test_manifest_defaults() {
+    setup();
+
+    let manifest = parse_manifest(&find_repo_folder().unwrap().join("manifest.xml")).unwrap();
+
+    let coffeemaker = manifest.find_project("coffeemaker").unwrap();
+    assert_eq!(manifest.remote_of(coffeemaker), Some("origin"));
+    assert_eq!(manifest.revision_of(coffeemaker), Some("main"));
+
+    let boiler = manifest.find_project("boiler").unwrap();
+    assert_eq!(manifest.remote_of(boiler), Some("vendor"));
+    assert_eq!(manifest.revision_of(boiler), Some("stable"));
+}
+
+#[test]
+fn test_manifest_remotes() {
+    setup();
+
+    let manifest = parse_manifest(&find_repo_folder().unwrap().join("manifest.xml")).unwrap();
+    assert_eq!(manifest.remotes.len(), 2);
+    assert_eq!(
+        manifest.find_remote("vendor").unwrap().fetch,
+        "https://example.com/vendor"
+    );
+
+    let pot = manifest.find_project("pot").unwrap();
+    assert_eq!(manifest.remote_of(pot), Some("vendor"));
+    assert_eq!(manifest.revision_of(pot), Some("vendor-main"));
+    assert_eq!(manifest.upstream_of(pot), Some("pot-upstream"));
+    assert_eq!(manifest.dest_branch_of(pot), Some("develop"));
+
+    let coffeemaker = manifest.find_project("coffeemaker").unwrap();
+    assert_eq!(manifest.upstream_of(coffeemaker), None);
+}
+
+#[test]
+fn test_manifest_remove_project() {
+    setup();
+
+    let manifest = parse(&find_repo_manifests_folder().unwrap().join("overlay.xml")).unwrap();
+    let paths: Vec<_> = manifest.projects.iter().map(|p| p.path.as_str()).collect();
+
+    assert_eq!(
+        paths.join(","),
+        "kettle,boiler,pressureliefvalve,startbutton"
+    );
+}
+
+#[test]
+fn test_manifest_extend_project() {
+    setup();
+
+    let manifest = parse(&find_repo_manifests_folder().unwrap().join("extended.xml")).unwrap();
+
+    let boiler = manifest.find_project("boiler").unwrap();
+    assert_eq!(boiler.group_names(), vec!["electrical", "heating"]);
+    assert_eq!(boiler.revision.as_deref(), Some("v2"));
+    assert_eq!(boiler.remote.as_deref(), Some("origin"));
+
+    // path doesn't match, so the extension doesn't apply
+    let pot = manifest.find_project("pot").unwrap();
+    assert_eq!(pot.revision, None);
+
+    assert!(!manifest.contains_project("startbutton"));
+    let startbutton = manifest.find_project("controls/startbutton").unwrap();
+    assert_eq!(startbutton.dest_branch.as_deref(), Some("release"));
+}
+
+#[test]
+fn test_local_manifests() {
+    setup();
+
+    let manifest = parse_repo_manifest().unwrap();
+    assert!(manifest.contains_project("toaster"));
+
+    let pot = manifest.find_project("pot").unwrap();
+    assert_eq!(pot.group_names(), vec!["mechanical", "libs", "kitchen"]);
+    assert_eq!(manifest.revision_of(pot), Some("induction"));
+
+    // toaster is not synced (missing in project.list)
+    assert_select_projects(false, Some(vec!["kitchen".to_string()]), None, "pot");
+}
+
+#[test]
+fn test_write_xml() {
+    setup();
+
+    let mut manifest = parse_manifest(&find_repo_folder().unwrap().join("manifest.xml")).unwrap();
+    manifest.projects[0].upstream = Some("<\"fancy\" & odd>".to_string());
+
+    let mut xml = Vec::new();
+    manifest.write_xml(&mut xml).unwrap();
+    let file = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(file.path(), &xml).unwrap();
+    let reparsed = parse_file(file.path()).unwrap();
+
+    assert_eq!(reparsed.projects.len(), manifest.projects.len());
+    assert_eq!(reparsed.remotes.len(), 2);
+    assert_eq!(
+        reparsed.projects[0].upstream.as_deref(),
+        Some("<\"fancy\" & odd>")
+    );
+    let boiler = reparsed.find_project("boiler").unwrap();
+    assert_eq!(reparsed.revision_of(boiler), Some("stable"));
+    assert_eq!(boiler.group_names(), vec!["electrical", "libs"]);
+    assert_eq!(reparsed.dest_branch_of(boiler), Some("develop"));
+    let coffeemaker = reparsed.find_project("coffeemaker").unwrap();
+    assert_eq!(coffeemaker.annotations[1].keep.as_deref(), Some("false"));
+    let startbutton = reparsed.find_project("startbutton").unwrap();
+    assert_eq!(startbutton.copyfiles[0].dest, "docs/wiring.txt");
+}
+
+#[test]
+fn test_project_annotations() {
+    setup();
+
+    let manifest = parse_manifest(&find_repo_folder().unwrap().join("manifest.xml")).unwrap();
+    let coffeemaker = manifest.find_project("coffeemaker").unwrap();
+    let annotations = coffeemaker.annotation_map();
+
+    assert_eq!(annotations.len(), 2);
+    assert_eq!(annotations["BUILD_TARGET"], "espresso");
+    assert_eq!(annotations["OWNER"], "kitchen-team");
+    assert!(manifest
+        .find_project("boiler")
+        .unwrap()
+        .annotations
+        .is_empty());
+}
+
+#[test]
+fn test_sort_by_manifest_order() {
+    setup();
+
+    let manifest = parse_manifest(&find_repo_folder().unwrap().join("manifest.xml")).unwrap();
+    let paths = ["pot", "unknown", "boiler", "coffeemaker"];
+
+    assert_eq!(
+        manifest
+            .sort_by_manifest_order(paths.iter().map(|p| p.to_string()).collect())
+            .join(","),
+        "coffeemaker,boiler,pot,unknown"
+    );
+}
+
+#[test]
+fn test_select_infos() {
+    setup();
+
+    let infos = ProjectSelector::new()
+        .include_manifest_repo(true)
+        .select_infos()
+        .unwrap();
+    assert_eq!(infos.len(), 6);
+
+    let coffeemaker = &infos[0];
+    assert_eq!(coffeemaker.name, "coffeemaker");
+    assert_eq!(coffeemaker.groups, vec!["toplevel"]);
+    assert_eq!(coffeemaker.revision.as_deref(), Some("main"));
+    assert_eq!(coffeemaker.remote.as_deref(), Some("origin"));
+    assert_eq!(coffeemaker.annotations["OWNER"], "kitchen-team");
+    assert!(coffeemaker.absolute_path.ends_with("coffeemaker"));
+    assert!(coffeemaker.absolute_path.is_absolute());
+
+    let boiler = &infos[1];
+    assert_eq!(boiler.revision.as_deref(), Some("stable"));
+    assert_eq!(boiler.remote.as_deref(), Some("vendor"));
+
+    // not defined in the manifest, named after its path
+    let manifests = &infos[5];
+    assert_eq!(manifests.path, ".repo/manifests");
+    assert_eq!(manifests.name, ".repo/manifests");
+    assert!(manifests.groups.is_empty());
+}
+
 fn assert_select_projects(
     include_manifest_repo: bool,
     filter_by_groups: Option<Vec<String>>,
     filter_by_manifest_files: Option<Vec<PathBuf>>,
     expected_seclection: &str,
+) {
+    assert_select_projects_by_path(
+        include_manifest_repo,
+        filter_by_groups,
+        filter_by_manifest_files,
+        None,
+        expected_seclection,
+    );
+}
+
+fn assert_select_projects_by_path(
+    include_manifest_repo: bool,
+    filter_by_groups: Option<Vec<String>>,
+    filter_by_manifest_files: Option<Vec<PathBuf>>,
+    filter_by_path_globs: Option<Vec<String>>,
+    expected_seclection: &str,
 ) {
     assert_eq!(
         select_projects(
             include_manifest_repo,
             filter_by_groups,
             filter_by_manifest_files,
+            filter_by_path_globs,
+            None,
         )
         .unwrap()
         .join(","),
@@ -0,0 +1,47 @@
+mod common;
+
+use git2::BranchType;
+use std::process::Command;
+
+#[test]
+fn test_dry_run_and_prune() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    common::init_sync_branch(dir.path(), &repos);
+    let base = repos[1].head().unwrap().peel_to_commit().unwrap();
+    repos[1].branch("merged", &base, false).unwrap();
+    let local = repos[1]
+        .find_commit(common::commit_file(&repos[1], "lib.rs", "fn main() {}"))
+        .unwrap();
+    repos[1].branch("topic", &local, false).unwrap();
+
+    let prune = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_repo-prune"))
+            .arg("-C")
+            .arg(dir.path())
+            .args(args)
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let stdout = prune(&["--dry-run"]);
+    assert!(
+        stdout.contains("lib\n  merged (merged into m/main)\n"),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("topic"), "{}", stdout);
+    assert!(
+        stdout.contains("1 branches to delete in 1/2 projects"),
+        "{}",
+        stdout
+    );
+    assert!(repos[1].find_branch("merged", BranchType::Local).is_ok());
+
+    let stdout = prune(&["--yes"]);
+    assert!(stdout.contains("Deleted branch merged"), "{}", stdout);
+    assert!(repos[1].find_branch("merged", BranchType::Local).is_err());
+    assert!(repos[1].find_branch("topic", BranchType::Local).is_ok());
+}
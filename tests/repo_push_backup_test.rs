@@ -0,0 +1,57 @@
+mod common;
+
+use git2::{Repository, Signature};
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_backup_branches_and_stashes() {
+    let (dir, mut repos) = common::init_workspace(&["app", "lib"], "");
+    let head = {
+        let commit = repos[1].head().unwrap().peel_to_commit().unwrap();
+        repos[1].branch("topic", &commit, false).unwrap();
+        commit.id()
+    };
+    fs::write(dir.path().join("lib/README"), "work in progress").unwrap();
+    let signature = Signature::now("Tester", "tester@example.com").unwrap();
+    let stash = repos[1].stash_save(&signature, "wip", None).unwrap();
+
+    let backup_dir = tempfile::tempdir().unwrap();
+    let template = format!("{}/{{path}}.git", backup_dir.path().display());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-push-backup"))
+        .arg("-C")
+        .arg(dir.path())
+        .args(["--url", &template, "--stashes"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("lib: pushed 2 branches and 1 stashes"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("Backed up 2/2 projects"), "{}", stdout);
+
+    let backup = Repository::open_bare(backup_dir.path().join("lib.git")).unwrap();
+    assert_eq!(backup.refname_to_id("refs/heads/topic").unwrap(), head);
+    assert_eq!(backup.refname_to_id("refs/stashes/0").unwrap(), stash);
+}
+
+#[test]
+fn test_url_is_required() {
+    let (dir, _repos) = common::init_workspace(&["app"], "");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-push-backup"))
+        .arg("-C")
+        .arg(dir.path())
+        .env("XDG_CONFIG_HOME", dir.path())
+        .env("XDG_CONFIG_DIRS", dir.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("no backup url given"), "{}", stderr);
+}
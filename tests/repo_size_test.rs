@@ -0,0 +1,41 @@
+mod common;
+
+use repo_utils::disk_usage::human_readable;
+use std::process::Command;
+
+#[test]
+fn test_usage_and_largest_blobs() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    common::commit_file(&repos[1], "big.bin", &"x".repeat(64 * 1024));
+
+    let size = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_repo-size"))
+            .arg("-C")
+            .arg(dir.path())
+            .args(args)
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let stdout = size(&[]);
+    let rows: Vec<_> = stdout
+        .lines()
+        .skip_while(|l| !l.contains("worktree"))
+        .collect();
+    assert!(rows[1].ends_with("lib"), "{}", stdout);
+    assert!(rows[1].starts_with("  64.0 KiB"), "{}", stdout);
+    assert!(rows[3].ends_with("total of 2 projects"), "{}", stdout);
+
+    let stdout = size(&["--top", "1"]);
+    assert!(stdout.trim_end().ends_with("lib/big.bin"), "{}", stdout);
+}
+
+#[test]
+fn test_human_readable() {
+    assert_eq!(human_readable(512), "512 B");
+    assert_eq!(human_readable(1536), "1.5 KiB");
+    assert_eq!(human_readable(3 * 1024 * 1024 * 1024), "3.0 GiB");
+}
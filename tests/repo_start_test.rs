@@ -0,0 +1,52 @@
+mod common;
+
+use std::process::Command;
+
+#[test]
+fn test_start_in_touched_and_all_projects() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    common::init_sync_branch(dir.path(), &repos);
+    let local = common::commit_file(&repos[1], "lib.rs", "fn main() {}");
+
+    let start = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_repo-start"))
+            .arg("-C")
+            .arg(dir.path())
+            .args(args)
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let stdout = start(&["--touched", "--head", "topic"]);
+    assert!(
+        stdout.contains("lib: Switched to branch 'topic'"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("Started branch topic in 1/2 projects"),
+        "{}",
+        stdout
+    );
+    let head = repos[1].head().unwrap();
+    assert_eq!(head.shorthand(), Some("topic"));
+    assert_eq!(head.target(), Some(local));
+    let config = repos[1].config().unwrap().snapshot().unwrap();
+    assert_eq!(config.get_str("branch.topic.remote").unwrap(), "origin");
+    assert_eq!(
+        config.get_str("branch.topic.merge").unwrap(),
+        "refs/heads/main"
+    );
+
+    let stdout = start(&["topic"]);
+    assert!(
+        stdout.contains("Started branch topic in 1/2 projects"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("Already existed in: lib"), "{}", stdout);
+    assert_eq!(repos[0].head().unwrap().shorthand(), Some("topic"));
+}
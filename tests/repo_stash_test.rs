@@ -0,0 +1,52 @@
+mod common;
+
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_save_list_and_pop() {
+    let (dir, _repos) = common::init_workspace(&["app", "lib"], "");
+    fs::write(dir.path().join("app/README"), "changed").unwrap();
+
+    let stash = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_repo-stash"))
+            .arg("-C")
+            .arg(dir.path())
+            .args(args)
+            .env("NO_COLOR", "1")
+            .env("GIT_AUTHOR_NAME", "Tester")
+            .env("GIT_AUTHOR_EMAIL", "tester@example.com")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let stdout = stash(&["save", "-m", "wip"]);
+    assert!(
+        stdout.contains("Stashed changes in 1/2 projects"),
+        "{}",
+        stdout
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("app/README")).unwrap(),
+        "app"
+    );
+
+    let stdout = stash(&["list"]);
+    assert!(stdout.contains("app\n  stash@{0}: "), "{}", stdout);
+    assert!(stdout.contains("wip"), "{}", stdout);
+    assert!(stdout.contains("1/2 projects have stashes"), "{}", stdout);
+
+    let stdout = stash(&["pop"]);
+    assert!(
+        stdout.contains("Popped stash in 1/2 projects"),
+        "{}",
+        stdout
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("app/README")).unwrap(),
+        "changed"
+    );
+    assert!(stash(&["list"]).contains("0/2 projects have stashes"));
+}
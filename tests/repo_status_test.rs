@@ -0,0 +1,261 @@
+mod common;
+
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::process::Command;
+use std::thread;
+
+/// accepts one http request, answers it with 200 and returns its body
+fn serve_one_request(listener: TcpListener) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line.trim().is_empty() {
+                break;
+            }
+            let lower = line.to_lowercase();
+            if let Some(length) = lower.strip_prefix("content-length:") {
+                content_length = length.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+        String::from_utf8(body).unwrap()
+    })
+}
+
+#[test]
+fn test_status_as_json_lines() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    common::init_sync_branch(dir.path(), &repos);
+    fs::write(dir.path().join("lib/README"), "changed").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-status"))
+        .arg("-C")
+        .arg(dir.path())
+        .arg("--jsonl")
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut statuses: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    statuses.sort_by_key(|s| s["path"].as_str().unwrap().to_string());
+    assert_eq!(statuses.len(), 2, "{}", stdout);
+    assert_eq!(statuses[0]["uncommitted_changes"], false);
+    assert_eq!(statuses[1]["path"], "lib");
+    assert_eq!(statuses[1]["uncommitted_changes"], true);
+    assert_eq!(statuses[1]["local_commits"], 0);
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("1/2 git repos dirty"), "{}", stderr);
+}
+
+#[test]
+fn test_notify_webhook_about_dirty_projects() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    common::init_sync_branch(dir.path(), &repos);
+    common::commit_file(&repos[0], "CHANGES", "local work");
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let request = serve_one_request(listener);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-status"))
+        .arg("-C")
+        .arg(dir.path())
+        .args(["--notify", &url])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    let body: serde_json::Value = serde_json::from_str(&request.join().unwrap()).unwrap();
+    assert_eq!(body["total"], 2);
+    assert_eq!(body["dirty"][0]["path"], "app");
+    assert_eq!(body["dirty"][0]["local_commits"], 1);
+    let text = body["text"].as_str().unwrap();
+    assert!(text.starts_with("1/2 projects of "), "{}", text);
+    assert!(
+        text.ends_with("deviate from the last repo sync: app"),
+        "{}",
+        text
+    );
+}
+
+#[test]
+fn test_status_snapshots_in_sqlite() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    common::init_sync_branch(dir.path(), &repos);
+    let database = dir.path().join("status.db");
+
+    let run = || {
+        let output = Command::new(env!("CARGO_BIN_EXE_repo-status"))
+            .arg("-C")
+            .arg(dir.path())
+            .arg("--sqlite")
+            .arg(&database)
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+    };
+    run();
+    common::commit_file(&repos[1], "CHANGES", "local work");
+    run();
+
+    let db = rusqlite::Connection::open(&database).unwrap();
+    let count = |sql: &str| -> i64 { db.query_row(sql, [], |row| row.get(0)).unwrap() };
+    assert_eq!(count("SELECT count(*) FROM runs"), 2);
+    assert_eq!(count("SELECT count(*) FROM projects"), 2);
+    assert_eq!(count("SELECT count(*) FROM statuses"), 4);
+    let local_commits_per_run: Vec<i64> = db
+        .prepare(
+            "SELECT s.local_commits FROM statuses s JOIN projects p ON p.id = s.project_id
+             WHERE p.path = 'lib' ORDER BY s.run_id",
+        )
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+    assert_eq!(local_commits_per_run, vec![0, 1]);
+    let (branch, ahead, behind): (Option<String>, i64, i64) = db
+        .query_row(
+            "SELECT s.branch, s.ahead, s.behind FROM statuses s
+             JOIN projects p ON p.id = s.project_id
+             WHERE p.path = 'lib' ORDER BY s.run_id DESC",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .unwrap();
+    assert!(branch.is_some());
+    assert_eq!((ahead, behind), (1, 0));
+}
+
+#[test]
+fn test_metrics_for_textfile_collector() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    common::init_sync_branch(dir.path(), &repos);
+    fs::write(dir.path().join("app/README"), "changed").unwrap();
+    let metrics = dir.path().join("repo.prom");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-status"))
+        .arg("-C")
+        .arg(dir.path())
+        .arg("--metrics")
+        .arg(&metrics)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    let metrics = fs::read_to_string(metrics).unwrap();
+    let value = |name: &str| -> f64 {
+        let line = metrics
+            .lines()
+            .find(|l| l.starts_with(&format!("{}{{workspace=", name)))
+            .unwrap();
+        line.rsplit(' ').next().unwrap().parse().unwrap()
+    };
+    assert!(
+        metrics.contains("# TYPE repo_dirty_total gauge\n"),
+        "{}",
+        metrics
+    );
+    assert_eq!(value("repo_projects_total"), 2.0);
+    assert_eq!(value("repo_dirty_total"), 1.0);
+    assert_eq!(value("repo_local_commits_total"), 0.0);
+    assert!(value("scan_duration_seconds") >= 0.0);
+}
+
+#[test]
+fn test_status_as_csv() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    common::init_sync_branch(dir.path(), &repos);
+    let head = repos[0].head().unwrap().target().unwrap();
+    repos[0].set_head_detached(head).unwrap();
+    let head = repos[1].head().unwrap().peel_to_commit().unwrap();
+    repos[1].branch("topic", &head, false).unwrap();
+    repos[1].set_head("refs/heads/topic").unwrap();
+    common::commit_file(&repos[1], "lib.rs", "fn lib() {}");
+    let csv = dir.path().join("status.csv");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-status"))
+        .arg("-C")
+        .arg(dir.path())
+        .arg("--csv")
+        .arg(&csv)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    assert_eq!(
+        fs::read_to_string(csv).unwrap(),
+        "path,uncommitted_changes,local_commits,branch,ahead,behind\n\
+         \"app\",false,0,\"\",0,0\n\
+         \"lib\",false,1,\"topic\",1,0\n"
+    );
+}
+
+#[test]
+fn test_no_verbose_overrides_the_config() {
+    let (dir, repos) = common::init_workspace(&["app"], "");
+    common::init_sync_branch(dir.path(), &repos);
+
+    let status = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_repo-status"))
+            .arg("-C")
+            .arg(dir.path())
+            .args(args)
+            .env("NO_COLOR", "1")
+            .env("REPO_UTILS_VERBOSE", "true")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let stdout = status(&[]);
+    assert!(stdout.contains("app: clean"), "{}", stdout);
+    let stdout = status(&["--no-verbose"]);
+    assert!(!stdout.contains("app: clean"), "{}", stdout);
+    let stdout = status(&["--no-verbose", "-v"]);
+    assert!(stdout.contains("app: clean"), "{}", stdout);
+}
+
+#[test]
+fn test_notify_without_curl() {
+    let (dir, repos) = common::init_workspace(&["app"], "");
+    common::init_sync_branch(dir.path(), &repos);
+    let empty = tempfile::tempdir().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-status"))
+        .arg("-C")
+        .arg(dir.path())
+        .args(["--notify", "http://127.0.0.1:9/hook"])
+        .env("NO_COLOR", "1")
+        .env("PATH", empty.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{:?}", output);
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("needs curl"), "{}", stderr);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("Selected"), "{}", stdout);
+}
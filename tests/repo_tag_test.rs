@@ -0,0 +1,43 @@
+mod common;
+
+use std::process::Command;
+
+#[test]
+fn test_create_verify_list_and_delete() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+
+    let tag = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_repo-tag"))
+            .arg("-C")
+            .arg(dir.path())
+            .args(args)
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap();
+        (
+            output.status.success(),
+            String::from_utf8(output.stdout).unwrap(),
+        )
+    };
+
+    let (success, stdout) = tag(&["create", "v1.0", "-m", "release 1.0"]);
+    assert!(success, "{}", stdout);
+    assert!(stdout.contains("Created tag in 2/2 projects"), "{}", stdout);
+    let tag_object = repos[0].revparse_single("v1.0").unwrap();
+    assert_eq!(tag_object.as_tag().unwrap().message(), Some("release 1.0"));
+
+    let (success, stdout) = tag(&["verify", "v1.0"]);
+    assert!(success, "{}", stdout);
+
+    let (success, stdout) = tag(&["list", "v1.*"]);
+    assert!(success, "{}", stdout);
+    assert!(stdout.contains("app\n  v1.0\nlib\n  v1.0\n"), "{}", stdout);
+
+    let (success, stdout) = tag(&["--path", "lib", "delete", "v1.0"]);
+    assert!(success, "{}", stdout);
+    assert!(stdout.contains("Deleted tag in 1/1 projects"), "{}", stdout);
+
+    let (success, stdout) = tag(&["verify", "v1.0"]);
+    assert!(!success);
+    assert!(stdout.contains("lib: tag v1.0 is missing"), "{}", stdout);
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use git2::Repository;
+use std::process::Command;
+
+#[test]
+fn test_upload_local_commits() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    common::init_sync_branch(dir.path(), &repos);
+    let oid = common::commit_file(&repos[1], "lib.rs", "fn main() {}");
+
+    // stands in for Gerrit, which would turn the push into a change
+    let server = dir.path().join("server.git");
+    let bare = Repository::init_bare(&server).unwrap();
+    repos[1].remote("origin", server.to_str().unwrap()).unwrap();
+
+    let upload = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_repo-upload"))
+            .arg("-C")
+            .arg(dir.path())
+            .args(args)
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let stdout = upload(&["--dry-run"]);
+    assert!(
+        stdout.contains("Uploaded 1/2 projects (dry-run)"),
+        "{}",
+        stdout
+    );
+    assert!(bare.references().unwrap().next().is_none());
+
+    let stdout = upload(&["--topic", "feature", "-r", "alice", "--wip"]);
+    assert!(
+        stdout.contains("lib: 1 commits uploaded to refs/for/main%topic=feature,r=alice,wip"),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("app:"), "{}", stdout);
+    let reference = bare
+        .find_reference("refs/for/main%topic=feature,r=alice,wip")
+        .unwrap();
+    assert_eq!(reference.target(), Some(oid));
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use std::fs;
+use std::process::Command;
+
+#[cfg(unix)]
+#[test]
+fn test_verify_links() {
+    let (workspace, _repos) = common::init_workspace(&["build", "docs"], "");
+    let root = workspace.path();
+    fs::write(
+        root.join(".repo/manifest.xml"),
+        r#"<manifest>
+  <remote name="origin" fetch="https://example.com"/>
+  <default remote="origin" revision="main"/>
+  <project name="build" path="build">
+    <copyfile src="README" dest="Makefile"/>
+    <copyfile src="README" dest="Makefile.stale"/>
+    <linkfile src="README" dest="build.md"/>
+  </project>
+  <project name="docs" path="docs">
+    <copyfile src="README" dest="docs.md"/>
+    <linkfile src="README" dest="docs-link.md"/>
+    <linkfile src="README" dest="docs-wrong.md"/>
+  </project>
+</manifest>
+"#,
+    )
+    .unwrap();
+
+    fs::copy(root.join("build/README"), root.join("Makefile")).unwrap();
+    fs::write(root.join("Makefile.stale"), "outdated").unwrap();
+    std::os::unix::fs::symlink("build/README", root.join("build.md")).unwrap();
+    std::os::unix::fs::symlink("build/README", root.join("docs-wrong.md")).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-verify-links"))
+        .arg("-C")
+        .arg(root)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(!output.status.success());
+    assert!(stdout.contains("build: copy Makefile.stale is stale"));
+    assert!(stdout.contains("docs: copy docs.md can't be read"));
+    assert!(stdout.contains("docs: link docs-link.md is missing"));
+    assert!(stdout.contains("docs: link docs-wrong.md points to"));
+    assert!(stdout.contains("Found 4 problems in 6 copyfile/linkfile elements"));
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn verify(root: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_repo-verify-signatures"))
+        .arg("-C")
+        .arg(root)
+        .args(args)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap()
+}
+
+fn git(cwd: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_unsigned_commits_are_reported() {
+    let (dir, repos) = common::init_workspace(&["app", "lib"], "");
+    common::init_sync_branch(dir.path(), &repos);
+    common::commit_file(&repos[1], "lib.rs", "fn main() {}");
+
+    let output = verify(dir.path(), &[]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("commit: unsigned"), "{}", stdout);
+    assert!(
+        stdout.contains("0/2 commits compliant, violations in 2/2 projects"),
+        "{}",
+        stdout
+    );
+
+    let output = verify(dir.path(), &["--since-sync", "--json"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 1);
+    assert_eq!(json[0]["path"], "lib");
+    assert_eq!(json[0]["status"], "unsigned");
+    assert_eq!(json[0]["compliant"], false);
+}
+
+#[test]
+fn test_ssh_signed_commits_are_checked_against_allowed_keys() {
+    let (dir, _repos) = common::init_workspace(&["app"], "");
+    let key = dir.path().join("key");
+    let status = Command::new("ssh-keygen")
+        .args(["-q", "-t", "ed25519", "-N", "", "-C", "tester", "-f"])
+        .arg(&key)
+        .status();
+    if !status.is_ok_and(|s| s.success()) {
+        eprintln!("ssh-keygen is not available, skipping");
+        return;
+    }
+    let public_key = fs::read_to_string(dir.path().join("key.pub")).unwrap();
+    fs::write(
+        dir.path().join("allowed_signers"),
+        format!("tester@example.com {}", public_key),
+    )
+    .unwrap();
+
+    let app = dir.path().join("app");
+    git(&app, &["config", "user.name", "Tester"]);
+    git(&app, &["config", "user.email", "tester@example.com"]);
+    git(&app, &["config", "gpg.format", "ssh"]);
+    git(&app, &["config", "user.signingkey", key.to_str().unwrap()]);
+    let allowed_signers = dir.path().join("allowed_signers");
+    git(
+        &app,
+        &[
+            "config",
+            "gpg.ssh.allowedSignersFile",
+            allowed_signers.to_str().unwrap(),
+        ],
+    );
+    git(
+        &app,
+        &["commit", "-q", "-S", "--allow-empty", "-m", "signed"],
+    );
+    let fingerprint = git(&app, &["log", "-1", "--format=%GF"]);
+
+    fs::write(dir.path().join("allowed_keys"), "# nobody\n").unwrap();
+    let output = verify(dir.path(), &["--allowed-keys", "allowed_keys"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("which is not allowed"), "{}", stdout);
+
+    // only the full fingerprint counts, not a tail of it
+    let tail = &fingerprint.trim()[10..];
+    fs::write(dir.path().join("allowed_keys"), tail).unwrap();
+    let output = verify(dir.path(), &["--allowed-keys", "allowed_keys"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("which is not allowed"), "{}", stdout);
+
+    fs::write(dir.path().join("allowed_keys"), &fingerprint).unwrap();
+    let output = verify(dir.path(), &["--allowed-keys", "allowed_keys"]);
+    assert!(output.status.success(), "{:?}", output);
+}
+
+#[test]
+fn test_short_key_ids_are_rejected() {
+    let (dir, _repos) = common::init_workspace(&["app"], "");
+    fs::write(
+        dir.path().join("allowed_keys"),
+        "# short key id\nDEADBEEF\n",
+    )
+    .unwrap();
+
+    let output = verify(dir.path(), &["--allowed-keys", "allowed_keys"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("allowed key DEADBEEF is too short"),
+        "{}",
+        stderr
+    );
+}
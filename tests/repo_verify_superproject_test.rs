@@ -0,0 +1,55 @@
+mod common;
+
+use git2::{Oid, Repository, Signature};
+use std::path::Path;
+use std::process::Command;
+
+/// creates the bare superproject repo the way repo keeps it,
+/// with a commit on main containing the given gitlinks
+fn init_superproject(root: &Path, gitlinks: &[(&str, Oid)]) {
+    let repo = Repository::init_bare(root.join(".repo/exp-superproject/superproject.git")).unwrap();
+    let mut builder = repo.treebuilder(None).unwrap();
+    for (path, id) in gitlinks {
+        builder.insert(path, *id, 0o160000).unwrap();
+    }
+    let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+    let signature = Signature::now("Tester", "tester@example.com").unwrap();
+    repo.commit(
+        Some("refs/heads/main"),
+        &signature,
+        &signature,
+        "superproject",
+        &tree,
+        &[],
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_verify_superproject() {
+    let (workspace, repos) = common::init_workspace(
+        &["kettle", "toaster", "fridge"],
+        "  <superproject name=\"platform/superproject\"/>\n",
+    );
+    let head_of = |repo: &Repository| repo.head().unwrap().target().unwrap();
+    let outdated = head_of(&repos[1]);
+    common::commit_file(&repos[1], "README", "changed");
+    init_superproject(
+        workspace.path(),
+        &[("kettle", head_of(&repos[0])), ("toaster", outdated)],
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_repo-verify-superproject"))
+        .arg("-C")
+        .arg(workspace.path())
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(!output.status.success());
+    assert!(!stdout.contains("kettle:"));
+    assert!(stdout.contains(&format!("superproject has {:.7}", outdated)));
+    assert!(stdout.contains("fridge: not part of the superproject"));
+    assert!(stdout.contains("2/3 projects diverge from superproject platform/superproject at main"));
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use repo_utils::repo_project_selector::ProjectSelector;
+use repo_utils::workspace::Workspace;
+use repo_utils::Error;
+use std::fs;
+
+#[test]
+fn test_discover_from_nested_folder() {
+    let (dir, _repos) = common::init_workspace(&["app", "libs/core"], "");
+    let root = dir.path().canonicalize().unwrap();
+
+    let workspace = Workspace::discover(&root.join("libs/core")).unwrap();
+
+    assert_eq!(workspace.root_folder(), root);
+    assert_eq!(workspace.manifests_folder(), root.join(".repo/manifests"));
+    assert_eq!(workspace.project_list().unwrap(), vec!["app", "libs/core"]);
+    assert_eq!(workspace.manifest().unwrap().projects.len(), 2);
+}
+
+#[test]
+fn test_select_in_workspace() {
+    let (dir, _repos) = common::init_workspace(&["app", "libs/core"], "");
+    let workspace = Workspace::discover(dir.path()).unwrap();
+
+    let projects = workspace
+        .select(&ProjectSelector::new().path_globs(vec!["libs/*".to_string()]))
+        .unwrap();
+
+    assert_eq!(projects.len(), 1);
+    assert_eq!(projects[0].name, "libs/core");
+    assert_eq!(projects[0].revision.as_deref(), Some("main"));
+    assert_eq!(
+        projects[0].absolute_path,
+        workspace.root_folder().join("libs/core")
+    );
+}
+
+#[test]
+fn test_discover_outside_of_workspace() {
+    let dir = tempfile::tempdir().unwrap();
+
+    assert!(matches!(
+        Workspace::discover(dir.path()),
+        Err(Error::NoWorkspace)
+    ));
+    assert!(matches!(
+        Workspace::discover(&dir.path().join("missing")),
+        Err(Error::Io { .. })
+    ));
+}
+
+#[test]
+fn test_errors_distinguish_failures() {
+    let (dir, _repos) = common::init_workspace(&["app"], "");
+    let workspace = Workspace::discover(dir.path()).unwrap();
+
+    fs::remove_file(dir.path().join(".repo/project.list")).unwrap();
+    assert!(matches!(workspace.project_list(), Err(Error::Manifest(_))));
+
+    fs::write(dir.path().join(".repo/manifest.xml"), "<manifest><oops").unwrap();
+    assert!(matches!(workspace.manifest(), Err(Error::Manifest(_))));
+
+    assert!(matches!(
+        workspace.sync_branch_name(),
+        Err(Error::Git { .. })
+    ));
+}
+
+#[test]
+fn test_manifest_includes_resolved_in_workspace() {
+    let (dir, _repos) = common::init_workspace(&["app"], "  <include name=\"libs.xml\"/>\n");
+    fs::write(
+        dir.path().join(".repo/manifests/libs.xml"),
+        "<manifest>\n  <project name=\"core\" path=\"libs/core\"/>\n</manifest>\n",
+    )
+    .unwrap();
+
+    // independent of the cwd, which is not part of the workspace
+    let workspace = Workspace::discover(dir.path()).unwrap();
+    let manifest = workspace.manifest().unwrap();
+
+    assert!(manifest.contains_project("libs/core"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_discover_from_project_linked_into_workspace() {
+    let (dir, _repos) = common::init_workspace(&["app"], "");
+    let other_disk = tempfile::tempdir().unwrap();
+    git2::Repository::init(other_disk.path().join("big")).unwrap();
+    std::os::unix::fs::symlink(other_disk.path().join("big"), dir.path().join("big")).unwrap();
+
+    let workspace = Workspace::discover(&dir.path().join("big")).unwrap();
+
+    assert_eq!(workspace.root_folder(), dir.path().canonicalize().unwrap());
+}
+
+#[test]
+fn test_local_manifest_replaces_project() {
+    let (dir, _repos) = common::init_workspace(&["app"], "");
+    let local_manifests = dir.path().join(".repo/local_manifests");
+    fs::create_dir(&local_manifests).unwrap();
+    fs::write(
+        local_manifests.join("replace.xml"),
+        "<manifest>\n  <remote name=\"vendor\" fetch=\"https://vendor.example.com\"/>\n  \
+         <remove-project name=\"app\"/>\n  \
+         <project name=\"app\" remote=\"vendor\" revision=\"stable\"/>\n\
+         </manifest>\n",
+    )
+    .unwrap();
+
+    let workspace = Workspace::discover(dir.path()).unwrap();
+    let manifest = workspace.manifest().unwrap();
+
+    assert_eq!(manifest.projects.len(), 1);
+    let app = manifest.find_project("app").unwrap();
+    assert_eq!(manifest.remote_of(app), Some("vendor"));
+    assert_eq!(manifest.revision_of(app), Some("stable"));
+}